@@ -0,0 +1,196 @@
+use anyhow::Result;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::info;
+
+use crate::error::VhqError;
+use crate::metrics::ChildProcesses;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Error message of work stopped through its cancellation token
+pub const CANCELLED: &str = "Task cancelled";
+
+/// How often a running child process checks its token
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn cancelled() -> anyhow::Error {
+    VhqError::Cancelled.into()
+}
+
+/// Fail early once the token has been cancelled
+pub fn check(cancel: &CancellationToken) -> Result<()> {
+    if cancel.is_cancelled() {
+        return Err(cancelled());
+    }
+    Ok(())
+}
+
+/// Like `Command::output`, but kills the child as soon as the token is cancelled.
+/// Every stdout line is also handed to `on_line` as soon as it is printed.
+/// The child is counted in `children` while it runs.
+pub fn command_output(
+    cmd: &mut Command,
+    cancel: &CancellationToken,
+    children: &ChildProcesses,
+    on_line: impl FnMut(&str) + Send + 'static,
+) -> std::io::Result<Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let _tracked = children.track(child.id());
+
+    // Drain pipes on their own threads so a chatty child never blocks on a full pipe
+    let stdout = drain_lines(child.stdout.take(), on_line);
+    let stderr = drain(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if cancel.is_cancelled() {
+            info!("Killing process {} after cancellation", child.id());
+            child.kill()?;
+            child.wait()?;
+            return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, CANCELLED));
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn drain_lines<R: Read + Send + 'static>(pipe: Option<R>, mut on_line: impl FnMut(&str) + Send + 'static) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(pipe) = pipe {
+            let mut reader = BufReader::new(pipe);
+            let mut line = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                on_line(String::from_utf8_lossy(&line).trim_end());
+                buffer.append(&mut line);
+            }
+        }
+        buffer
+    })
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Drive async I/O from blocking agent code. Cancelling drops the future,
+/// which aborts an HTTP request in flight.
+pub fn block_on<F: Future>(future: F, cancel: &CancellationToken) -> Result<F::Output> {
+    let run = async {
+        tokio::select! {
+            _ = cancel.cancelled() => Err(cancelled()),
+            output = future => Ok(output),
+        }
+    };
+
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => handle.block_on(run),
+        Err(_) => tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(run),
+    }
+}
+
+/// Output files an agent is still producing. They are removed when this is
+/// dropped (error or cancellation) unless `keep` was called.
+#[derive(Debug, Default)]
+pub struct PartialOutputs {
+    paths: Vec<PathBuf>,
+}
+
+impl PartialOutputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a file about to be written. Files that already exist are left alone.
+    pub fn track(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        if !path.exists() {
+            self.paths.push(path);
+        }
+    }
+
+    /// The work finished, keep all tracked files
+    pub fn keep(mut self) {
+        self.paths.clear();
+    }
+}
+
+impl Drop for PartialOutputs {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            if path.exists() && std::fs::remove_file(path).is_ok() {
+                info!("Removed partial output: {}", path.display());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_kills_child_process() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            trigger.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let result = command_output(Command::new("sleep").arg("30"), &cancel, &ChildProcesses::default(), |_| {});
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_partial_outputs_removed_unless_kept() {
+        let dir = std::env::temp_dir().join(format!("vhq_partial_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dropped = dir.join("dropped.txt");
+        let kept = dir.join("kept.txt");
+
+        let mut outputs = PartialOutputs::new();
+        outputs.track(&dropped);
+        std::fs::write(&dropped, "partial").unwrap();
+        drop(outputs);
+        assert!(!dropped.exists());
+
+        let mut outputs = PartialOutputs::new();
+        outputs.track(&kept);
+        std::fs::write(&kept, "done").unwrap();
+        outputs.keep();
+        assert!(kept.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
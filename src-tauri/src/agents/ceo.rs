@@ -1,12 +1,28 @@
 use anyhow::Result;
-use tracing::info;
+use tracing::{info, error, warn};
 use std::path::Path;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
 use crate::commands::AgentStatus;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::error::VhqError;
+use crate::health::{HealthChecker, HealthHistory, HealthStatus, HealthThresholds};
+use crate::metrics::{self, DiskUsage, SystemSampler};
+use super::cancellation::CancellationToken;
+use super::dispatcher::{TaskContext, TaskDispatcher};
+use super::graph::{self, TaskGraphRequest, TaskGraphStatus, TaskNodeStatus};
+use super::progress::ProgressHub;
+use super::retry::{BackoffStrategy, RetryPolicy};
 use serde::{Deserialize, Serialize};
 
+/// How often the health monitor probes the database, Ollama, whisper and output directories
+const HEALTH_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long a timed-out task may take to stop before its slot is freed anyway
+const CANCEL_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(10);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
@@ -20,15 +36,127 @@ pub struct Task {
     pub completed_at: Option<String>,
     pub result: Option<String>,
     pub error: Option<String>,
+    /// Number of attempts started so far
+    pub attempt: i32,
+    pub max_attempts: i32,
+    pub backoff: BackoffStrategy,
+    /// Earliest time a retried task may run again
+    pub next_eligible_at: Option<String>,
+    /// IDs of tasks that must complete before this one is released
+    pub depends_on: Vec<String>,
+    pub graph_id: Option<String>,
+    /// Name of this task inside its graph
+    pub node_key: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Task {
+    /// New pending task. `parameters.options.max_attempts` overrides the policy's attempt budget.
+    pub fn new(agent_name: &str, task_type: &str, parameters: serde_json::Value, retry_policy: &RetryPolicy) -> Self {
+        let max_attempts = parameters.get("options")
+            .and_then(|options| options.get("max_attempts"))
+            .and_then(|v| v.as_i64())
+            .map(|v| v.max(1) as i32)
+            .unwrap_or(retry_policy.max_attempts);
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_name: agent_name.to_string(),
+            task_type: task_type.to_string(),
+            parameters,
+            priority: 1,
+            status: TaskStatus::Pending,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            completed_at: None,
+            result: None,
+            error: None,
+            attempt: 0,
+            max_attempts,
+            backoff: retry_policy.backoff.clone(),
+            next_eligible_at: None,
+            depends_on: Vec::new(),
+            graph_id: None,
+            node_key: None,
+        }
+    }
+
+    /// Limit from `parameters.options.max_concurrent` and the tasks it is shared with:
+    /// those of the same graph, or outside a graph those of the same agent and task type
+    pub fn concurrency_group(&self) -> Option<(String, usize)> {
+        let max = self.parameters.get("options")
+            .and_then(|options| options.get("max_concurrent"))
+            .and_then(|v| v.as_u64())?;
+        let group = match &self.graph_id {
+            Some(graph_id) => format!("graph:{}", graph_id),
+            None => format!("{}:{}", self.agent_name, self.task_type),
+        };
+        Some((group, max.max(1) as usize))
+    }
+
+    /// Whether a pending task's backoff delay has elapsed
+    pub fn is_eligible(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        match &self.next_eligible_at {
+            Some(at) => chrono::DateTime::parse_from_rfc3339(at)
+                .map(|at| at <= now)
+                .unwrap_or(true),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TaskStatus {
     Pending,
     Running,
     Completed,
     Failed,
     Cancelled,
+    /// Ran out of retry attempts; kept aside until replayed
+    DeadLetter,
+}
+
+impl TaskStatus {
+    /// Whether the task will not run again without intervention
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, TaskStatus::Pending | TaskStatus::Running)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "Pending",
+            TaskStatus::Running => "Running",
+            TaskStatus::Completed => "Completed",
+            TaskStatus::Failed => "Failed",
+            TaskStatus::Cancelled => "Cancelled",
+            TaskStatus::DeadLetter => "DeadLetter",
+        }
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "Pending" => Ok(TaskStatus::Pending),
+            "Running" => Ok(TaskStatus::Running),
+            "Completed" => Ok(TaskStatus::Completed),
+            "Failed" => Ok(TaskStatus::Failed),
+            "Cancelled" => Ok(TaskStatus::Cancelled),
+            "DeadLetter" => Ok(TaskStatus::DeadLetter),
+            _ => Err(anyhow::anyhow!("Unknown task status: {}", value)),
+        }
+    }
+}
+
+/// What to do with tasks found in `Running` state when the app starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryPolicy {
+    /// Put interrupted tasks back in the queue; those out of attempts go to the dead-letter queue
+    Requeue,
+    /// Fail interrupted tasks so they are not retried automatically
+    MarkFailed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +169,44 @@ pub struct AgentInfo {
     pub last_activity: String,
     pub capabilities: Vec<String>,
     pub current_task: Option<String>,
+    pub running_tasks: Vec<String>,
+    pub max_concurrent_tasks: i32,
+    /// Per-agent timeout in seconds; falls back to the CEO default when unset
+    pub task_timeout: Option<i32>,
+}
+
+impl AgentInfo {
+    fn has_free_slot(&self) -> bool {
+        (self.running_tasks.len() as i32) < self.max_concurrent_tasks
+    }
+
+    fn assign(&mut self, task_id: &str) {
+        self.running_tasks.push(task_id.to_string());
+        self.current_task = Some(task_id.to_string());
+        self.refresh_status();
+    }
+
+    fn release(&mut self, task_id: &str) {
+        self.running_tasks.retain(|id| id != task_id);
+        self.current_task = self.running_tasks.last().cloned();
+        self.last_activity = chrono::Utc::now().to_rfc3339();
+        self.refresh_status();
+    }
+
+    fn refresh_status(&mut self) {
+        if self.status != "available" && self.status != "busy" {
+            return;
+        }
+        self.status = if self.has_free_slot() { "available" } else { "busy" }.to_string();
+    }
+}
+
+/// Scheduler-wide concurrency and timeout limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskLimits {
+    pub max_concurrent_tasks: i32,
+    /// Default task timeout in seconds
+    pub task_timeout: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,29 +219,49 @@ pub struct SystemMetrics {
     pub completed_tasks: i32,
     pub failed_tasks: i32,
     pub uptime: f64,
+    /// Filesystems holding the app data and agent output directories
+    pub disks: Vec<DiskUsage>,
 }
 
 #[derive(Debug, Clone)]
 pub struct CeoAgent {
+    pub db: Database,
     pub status: String,
     pub memory_usage: i64,
     pub cpu_usage: f64,
     pub last_activity: String,
-    pub task_queue: Arc<Mutex<Vec<Task>>>,
     pub agent_registry: Arc<Mutex<HashMap<String, AgentInfo>>>,
     pub system_metrics: Arc<Mutex<SystemMetrics>>,
-    pub max_concurrent_tasks: i32,
-    pub task_timeout: i32,
+    pub limits: Arc<Mutex<TaskLimits>>,
+    pub recovery_policy: RecoveryPolicy,
+    pub retry_policy: RetryPolicy,
+    pub dispatcher: TaskDispatcher,
+    /// Cancellation tokens of running tasks, by task ID
+    pub running_tasks: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// Running tasks per concurrency group, see `Task::concurrency_group`
+    running_groups: Arc<Mutex<HashMap<String, usize>>>,
+    /// Latest progress of running tasks, forwarded to the UI
+    pub progress: ProgressHub,
+    /// Recent health probe reports
+    pub health: HealthHistory,
+    pub health_thresholds: Arc<Mutex<HealthThresholds>>,
+    /// Stops the task processor and health monitor of the current run
+    workers: Arc<Mutex<Option<CancellationToken>>>,
 }
 
 impl CeoAgent {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(db: Database) -> Self {
+        Self::with_dispatcher(db, TaskDispatcher::standalone())
+    }
+
+    /// Create a CEO that executes tasks through the given agent instances
+    pub fn with_dispatcher(db: Database, dispatcher: TaskDispatcher) -> Self {
+        let agent = Self {
+            db,
             status: "stopped".to_string(),
             memory_usage: 0,
             cpu_usage: 0.0,
             last_activity: chrono::Utc::now().to_rfc3339(),
-            task_queue: Arc::new(Mutex::new(Vec::new())),
             agent_registry: Arc::new(Mutex::new(HashMap::new())),
             system_metrics: Arc::new(Mutex::new(SystemMetrics {
                 total_memory: 0,
@@ -86,26 +272,49 @@ impl CeoAgent {
                 completed_tasks: 0,
                 failed_tasks: 0,
                 uptime: 0.0,
+                disks: Vec::new(),
             })),
-            max_concurrent_tasks: 5,
-            task_timeout: 300, // 5 minutes
-        }
+            limits: Arc::new(Mutex::new(TaskLimits {
+                max_concurrent_tasks: 5,
+                task_timeout: 300, // 5 minutes
+            })),
+            recovery_policy: RecoveryPolicy::Requeue,
+            retry_policy: RetryPolicy::default(),
+            dispatcher,
+            running_tasks: Arc::new(Mutex::new(HashMap::new())),
+            running_groups: Arc::new(Mutex::new(HashMap::new())),
+            progress: ProgressHub::new(),
+            health: HealthHistory::default(),
+            health_thresholds: Arc::new(Mutex::new(HealthThresholds::default())),
+            workers: Arc::new(Mutex::new(None)),
+        };
+        
+        // Register agents up front so their limits can be tuned before start
+        agent.initialize_agent_registry();
+        agent
     }
 
     pub fn start(&mut self) -> Result<AgentStatus> {
         info!("Starting CEO LAG agent");
         
+        // Background workers run once, shared by all clones of this instance
+        let mut workers = self.workers.lock().unwrap();
+        if workers.is_some() {
+            return Ok(self.get_status());
+        }
+        
         self.status = "running".to_string();
         self.last_activity = chrono::Utc::now().to_rfc3339();
         
-        // Initialize agent registry
-        self.initialize_agent_registry()?;
+        let stop = CancellationToken::new();
         
         // Start task processor
-        self.start_task_processor()?;
+        self.start_task_processor(stop.clone())?;
         
         // Start health monitoring
-        self.start_health_monitoring()?;
+        self.start_health_monitoring(stop.clone())?;
+        
+        *workers = Some(stop);
         
         Ok(self.get_status())
     }
@@ -113,6 +322,11 @@ impl CeoAgent {
     pub fn stop(&mut self) -> Result<AgentStatus> {
         info!("Stopping CEO LAG agent");
         
+        // Running tasks finish on their own; no new ones are claimed
+        if let Some(stop) = self.workers.lock().unwrap().take() {
+            stop.cancel();
+        }
+        
         self.status = "stopped".to_string();
         self.last_activity = chrono::Utc::now().to_rfc3339();
         
@@ -129,19 +343,19 @@ impl CeoAgent {
         }
     }
 
-    pub fn process_file(&self, file_path: &str, options: serde_json::Value) -> Result<String> {
+    pub async fn process_file(&self, file_path: &str, options: serde_json::Value) -> Result<String> {
         info!("CEO LAG processing file: {}", file_path);
         
         // Validate file exists
         if !Path::new(file_path).exists() {
-            return Err(anyhow::anyhow!("File not found: {}", file_path));
+            return Err(VhqError::FileNotFound(file_path.to_string()).into());
         }
 
         // Create orchestration task
         let task = self.create_orchestration_task(file_path, options)?;
         
         // Add to task queue
-        self.add_task(task.clone())?;
+        self.add_task(task.clone()).await?;
         
         // Return task ID for tracking
         Ok(serde_json::to_string(&serde_json::json!({
@@ -151,7 +365,7 @@ impl CeoAgent {
         }))?)
     }
 
-    fn initialize_agent_registry(&self) -> Result<()> {
+    fn initialize_agent_registry(&self) {
         info!("Initializing agent registry");
         
         let mut registry = self.agent_registry.lock().unwrap();
@@ -166,6 +380,9 @@ impl CeoAgent {
             last_activity: chrono::Utc::now().to_rfc3339(),
             capabilities: vec!["transcription".to_string(), "translation".to_string()],
             current_task: None,
+            running_tasks: Vec::new(),
+            max_concurrent_tasks: 1, // whisper saturates the CPU on its own
+            task_timeout: Some(1800),
         });
         
         registry.insert("ghost_lag".to_string(), AgentInfo {
@@ -177,6 +394,9 @@ impl CeoAgent {
             last_activity: chrono::Utc::now().to_rfc3339(),
             capabilities: vec!["content_generation".to_string(), "optimization".to_string()],
             current_task: None,
+            running_tasks: Vec::new(),
+            max_concurrent_tasks: 2,
+            task_timeout: None,
         });
         
         registry.insert("ceo_lag".to_string(), AgentInfo {
@@ -188,105 +408,406 @@ impl CeoAgent {
             last_activity: chrono::Utc::now().to_rfc3339(),
             capabilities: vec!["orchestration".to_string(), "task_management".to_string()],
             current_task: None,
+            running_tasks: Vec::new(),
+            max_concurrent_tasks: 0,
+            task_timeout: None,
         });
         
         info!("Agent registry initialized with {} agents", registry.len());
-        Ok(())
     }
 
-    fn start_task_processor(&self) -> Result<()> {
+    fn start_task_processor(&self, stop: CancellationToken) -> Result<()> {
         info!("Starting task processor");
         
-        let task_queue = self.task_queue.clone();
-        let agent_registry = self.agent_registry.clone();
+        // Shares the registry, limits and database with this instance
+        let ceo = self.clone();
         
-        // Spawn task processor thread
-        std::thread::spawn(move || {
+        // Spawn task processor on the async runtime
+        tokio::spawn(async move {
             loop {
                 // Process pending tasks
-                let mut queue = task_queue.lock().unwrap();
-                let mut registry = agent_registry.lock().unwrap();
+                let pending = match ceo.db.tasks().list_by_status(TaskStatus::Pending).await {
+                    Ok(pending) => pending,
+                    Err(e) => {
+                        error!("Failed to load pending tasks: {}", e);
+                        Vec::new()
+                    }
+                };
                 
-                for task in queue.iter_mut() {
-                    if matches!(task.status, TaskStatus::Pending) {
-                        // Find available agent
-                        if let Some(agent) = registry.get_mut(&task.agent_name) {
-                            if agent.status == "available" {
-                                // Assign task to agent
-                                task.status = TaskStatus::Running;
-                                task.started_at = Some(chrono::Utc::now().to_rfc3339());
-                                agent.current_task = Some(task.id.clone());
-                                agent.status = "busy".to_string();
-                                
-                                info!("Assigned task {} to agent {}", task.id, task.agent_name);
+                let now = chrono::Utc::now();
+                for mut task in pending.into_iter().filter(|task| task.is_eligible(now)) {
+                    if stop.is_cancelled() {
+                        break;
+                    }
+                    
+                    let (max_concurrent, default_timeout) = {
+                        let limits = ceo.limits.lock().unwrap();
+                        (limits.max_concurrent_tasks, limits.task_timeout)
+                    };
+                    
+                    // Respect the global limit and the target agent's own limit
+                    let timeout = {
+                        let registry = ceo.agent_registry.lock().unwrap();
+                        let running: usize = registry.values().map(|agent| agent.running_tasks.len()).sum();
+                        if running as i32 >= max_concurrent {
+                            break;
+                        }
+                        
+                        match registry.get(&task.agent_name) {
+                            Some(agent) if agent.has_free_slot() => {
+                                Self::resolve_timeout(&task, agent, default_timeout)
+                            }
+                            _ => continue,
+                        }
+                    };
+                    
+                    // And the task's own limit, if it set one
+                    if let Some((group, max)) = task.concurrency_group() {
+                        if ceo.running_groups.lock().unwrap().get(&group).copied().unwrap_or(0) >= max {
+                            continue;
+                        }
+                    }
+                    
+                    // Graph tasks wait for their parents and take their outputs
+                    if !task.depends_on.is_empty() {
+                        match ceo.release_dependencies(&mut task).await {
+                            Ok(true) => {}
+                            Ok(false) => continue,
+                            Err(e) => {
+                                error!("Failed to resolve dependencies of task {}: {}", task.id, e);
+                                continue;
+                            }
+                        }
+                    }
+                    
+                    match ceo.db.tasks().claim(&mut task).await {
+                        Ok(true) => {
+                            // Assign task to agent
+                            if let Some(agent) = ceo.agent_registry.lock().unwrap().get_mut(&task.agent_name) {
+                                agent.assign(&task.id);
                             }
+                            ceo.system_metrics.lock().unwrap().active_tasks += 1;
+                            if let Some((group, _)) = task.concurrency_group() {
+                                *ceo.running_groups.lock().unwrap().entry(group).or_default() += 1;
+                            }
+                            
+                            let context = TaskContext {
+                                cancel: CancellationToken::new(),
+                                progress: ceo.progress.reporter(&task.id),
+                            };
+                            ceo.running_tasks.lock().unwrap().insert(task.id.clone(), context.cancel.clone());
+                            context.progress.report("starting", 0.0);
+                            
+                            info!("Assigned task {} to agent {} (attempt {}/{}, timeout: {}s)", task.id, task.agent_name, task.attempt, task.max_attempts, timeout);
+                            
+                            tokio::spawn(ceo.clone().execute_task(task, timeout, context));
                         }
+                        Ok(false) => {}
+                        Err(e) => error!("Failed to claim task {}: {}", task.id, e),
                     }
                 }
                 
-                drop(queue);
-                drop(registry);
-                
-                std::thread::sleep(std::time::Duration::from_secs(1));
+                tokio::select! {
+                    _ = stop.cancelled() => break,
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
+                }
             }
+            info!("Task processor stopped");
         });
         
         Ok(())
     }
 
-    fn start_health_monitoring(&self) -> Result<()> {
+    /// Check a task's parents. Returns true once all of them completed, after
+    /// passing their results into the task's parameters.
+    async fn release_dependencies(&self, task: &mut Task) -> Result<bool> {
+        let parents = self.db.tasks().list_by_ids(&task.depends_on).await?;
+        
+        if let Some(parent) = parents.iter().find(|p| p.status.is_terminal() && p.status != TaskStatus::Completed) {
+            // Parent ended without completing; safety net for cascades missed while stopped
+            let reason = format!("Upstream task {} ended as {}", graph::reference_name(parent), parent.status.as_str());
+            self.db.tasks().cancel(&task.id, Some(&reason)).await?;
+            self.cancel_dependents(&task.id, &reason).await?;
+            return Ok(false);
+        }
+        
+        if parents.len() != task.depends_on.len() {
+            // A parent was deleted, so its outputs can never be passed in
+            let missing: Vec<&str> = task.depends_on.iter()
+                .filter(|id| !parents.iter().any(|p| &p.id == *id))
+                .map(String::as_str)
+                .collect();
+            let reason = format!("Upstream task {} no longer exists", missing.join(", "));
+            self.db.tasks().cancel(&task.id, Some(&reason)).await?;
+            self.cancel_dependents(&task.id, &reason).await?;
+            return Ok(false);
+        }
+        
+        if parents.iter().any(|p| p.status != TaskStatus::Completed) {
+            return Ok(false);
+        }
+        
+        let parameters = match graph::resolve_inputs(&task.parameters, &parents) {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                // Outputs don't fit the child's parameters, so it can never run
+                let reason = format!("Failed to pass upstream outputs: {}", e);
+                self.db.tasks().cancel(&task.id, Some(&reason)).await?;
+                self.cancel_dependents(&task.id, &reason).await?;
+                return Ok(false);
+            }
+        };
+        self.db.tasks().update_parameters(&task.id, &parameters).await?;
+        task.parameters = parameters;
+        
+        Ok(true)
+    }
+
+    /// Cancel every pending task downstream of the given one
+    async fn cancel_dependents(&self, task_id: &str, reason: &str) -> Result<()> {
+        let tasks = self.db.tasks();
+        let mut queue = vec![task_id.to_string()];
+        
+        while let Some(id) = queue.pop() {
+            for dependent in tasks.list_dependents(&id).await? {
+                if tasks.cancel(&dependent.id, Some(reason)).await? {
+                    info!("Cancelled task {}: {}", dependent.id, reason);
+                    queue.push(dependent.id);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Timeout for a task: its own `timeout` option, then the agent's, then the CEO default
+    fn resolve_timeout(task: &Task, agent: &AgentInfo, default_timeout: i32) -> u64 {
+        let task_timeout = task.parameters.get("options")
+            .and_then(|options| options.get("timeout"))
+            .and_then(|v| v.as_u64());
+        
+        task_timeout
+            .or_else(|| agent.task_timeout.map(|t| t.max(1) as u64))
+            .unwrap_or(default_timeout.max(1) as u64)
+    }
+
+    /// Run a claimed task to completion, record its outcome and release the agent
+    async fn execute_task(self, mut task: Task, timeout: u64, context: TaskContext) {
+        let file_path = task.parameters.get("file_path").and_then(|v| v.as_str()).map(String::from);
+        if let Some(file_path) = &file_path {
+            if let Err(e) = self.db.file_processing().log(file_path, &task.agent_name).await {
+                error!("Failed to log processing of {}: {}", file_path, e);
+            }
+        }
+        let started = std::time::Instant::now();
+        
+        let mut timed_out = false;
+        let outcome = {
+            let dispatch = self.dispatcher.dispatch(&task, context.clone());
+            tokio::pin!(dispatch);
+            
+            tokio::select! {
+                outcome = &mut dispatch => outcome,
+                _ = tokio::time::sleep(std::time::Duration::from_secs(timeout)) => {
+                    // Stop the agent and let it clean up, but do not wait on one that ignores the token
+                    context.cancel.cancel();
+                    if tokio::time::timeout(CANCEL_GRACE_PERIOD, dispatch).await.is_err() {
+                        warn!("Task {} did not stop within {:?} of its timeout, abandoning it", task.id, CANCEL_GRACE_PERIOD);
+                    }
+                    timed_out = true;
+                    Err(VhqError::Timeout { operation: "Task".to_string(), secs: timeout }.into())
+                }
+            }
+        };
+        self.running_tasks.lock().unwrap().remove(&task.id);
+        
+        let now = chrono::Utc::now();
+        match outcome {
+            Ok(result) => {
+                info!("Task {} completed", task.id);
+                task.status = TaskStatus::Completed;
+                task.completed_at = Some(now.to_rfc3339());
+                task.result = Some(result);
+                task.error = None;
+            }
+            Err(e) => {
+                let error = VhqError::from(e);
+                let message = error.to_string();
+                task.error = Some(message.clone());
+                
+                // Another attempt would get the same budget, so going over it is final
+                if timed_out || !error.retryable() {
+                    error!("Task {} failed: {}", task.id, message);
+                    task.status = TaskStatus::Failed;
+                    task.completed_at = Some(now.to_rfc3339());
+                } else if task.attempt < task.max_attempts {
+                    // Back to the queue once the backoff delay has passed
+                    let next = task.backoff.next_eligible_at(task.attempt, now);
+                    error!("Task {} failed on attempt {}/{}, retrying at {}: {}", task.id, task.attempt, task.max_attempts, next, message);
+                    task.status = TaskStatus::Pending;
+                    task.started_at = None;
+                    task.next_eligible_at = Some(next.to_rfc3339());
+                } else {
+                    error!("Task {} exhausted {} attempts, moving to dead-letter queue: {}", task.id, task.max_attempts, message);
+                    task.status = TaskStatus::DeadLetter;
+                    task.completed_at = Some(now.to_rfc3339());
+                }
+            }
+        }
+        
+        // A task cancelled while running keeps its Cancelled status
+        let stage = match self.db.tasks().finish(&task).await {
+            Ok(true) => {
+                let stage = match task.status {
+                    TaskStatus::Completed => "completed",
+                    TaskStatus::Pending => "retrying",
+                    TaskStatus::DeadLetter => "dead_letter",
+                    _ => "failed",
+                };
+                if task.status == TaskStatus::Completed {
+                    context.progress.report(stage, 100.0);
+                } else {
+                    context.progress.finish(stage);
+                }
+                
+                {
+                    let mut metrics = self.system_metrics.lock().unwrap();
+                    match task.status {
+                        TaskStatus::Completed => metrics.completed_tasks += 1,
+                        TaskStatus::Failed | TaskStatus::DeadLetter => metrics.failed_tasks += 1,
+                        _ => {}
+                    }
+                }
+                
+                if matches!(task.status, TaskStatus::Failed | TaskStatus::DeadLetter) {
+                    let reason = format!("Upstream task {} ended as {}", graph::reference_name(&task), task.status.as_str());
+                    if let Err(e) = self.cancel_dependents(&task.id, &reason).await {
+                        error!("Failed to cancel dependents of task {}: {}", task.id, e);
+                    }
+                }
+                Some(stage)
+            }
+            Ok(false) => {
+                info!("Task {} was cancelled before it finished", task.id);
+                context.progress.finish("cancelled");
+                Some("cancelled")
+            }
+            Err(e) => {
+                error!("Failed to record outcome of task {}: {}", task.id, e);
+                None
+            }
+        };
+        
+        if let (Some(file_path), Some(stage)) = (&file_path, stage) {
+            let elapsed = started.elapsed().as_millis() as i64;
+            if let Err(e) = self.db.file_processing().update_status(file_path, stage, task.result.as_deref(), task.error.as_deref(), Some(elapsed)).await {
+                error!("Failed to record processing of {}: {}", file_path, e);
+            }
+        }
+        
+        self.system_metrics.lock().unwrap().active_tasks -= 1;
+        if let Some((group, _)) = task.concurrency_group() {
+            let mut groups = self.running_groups.lock().unwrap();
+            if let Some(running) = groups.get_mut(&group) {
+                *running -= 1;
+                if *running == 0 {
+                    groups.remove(&group);
+                }
+            }
+        }
+        
+        // Release agent
+        if let Some(agent) = self.agent_registry.lock().unwrap().get_mut(&task.agent_name) {
+            agent.release(&task.id);
+        }
+    }
+
+    fn start_health_monitoring(&self, stop: CancellationToken) -> Result<()> {
         info!("Starting health monitoring");
         
-        let agent_registry = self.agent_registry.clone();
-        let system_metrics = self.system_metrics.clone();
+        // Shares the registry, metrics and health history with this instance
+        let ceo = self.clone();
+        let started = std::time::Instant::now();
         
-        // Spawn health monitoring thread
-        std::thread::spawn(move || {
+        // Spawn health monitoring on the async runtime
+        tokio::spawn(async move {
+            let mut sampler = SystemSampler::new(Vec::new());
+            let agent_processes = ceo.dispatcher.child_processes().await;
+            let mut last_probe: Option<std::time::Instant> = None;
+            
             loop {
+                // Follow the configured output directories
+                let output_dirs = ceo.dispatcher.output_dirs().await;
+                sampler.set_dirs(metrics::monitored_dirs(&output_dirs));
+                
                 // Update system metrics
-                let mut metrics = system_metrics.lock().unwrap();
-                metrics.uptime += 1.0;
+                let snapshot = sampler.sample();
+                {
+                    let mut metrics = ceo.system_metrics.lock().unwrap();
+                    metrics.total_memory = snapshot.memory.total_bytes as i64;
+                    metrics.used_memory = snapshot.memory.used_bytes as i64;
+                    metrics.cpu_usage = snapshot.cpu_percent;
+                    metrics.disk_usage = snapshot.disk_percent();
+                    metrics.disks = snapshot.disks;
+                    metrics.uptime = started.elapsed().as_secs_f64();
+                }
                 
-                // Update agent health scores
-                let mut registry = agent_registry.lock().unwrap();
-                for agent in registry.values_mut() {
-                    // Simulate health monitoring
-                    agent.health_score = 0.95 + (rand::random::<f64>() * 0.05);
-                    agent.last_activity = chrono::Utc::now().to_rfc3339();
+                // Probe dependencies less often than metrics are sampled
+                if last_probe.is_none_or(|at| at.elapsed() >= HEALTH_PROBE_INTERVAL) {
+                    let checker = HealthChecker {
+                        db: ceo.db.clone(),
+                        ollama_url: ceo.dispatcher.ollama_url().await,
+                        output_dirs,
+                        thresholds: ceo.health_thresholds.lock().unwrap().clone(),
+                    };
+                    let report = checker.run().await;
+                    if report.status != HealthStatus::Healthy {
+                        warn!("Health check reported {:?}", report.status);
+                    }
+                    ceo.health.push(report);
+                    last_probe = Some(std::time::Instant::now());
                 }
+                let report = ceo.health.latest();
                 
-                drop(metrics);
-                drop(registry);
+                // Resources used by each agent's child processes
+                let usage: HashMap<&str, _> = agent_processes.iter()
+                    .map(|(name, children)| (name.as_str(), children.sample()))
+                    .collect();
+                
+                // Update agent health scores
+                {
+                    let mut registry = ceo.agent_registry.lock().unwrap();
+                    for (name, agent) in registry.iter_mut() {
+                        if let Some(score) = report.as_ref().and_then(|report| report.agent_score(name)) {
+                            agent.health_score = score;
+                        }
+                        
+                        if let Some(usage) = usage.get(name.as_str()) {
+                            agent.memory_usage = usage.memory_bytes as i64;
+                            agent.cpu_usage = usage.cpu_percent;
+                        }
+                    }
+                }
                 
-                std::thread::sleep(std::time::Duration::from_secs(5));
+                tokio::select! {
+                    _ = stop.cancelled() => break,
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {}
+                }
             }
+            info!("Health monitoring stopped");
         });
         
         Ok(())
     }
 
     fn create_orchestration_task(&self, file_path: &str, options: serde_json::Value) -> Result<Task> {
-        let task_id = uuid::Uuid::new_v4().to_string();
-        
         // Determine which agent to use based on file type
         let agent_name = self.determine_agent_for_file(file_path)?;
         
-        let task = Task {
-            id: task_id,
-            agent_name,
-            task_type: "file_processing".to_string(),
-            parameters: serde_json::json!({
-                "file_path": file_path,
-                "options": options
-            }),
-            priority: 1,
-            status: TaskStatus::Pending,
-            created_at: chrono::Utc::now().to_rfc3339(),
-            started_at: None,
-            completed_at: None,
-            result: None,
-            error: None,
-        };
+        let task = Task::new(&agent_name, "file_processing", serde_json::json!({
+            "file_path": file_path,
+            "options": options
+        }), &self.retry_policy);
         
         Ok(task)
     }
@@ -305,55 +826,52 @@ impl CeoAgent {
         }
     }
 
-    fn add_task(&self, task: Task) -> Result<()> {
-        let task_id = task.id.clone();
-        let task_priority = task.priority;
-        let mut queue = self.task_queue.lock().unwrap();
-        queue.push(task);
-        queue.sort_by(|a, b| b.priority.cmp(&a.priority)); // Higher priority first
+    async fn add_task(&self, task: Task) -> Result<()> {
+        self.db.tasks().insert(&task).await?;
         
-        info!("Added task {} to queue (priority: {})", task_id, task_priority);
+        info!("Added task {} to queue (priority: {})", task.id, task.priority);
         Ok(())
     }
 
-    pub fn get_task_status(&self, task_id: &str) -> Result<serde_json::Value> {
-        let queue = self.task_queue.lock().unwrap();
-        
-        if let Some(task) = queue.iter().find(|t| t.id == task_id) {
+    pub async fn get_task_status(&self, task_id: &str) -> Result<serde_json::Value> {
+        if let Some(task) = self.db.tasks().get(task_id).await? {
             Ok(serde_json::json!({
                 "task_id": task.id,
-                "status": format!("{:?}", task.status),
+                "status": task.status.as_str(),
                 "agent": task.agent_name,
                 "created_at": task.created_at,
                 "started_at": task.started_at,
                 "completed_at": task.completed_at,
                 "result": task.result,
-                "error": task.error
+                "error": task.error,
+                "attempt": task.attempt,
+                "max_attempts": task.max_attempts,
+                "next_eligible_at": task.next_eligible_at,
+                "depends_on": task.depends_on,
+                "graph_id": task.graph_id,
+                "progress": self.progress.get(&task.id)
             }))
         } else {
-            Err(anyhow::anyhow!("Task not found: {}", task_id))
+            Err(VhqError::NotFound { entity: "task", id: task_id.to_string() }.into())
         }
     }
 
-    pub fn get_queue_status(&self) -> Result<serde_json::Value> {
-        let queue = self.task_queue.lock().unwrap();
+    pub async fn get_queue_status(&self) -> Result<serde_json::Value> {
+        let counts = self.db.tasks().count_by_status().await?;
+        let count = |status: TaskStatus| counts.get(status.as_str()).copied().unwrap_or(0);
         let registry = self.agent_registry.lock().unwrap();
         
-        let pending = queue.iter().filter(|t| matches!(t.status, TaskStatus::Pending)).count();
-        let running = queue.iter().filter(|t| matches!(t.status, TaskStatus::Running)).count();
-        let completed = queue.iter().filter(|t| matches!(t.status, TaskStatus::Completed)).count();
-        let failed = queue.iter().filter(|t| matches!(t.status, TaskStatus::Failed)).count();
-        
         Ok(serde_json::json!({
             "queue_stats": {
-                "pending": pending,
-                "running": running,
-                "completed": completed,
-                "failed": failed,
-                "total": queue.len()
+                "pending": count(TaskStatus::Pending),
+                "running": count(TaskStatus::Running),
+                "completed": count(TaskStatus::Completed),
+                "failed": count(TaskStatus::Failed),
+                "dead_letter": count(TaskStatus::DeadLetter),
+                "total": counts.values().sum::<i64>()
             },
             "agents": registry.values().collect::<Vec<_>>(),
-            "max_concurrent_tasks": self.max_concurrent_tasks
+            "max_concurrent_tasks": self.limits.lock().unwrap().max_concurrent_tasks
         }))
     }
 
@@ -362,32 +880,355 @@ impl CeoAgent {
         Ok(serde_json::to_value(&*metrics)?)
     }
 
-    pub fn cancel_task(&self, task_id: &str) -> Result<()> {
-        let mut queue = self.task_queue.lock().unwrap();
-        
-        if let Some(task) = queue.iter_mut().find(|t| t.id == task_id) {
-            task.status = TaskStatus::Cancelled;
+    pub async fn cancel_task(&self, task_id: &str) -> Result<()> {
+        if self.db.tasks().cancel(task_id, None).await? {
             info!("Cancelled task: {}", task_id);
-            Ok(())
+            
+            // Stop the work if the task is already running
+            if let Some(cancel) = self.running_tasks.lock().unwrap().get(task_id) {
+                cancel.cancel();
+            }
+            
+            self.cancel_dependents(task_id, &format!("Upstream task {} was cancelled", task_id)).await
+        } else if self.db.tasks().get(task_id).await?.is_some() {
+            Err(VhqError::invalid_input(format!("Task already finished: {}", task_id)).into())
         } else {
-            Err(anyhow::anyhow!("Task not found: {}", task_id))
+            Err(VhqError::NotFound { entity: "task", id: task_id.to_string() }.into())
         }
     }
 
-    pub fn clear_completed_tasks(&self) -> Result<()> {
-        let mut queue = self.task_queue.lock().unwrap();
-        queue.retain(|task| !matches!(task.status, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled));
+    pub async fn clear_completed_tasks(&self) -> Result<()> {
+        self.db.tasks().delete_finished().await?;
+        
+        let running: Vec<String> = self.running_tasks.lock().unwrap().keys().cloned().collect();
+        self.progress.retain(&running);
         
         info!("Cleared completed tasks from queue");
         Ok(())
     }
 
+    /// Queue a whole task graph in one call. Nodes run once all their parents completed.
+    pub async fn submit_graph(&self, request: TaskGraphRequest) -> Result<TaskGraphStatus> {
+        let order = graph::validate(&request)?;
+        
+        let graph_id = uuid::Uuid::new_v4().to_string();
+        let task_ids: HashMap<&str, String> = request.nodes.iter()
+            .map(|node| (node.key.as_str(), uuid::Uuid::new_v4().to_string()))
+            .collect();
+        let nodes: HashMap<&str, _> = request.nodes.iter().map(|node| (node.key.as_str(), node)).collect();
+        
+        let mut tasks = Vec::new();
+        for key in &order {
+            let node = nodes[key.as_str()];
+            
+            if !matches!(node.agent_name.as_str(), "vitra_lag" | "ghost_lag") {
+                return Err(VhqError::invalid_input(format!("Node {} targets unknown agent: {}", node.key, node.agent_name)).into());
+            }
+            if let Some(file_path) = &node.file_path {
+                if !file_path.contains("{{") && !Path::new(file_path).exists() {
+                    return Err(VhqError::FileNotFound(file_path.clone()).into());
+                }
+            }
+            
+            let mut task = Task::new(&node.agent_name, "graph_node", serde_json::json!({
+                "file_path": node.file_path,
+                "options": node.options
+            }), &self.retry_policy);
+            task.id = task_ids[key.as_str()].clone();
+            task.priority = node.priority.unwrap_or(1);
+            task.depends_on = node.depends_on.iter().map(|parent| task_ids[parent.as_str()].clone()).collect();
+            task.graph_id = Some(graph_id.clone());
+            task.node_key = Some(node.key.clone());
+            tasks.push(task);
+        }
+        
+        self.db.tasks().insert_many(&tasks).await?;
+        info!("Queued task graph {} ({:?}) with {} nodes", graph_id, request.name, tasks.len());
+        
+        self.get_graph_status(&graph_id).await
+    }
+
+    /// Per-node progress of a submitted task graph
+    pub async fn get_graph_status(&self, graph_id: &str) -> Result<TaskGraphStatus> {
+        let tasks = self.db.tasks().list_by_graph(graph_id).await?;
+        if tasks.is_empty() {
+            return Err(VhqError::NotFound { entity: "task graph", id: graph_id.to_string() }.into());
+        }
+        
+        let keys: HashMap<&str, &str> = tasks.iter().map(|t| (t.id.as_str(), graph::reference_name(t))).collect();
+        let nodes: Vec<TaskNodeStatus> = tasks.iter().map(|task| TaskNodeStatus {
+            key: graph::reference_name(task).to_string(),
+            task_id: task.id.clone(),
+            agent_name: task.agent_name.clone(),
+            status: task.status,
+            depends_on: task.depends_on.iter()
+                .map(|id| keys.get(id.as_str()).copied().unwrap_or(id).to_string())
+                .collect(),
+            attempt: task.attempt,
+            started_at: task.started_at.clone(),
+            completed_at: task.completed_at.clone(),
+            error: task.error.clone(),
+        }).collect();
+        
+        let completed = nodes.iter().filter(|n| n.status == TaskStatus::Completed).count();
+        let failed = nodes.iter().filter(|n| n.status.is_terminal() && n.status != TaskStatus::Completed).count();
+        
+        Ok(TaskGraphStatus {
+            graph_id: graph_id.to_string(),
+            total: nodes.len(),
+            completed,
+            failed,
+            progress: completed as f64 / nodes.len() as f64,
+            finished: nodes.iter().all(|n| n.status.is_terminal()),
+            nodes,
+        })
+    }
+
+    /// Tasks that exhausted their retry attempts
+    pub async fn get_dead_letter_tasks(&self) -> Result<Vec<Task>> {
+        Ok(self.db.tasks().list_by_status(TaskStatus::DeadLetter).await?)
+    }
+
+    /// Put a dead-lettered task back in the queue with a fresh attempt budget
+    pub async fn replay_dead_letter_task(&self, task_id: &str) -> Result<()> {
+        if self.db.tasks().replay(task_id).await? {
+            info!("Replaying dead-letter task: {}", task_id);
+            Ok(())
+        } else if self.db.tasks().get(task_id).await?.is_some() {
+            Err(VhqError::invalid_input(format!("Task is not in the dead-letter queue: {}", task_id)).into())
+        } else {
+            Err(VhqError::NotFound { entity: "task", id: task_id.to_string() }.into())
+        }
+    }
+
+    /// Take over configured settings that are not per-agent limits
+    pub fn apply_config(&mut self, config: &AppConfig) {
+        self.recovery_policy = config.tasks.recovery_policy;
+        *self.health_thresholds.lock().unwrap() = config.health.clone();
+    }
+
+    /// Resolve tasks that were still running when the previous session ended
+    pub async fn recover_interrupted_tasks(&self) -> Result<u64> {
+        Ok(self.db.tasks().recover_running(self.recovery_policy).await?)
+    }
+
+    /// Update the scheduler-wide limits; applies to tasks scheduled from now on
+    pub fn set_limits(&self, max_concurrent_tasks: Option<i32>, task_timeout: Option<i32>) -> Result<()> {
+        let mut limits = self.limits.lock().unwrap();
+        
+        if let Some(max) = max_concurrent_tasks {
+            if max < 1 {
+                return Err(VhqError::invalid_input("max_concurrent_tasks must be at least 1").into());
+            }
+            limits.max_concurrent_tasks = max;
+        }
+        if let Some(timeout) = task_timeout {
+            if timeout < 1 {
+                return Err(VhqError::invalid_input("task_timeout must be at least 1 second").into());
+            }
+            limits.task_timeout = timeout;
+        }
+        
+        info!("Task limits updated: {:?}", *limits);
+        Ok(())
+    }
+
+    /// Update the concurrency and timeout limits of a single agent
+    pub fn set_agent_limits(&self, agent_name: &str, max_concurrent_tasks: Option<i32>, task_timeout: Option<i32>) -> Result<()> {
+        let mut registry = self.agent_registry.lock().unwrap();
+        let agent = registry.get_mut(agent_name)
+            .ok_or_else(|| VhqError::NotFound { entity: "agent", id: agent_name.to_string() })?;
+        
+        if let Some(max) = max_concurrent_tasks {
+            if max < 1 {
+                return Err(VhqError::invalid_input("max_concurrent_tasks must be at least 1").into());
+            }
+            agent.max_concurrent_tasks = max;
+        }
+        if let Some(timeout) = task_timeout {
+            if timeout < 1 {
+                return Err(VhqError::invalid_input("task_timeout must be at least 1 second").into());
+            }
+            agent.task_timeout = Some(timeout);
+        }
+        agent.refresh_status();
+        
+        info!("Limits updated for {}: max {} concurrent, timeout {:?}", agent_name, agent.max_concurrent_tasks, agent.task_timeout);
+        Ok(())
+    }
+
+    /// Replace an agent's limits with configured values; `None` falls back to the scheduler-wide timeout
+    pub fn configure_agent(&self, agent_name: &str, max_concurrent_tasks: i32, task_timeout: Option<i32>) -> Result<()> {
+        self.set_agent_limits(agent_name, Some(max_concurrent_tasks), task_timeout)?;
+        if task_timeout.is_none() {
+            if let Some(agent) = self.agent_registry.lock().unwrap().get_mut(agent_name) {
+                agent.task_timeout = None;
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_agent_info(&self) -> serde_json::Value {
+        let limits = self.limits.lock().unwrap();
         serde_json::json!({
-            "max_concurrent_tasks": self.max_concurrent_tasks,
-            "task_timeout": self.task_timeout,
+            "max_concurrent_tasks": limits.max_concurrent_tasks,
+            "task_timeout": limits.task_timeout,
             "status": self.status,
             "last_activity": self.last_activity
         })
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::ghost::GhostAgent;
+    use crate::agents::vitra::VitraAgent;
+    use crate::config::GhostConfig;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Ollama that takes longer to answer than any test waits
+    async fn slow_ollama() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(60)))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn ceo(ollama: &MockServer) -> CeoAgent {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let ghost = GhostAgent::with_config(&GhostConfig { ollama_url: ollama.uri(), ..Default::default() });
+        let dispatcher = TaskDispatcher::new(Arc::new(RwLock::new(VitraAgent::new())), Arc::new(RwLock::new(ghost)));
+        CeoAgent::with_dispatcher(Database::new(pool), dispatcher)
+    }
+
+    /// Queue `count` GHOST tasks on a text file
+    async fn queue(ceo: &CeoAgent, count: usize, options: serde_json::Value) -> Vec<String> {
+        let file = std::env::temp_dir().join(format!("vhq_ceo_{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&file, "notes").unwrap();
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            let task = Task::new("ghost_lag", "file_processing", serde_json::json!({
+                "file_path": file.to_string_lossy(),
+                "options": options
+            }), &ceo.retry_policy);
+            ids.push(task.id.clone());
+            ceo.add_task(task).await.unwrap();
+        }
+        ids
+    }
+
+    async fn statuses(ceo: &CeoAgent, ids: &[String]) -> Vec<TaskStatus> {
+        let mut statuses = Vec::new();
+        for id in ids {
+            statuses.push(ceo.db.tasks().get(id).await.unwrap().unwrap().status);
+        }
+        statuses
+    }
+
+    /// Number of `ids` running once the processor had time to claim all it may
+    async fn settle_running(ceo: &CeoAgent, ids: &[String]) -> usize {
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        statuses(ceo, ids).await.iter().filter(|status| **status == TaskStatus::Running).count()
+    }
+
+    async fn shut_down(mut ceo: CeoAgent, ids: &[String]) {
+        ceo.stop().unwrap();
+        for id in ids {
+            let _ = ceo.cancel_task(id).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_input_fails_task_and_records_file() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let ceo = CeoAgent::new(Database::new(pool));
+        let file_path = "/nonexistent/interview.wav";
+        let mut task = Task::new("vitra_lag", "file_processing", serde_json::json!({ "file_path": file_path }), &ceo.retry_policy);
+        ceo.add_task(task.clone()).await.unwrap();
+        assert!(ceo.db.tasks().claim(&mut task).await.unwrap());
+
+        let context = TaskContext { cancel: CancellationToken::new(), progress: ceo.progress.reporter(&task.id) };
+        ceo.clone().execute_task(task.clone(), 5, context).await;
+
+        let task = ceo.db.tasks().get(&task.id).await.unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.error, Some(VhqError::FileNotFound(file_path.to_string()).to_string()));
+        assert!(task.result.is_none());
+
+        let history = ceo.db.file_processing().history(10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].file_path, file_path);
+        assert_eq!(history[0].agent_type, "vitra_lag");
+        assert_eq!(history[0].status, "failed");
+        assert_eq!(history[0].error_message, task.error);
+        assert!(history[0].completed_at.is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_agent_limit_holds_tasks_back() {
+        let ollama = slow_ollama().await;
+        let mut ceo = ceo(&ollama).await;
+        ceo.set_agent_limits("ghost_lag", Some(1), None).unwrap();
+        let ids = queue(&ceo, 3, serde_json::json!({})).await;
+
+        ceo.start().unwrap();
+        assert_eq!(settle_running(&ceo, &ids).await, 1);
+        assert_eq!(statuses(&ceo, &ids).await.iter().filter(|status| **status == TaskStatus::Pending).count(), 2);
+
+        shut_down(ceo, &ids).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_global_limit_holds_tasks_back() {
+        let ollama = slow_ollama().await;
+        let mut ceo = ceo(&ollama).await;
+        ceo.set_agent_limits("ghost_lag", Some(3), None).unwrap();
+        ceo.set_limits(Some(2), None).unwrap();
+        let ids = queue(&ceo, 3, serde_json::json!({})).await;
+
+        ceo.start().unwrap();
+        assert_eq!(settle_running(&ceo, &ids).await, 2);
+
+        shut_down(ceo, &ids).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_task_limit_holds_its_group_back() {
+        let ollama = slow_ollama().await;
+        let mut ceo = ceo(&ollama).await;
+        ceo.set_agent_limits("ghost_lag", Some(3), None).unwrap();
+        let ids = queue(&ceo, 3, serde_json::json!({ "max_concurrent": 1 })).await;
+
+        ceo.start().unwrap();
+        assert_eq!(settle_running(&ceo, &ids).await, 1);
+
+        shut_down(ceo, &ids).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_task_over_budget_fails_with_timeout() {
+        let ollama = slow_ollama().await;
+        let mut ceo = ceo(&ollama).await;
+        let ids = queue(&ceo, 1, serde_json::json!({ "timeout": 1, "max_attempts": 3 })).await;
+
+        ceo.start().unwrap();
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+
+        let task = ceo.db.tasks().get(&ids[0]).await.unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.attempt, 1);
+        assert_eq!(task.error, Some(VhqError::Timeout { operation: "Task".to_string(), secs: 1 }.to_string()));
+        assert!(ceo.agent_registry.lock().unwrap()["ghost_lag"].running_tasks.is_empty());
+
+        shut_down(ceo, &ids).await;
+    }
+}
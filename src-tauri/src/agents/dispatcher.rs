@@ -0,0 +1,119 @@
+use anyhow::Result;
+use tracing::info;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::cancellation::CancellationToken;
+use super::ceo::Task;
+use super::progress::ProgressReporter;
+use super::ghost::GhostAgent;
+use super::vitra::{VitraAgent, TRANSCRIPTS_DIR};
+use crate::error::VhqError;
+use crate::metrics::ChildProcesses;
+
+/// Per-execution handles passed down to the agent running a task
+#[derive(Debug, Clone)]
+pub struct TaskContext {
+    pub cancel: CancellationToken,
+    pub progress: ProgressReporter,
+}
+
+/// Routes queued CEO tasks to the agent instances that execute them
+#[derive(Debug, Clone)]
+pub struct TaskDispatcher {
+    vitra: Arc<RwLock<VitraAgent>>,
+    ghost: Arc<RwLock<GhostAgent>>,
+}
+
+impl TaskDispatcher {
+    pub fn new(vitra: Arc<RwLock<VitraAgent>>, ghost: Arc<RwLock<GhostAgent>>) -> Self {
+        Self { vitra, ghost }
+    }
+
+    /// Dispatcher backed by its own agent instances, for a CEO running outside the app state
+    pub fn standalone() -> Self {
+        Self::new(
+            Arc::new(RwLock::new(VitraAgent::new())),
+            Arc::new(RwLock::new(GhostAgent::new())),
+        )
+    }
+
+    /// Child processes of each agent, by registry name
+    pub async fn child_processes(&self) -> Vec<(String, ChildProcesses)> {
+        vec![("vitra_lag".to_string(), self.vitra.read().await.children.clone())]
+    }
+
+    /// Ollama instance GHOST talks to
+    pub async fn ollama_url(&self) -> String {
+        self.ghost.read().await.ollama_url.clone()
+    }
+
+    /// Directories each agent writes its outputs to, by registry name
+    pub async fn output_dirs(&self) -> Vec<(String, PathBuf)> {
+        vec![
+            ("vitra_lag".to_string(), PathBuf::from(TRANSCRIPTS_DIR)),
+            ("ghost_lag".to_string(), PathBuf::from(&self.ghost.read().await.output_dir)),
+        ]
+    }
+
+    /// Run a task on its target agent and return the agent's output.
+    /// Cancelling the context's token stops the agent's work in progress.
+    pub async fn dispatch(&self, task: &Task, context: TaskContext) -> Result<String> {
+        let file_path = task.parameters.get("file_path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| VhqError::invalid_input(format!("Task {} has no file_path parameter", task.id)))?
+            .to_string();
+
+        let options = task.parameters.get("options")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({}));
+
+        info!("Dispatching task {} to {}", task.id, task.agent_name);
+
+        // Agents do blocking I/O, so run them on a snapshot off the async runtime.
+        // This keeps the shared instance free for start/stop while the task runs.
+        match task.agent_name.as_str() {
+            "vitra_lag" => {
+                let agent = self.vitra.read().await.clone();
+                tokio::task::spawn_blocking(move || {
+                    agent.process_file_cancellable(&file_path, options, &context.cancel, &context.progress)
+                }).await?
+            }
+            "ghost_lag" => {
+                let agent = self.ghost.read().await.clone();
+                tokio::task::spawn_blocking(move || {
+                    agent.process_file_cancellable(&file_path, options, &context.cancel, &context.progress)
+                }).await?
+            }
+            _ => Err(VhqError::invalid_input(format!("No executor for agent: {}", task.agent_name)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::progress::ProgressHub;
+    use crate::agents::retry::RetryPolicy;
+
+    fn context(task: &Task) -> TaskContext {
+        TaskContext { cancel: CancellationToken::new(), progress: ProgressHub::new().reporter(&task.id) }
+    }
+
+    #[tokio::test]
+    async fn test_routes_tasks_to_their_agent() {
+        let dispatcher = TaskDispatcher::standalone();
+        let parameters = serde_json::json!({ "file_path": "/nonexistent/input.wav" });
+
+        for agent in ["vitra_lag", "ghost_lag"] {
+            let task = Task::new(agent, "file_processing", parameters.clone(), &RetryPolicy::default());
+            let error = VhqError::from(dispatcher.dispatch(&task, context(&task)).await.unwrap_err());
+            assert_eq!(error.code(), "file_not_found", "{}", agent);
+        }
+
+        let task = Task::new("ceo_lag", "file_processing", parameters, &RetryPolicy::default());
+        let error = VhqError::from(dispatcher.dispatch(&task, context(&task)).await.unwrap_err());
+        assert_eq!(error.code(), "invalid_input");
+    }
+}
@@ -1,9 +1,15 @@
 use anyhow::Result;
-use tracing::info;
+use tracing::{info, warn};
 use std::path::Path;
 use std::process::Command;
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::commands::AgentStatus;
+use super::cancellation::{self, CancellationToken, PartialOutputs};
+use super::progress::ProgressReporter;
+use super::templates::TemplateSet;
+use crate::config::GhostConfig;
+use crate::error::VhqError;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +35,11 @@ pub struct GeneratedContent {
     pub created_at: String,
 }
 
+/// Where generated content is written unless configured otherwise
+pub const DEFAULT_OUTPUT_DIR: &str = "generated_content";
+
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
 #[derive(Debug, Clone)]
 pub struct GhostAgent {
     pub status: String,
@@ -38,23 +49,45 @@ pub struct GhostAgent {
     pub ollama_url: String,
     pub default_model: String,
     pub templates_dir: String,
+    /// Templates from `templates_dir`. Tasks run on a clone of the agent, so a reload
+    /// swaps in a new set for later tasks while running ones keep theirs.
+    pub templates: Arc<TemplateSet>,
     pub output_dir: String,
 }
 
 impl GhostAgent {
     pub fn new() -> Self {
+        Self::with_config(&GhostConfig::default())
+    }
+
+    pub fn with_config(config: &GhostConfig) -> Self {
+        let (templates, errors) = TemplateSet::load(Path::new(&config.templates_dir));
+        for error in errors {
+            warn!("Skipping GHOST template {}", error);
+        }
+
         Self {
             status: "stopped".to_string(),
             memory_usage: 0,
             cpu_usage: 0.0,
             last_activity: chrono::Utc::now().to_rfc3339(),
-            ollama_url: "http://localhost:11434".to_string(),
-            default_model: "llama2".to_string(),
-            templates_dir: "templates".to_string(),
-            output_dir: "generated_content".to_string(),
+            ollama_url: config.ollama_url.clone(),
+            default_model: config.default_model.clone(),
+            templates_dir: config.templates_dir.clone(),
+            templates: Arc::new(templates),
+            output_dir: config.output_dir.clone(),
         }
     }
 
+    /// Take over changed settings; requests already sent to Ollama are unaffected
+    pub fn apply_config(&mut self, config: &GhostConfig) {
+        self.ollama_url = config.ollama_url.clone();
+        self.default_model = config.default_model.clone();
+        self.templates_dir = config.templates_dir.clone();
+        self.output_dir = config.output_dir.clone();
+        info!("GHOST LAG configuration applied (Ollama: {}, model: {})", self.ollama_url, self.default_model);
+    }
+
     pub fn start(&mut self) -> Result<AgentStatus> {
         info!("Starting GHOST LAG agent");
         
@@ -91,11 +124,24 @@ impl GhostAgent {
     }
 
     pub fn process_file(&self, file_path: &str, options: serde_json::Value) -> Result<String> {
+        self.process_file_cancellable(file_path, options, &CancellationToken::new(), &ProgressReporter::disabled())
+    }
+
+    /// Process a file, reporting progress as tokens stream in. Aborts the Ollama
+    /// request and removes partial outputs once `cancel` fires.
+    pub fn process_file_cancellable(
+        &self,
+        file_path: &str,
+        options: serde_json::Value,
+        cancel: &CancellationToken,
+        progress: &ProgressReporter,
+    ) -> Result<String> {
         info!("GHOST LAG processing file: {}", file_path);
+        progress.report("preparing", 0.0);
         
         // Validate file exists
         if !Path::new(file_path).exists() {
-            return Err(anyhow::anyhow!("File not found: {}", file_path));
+            return Err(VhqError::FileNotFound(file_path.to_string()).into());
         }
 
         // Extract options
@@ -137,7 +183,7 @@ impl GhostAgent {
         let prompt = self.apply_template(&template, &variables)?;
         
         // Generate content
-        let result = self.generate_content(&prompt, model, &content_type)?;
+        let result = self.generate_content(&prompt, model, content_type, cancel, progress)?;
         
         // Save result
         self.save_generated_content(&result)?;
@@ -152,10 +198,10 @@ impl GhostAgent {
         let client = reqwest::blocking::Client::new();
         let response = client.get(&format!("{}/api/tags", self.ollama_url))
             .send()
-            .map_err(|e| anyhow::anyhow!("Failed to connect to Ollama: {}", e))?;
+            .map_err(|e| VhqError::request("Ollama", &e))?;
         
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!("Ollama is not responding properly"));
+            return Err(VhqError::backend("Ollama", Some(response.status().as_u16()), "not responding properly").into());
         }
         
         info!("Ollama connection established successfully");
@@ -163,23 +209,18 @@ impl GhostAgent {
     }
 
     fn load_template(&self, template_name: &str) -> Result<ContentTemplate> {
-        let template_path = format!("{}/{}.json", self.templates_dir, template_name);
-        
-        if !Path::new(&template_path).exists() {
-            // Return default template if not found
-            return Ok(ContentTemplate {
-                name: template_name.to_string(),
-                prompt_template: "Generate content based on the following input:\n\n{content}\n\nPlease provide a well-structured response.".to_string(),
-                variables: vec!["content".to_string()],
-                content_type: "article".to_string(),
-                description: "Default template for content generation".to_string(),
-            });
+        if let Some(template) = self.templates.get(template_name) {
+            return Ok(template.clone());
         }
-
-        let template_content = std::fs::read_to_string(&template_path)?;
-        let template: ContentTemplate = serde_json::from_str(&template_content)?;
         
-        Ok(template)
+        // Return default template if not found
+        Ok(ContentTemplate {
+            name: template_name.to_string(),
+            prompt_template: "Generate content based on the following input:\n\n{content}\n\nPlease provide a well-structured response.".to_string(),
+            variables: vec!["content".to_string()],
+            content_type: "article".to_string(),
+            description: "Default template for content generation".to_string(),
+        })
     }
 
     fn apply_template(&self, template: &ContentTemplate, variables: &HashMap<String, String>) -> Result<String> {
@@ -197,43 +238,81 @@ impl GhostAgent {
         Ok(prompt)
     }
 
-    fn generate_content(&self, prompt: &str, model: &str, content_type: &str) -> Result<GeneratedContent> {
+    fn generate_content(
+        &self,
+        prompt: &str,
+        model: &str,
+        content_type: &str,
+        cancel: &CancellationToken,
+        progress: &ProgressReporter,
+    ) -> Result<GeneratedContent> {
         info!("Generating content with model: {}", model);
         
         let start_time = std::time::Instant::now();
+        let num_predict = 1000;
         
-        // Prepare request payload
+        // Prepare request payload; streaming lets progress follow the generated tokens
         let request = serde_json::json!({
             "model": model,
             "prompt": prompt,
-            "stream": false,
+            "stream": true,
             "options": {
                 "temperature": 0.7,
                 "top_p": 0.9,
-                "num_predict": 1000,
+                "num_predict": num_predict,
             }
         });
 
-        // Make request to Ollama
-        let client = reqwest::blocking::Client::new();
-        let response = client
-            .post(&format!("{}/api/generate", self.ollama_url))
-            .json(&request)
-            .send()
-            .map_err(|e| anyhow::anyhow!("Failed to make request to Ollama: {}", e))?;
+        // Make request to Ollama; cancelling drops the request mid-flight
+        let client = reqwest::Client::new();
+        let url = format!("{}/api/generate", self.ollama_url);
+        let generated_text: String = cancellation::block_on(async {
+            let mut response = client
+                .post(&url)
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| VhqError::request("Ollama", &e))?;
+
+            let status = response.status().as_u16();
+            if !response.status().is_success() {
+                let error_text = response.text().await
+                    .map_err(|e| VhqError::request("Ollama", &e))?;
+                return Err(VhqError::backend("Ollama", Some(status), error_text));
+            }
 
-        if !response.status().is_success() {
-            let error_text = response.text()
-                .map_err(|e| anyhow::anyhow!("Failed to read error response: {}", e))?;
-            return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
-        }
+            // Ollama streams one JSON object per line, each carrying the next token(s)
+            let mut text = String::new();
+            let mut pending = Vec::new();
+            let mut tokens = 0;
+            progress.report("generating", 5.0);
+            while let Some(chunk) = response.chunk().await
+                .map_err(|e| VhqError::request("Ollama", &e))?
+            {
+                pending.extend_from_slice(&chunk);
+                while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    if line.iter().all(|b| b.is_ascii_whitespace()) {
+                        continue;
+                    }
 
-        let response_json: serde_json::Value = response.json()
-            .map_err(|e| anyhow::anyhow!("Failed to parse response: {}", e))?;
-        
-        let generated_text = response_json.get("response")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Invalid response from Ollama API"))?;
+                    let part: serde_json::Value = serde_json::from_slice(&line)
+                        .map_err(|e| VhqError::backend("Ollama", Some(status), format!("Failed to parse response: {}", e)))?;
+                    if let Some(error) = part.get("error").and_then(|v| v.as_str()) {
+                        return Err(VhqError::backend("Ollama", Some(status), error));
+                    }
+                    let token = part.get("response")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| VhqError::backend("Ollama", Some(status), "Invalid response"))?;
+
+                    text.push_str(token);
+                    tokens += 1;
+                    progress.report("generating", 5.0 + 90.0 * (tokens as f64 / num_predict as f64).min(1.0));
+                }
+            }
+
+            Ok::<_, VhqError>(text)
+        }, cancel)??;
 
         let generation_time = start_time.elapsed().as_secs_f64();
         
@@ -242,8 +321,13 @@ impl GhostAgent {
         let filename = format!("{}_{}.txt", content_type, timestamp);
         let file_path = format!("{}/{}", self.output_dir, filename);
         
-        std::fs::write(&file_path, generated_text)
+        progress.report("saving", 95.0);
+        let mut outputs = PartialOutputs::new();
+        outputs.track(&file_path);
+        std::fs::write(&file_path, &generated_text)
             .map_err(|e| anyhow::anyhow!("Failed to write generated content: {}", e))?;
+        cancellation::check(cancel)?;
+        outputs.keep();
 
         let result = GeneratedContent {
             id: uuid::Uuid::new_v4().to_string(),
@@ -278,7 +362,7 @@ impl GhostAgent {
         variables.insert("structure_type".to_string(), structure_type.to_string());
         
         let prompt = self.apply_template(&template, &variables)?;
-        let result = self.generate_content(&prompt, &self.default_model, "project_structure")?;
+        let result = self.generate_content(&prompt, &self.default_model, "project_structure", &CancellationToken::new(), &ProgressReporter::disabled())?;
         
         Ok(serde_json::to_string(&result)?)
     }
@@ -293,7 +377,7 @@ impl GhostAgent {
         variables.insert("optimization_type".to_string(), optimization_type.to_string());
         
         let prompt = self.apply_template(&template, &variables)?;
-        let result = self.generate_content(&prompt, &self.default_model, "optimized_content")?;
+        let result = self.generate_content(&prompt, &self.default_model, "optimized_content", &CancellationToken::new(), &ProgressReporter::disabled())?;
         
         Ok(serde_json::to_string(&result)?)
     }
@@ -302,18 +386,19 @@ impl GhostAgent {
         let client = reqwest::blocking::Client::new();
         let response = client.get(&format!("{}/api/tags", self.ollama_url))
             .send()
-            .map_err(|e| anyhow::anyhow!("Failed to get models: {}", e))?;
+            .map_err(|e| VhqError::request("Ollama", &e))?;
 
+        let status = response.status().as_u16();
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!("Failed to get models from Ollama"));
+            return Err(VhqError::backend("Ollama", Some(status), "Failed to get models").into());
         }
 
         let response_json: serde_json::Value = response.json()
-            .map_err(|e| anyhow::anyhow!("Failed to parse models response: {}", e))?;
+            .map_err(|e| VhqError::backend("Ollama", Some(status), format!("Failed to parse models response: {}", e)))?;
         
         let models = response_json.get("models")
             .and_then(|v| v.as_array())
-            .ok_or_else(|| anyhow::anyhow!("Invalid models response"))?
+            .ok_or_else(|| VhqError::backend("Ollama", Some(status), "Invalid models response"))?
             .iter()
             .filter_map(|model| model.get("name").and_then(|n| n.as_str()))
             .map(|s| s.to_string())
@@ -337,6 +422,7 @@ impl GhostAgent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::utils;
 
     #[test]
     fn test_ghost_lag_creation() {
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::ceo::{Task, TaskStatus};
+use crate::error::VhqError;
+
+/// A job made of several tasks wired together by dependencies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGraphRequest {
+    pub name: Option<String>,
+    pub nodes: Vec<TaskNodeRequest>,
+}
+
+/// One node of a task graph. `depends_on` refers to other nodes by `key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskNodeRequest {
+    pub key: String,
+    pub agent_name: String,
+    #[serde(default)]
+    pub file_path: Option<String>,
+    #[serde(default)]
+    pub options: serde_json::Value,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub priority: Option<i32>,
+}
+
+/// Progress of a single node in a submitted graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskNodeStatus {
+    pub key: String,
+    pub task_id: String,
+    pub agent_name: String,
+    pub status: TaskStatus,
+    pub depends_on: Vec<String>,
+    pub attempt: i32,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Progress of a whole graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGraphStatus {
+    pub graph_id: String,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub progress: f64,
+    pub finished: bool,
+    pub nodes: Vec<TaskNodeStatus>,
+}
+
+/// Check node keys are unique, dependencies exist and the graph has no cycles.
+/// Returns the node keys in a valid execution order.
+pub fn validate(request: &TaskGraphRequest) -> Result<Vec<String>> {
+    if request.nodes.is_empty() {
+        return Err(VhqError::invalid_input("Task graph has no nodes").into());
+    }
+
+    let mut keys = HashSet::new();
+    for node in &request.nodes {
+        if node.key.trim().is_empty() {
+            return Err(VhqError::invalid_input("Task graph node has an empty key").into());
+        }
+        if !keys.insert(node.key.as_str()) {
+            return Err(VhqError::invalid_input(format!("Duplicate task graph node: {}", node.key)).into());
+        }
+    }
+
+    let mut in_degree: HashMap<&str, usize> = HashMap::new();
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in &request.nodes {
+        in_degree.entry(node.key.as_str()).or_insert(0);
+        for parent in &node.depends_on {
+            if !keys.contains(parent.as_str()) {
+                return Err(VhqError::invalid_input(format!("Node {} depends on unknown node {}", node.key, parent)).into());
+            }
+            *in_degree.entry(node.key.as_str()).or_insert(0) += 1;
+            children.entry(parent.as_str()).or_default().push(node.key.as_str());
+        }
+    }
+
+    // Kahn's algorithm, seeded in declaration order so the result is stable
+    let mut ready: VecDeque<&str> = request.nodes.iter()
+        .map(|node| node.key.as_str())
+        .filter(|key| in_degree[key] == 0)
+        .collect();
+    let mut order = Vec::new();
+    while let Some(key) = ready.pop_front() {
+        order.push(key.to_string());
+        for child in children.get(key).into_iter().flatten() {
+            let degree = in_degree.get_mut(child).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push_back(child);
+            }
+        }
+    }
+
+    if order.len() != request.nodes.len() {
+        return Err(VhqError::invalid_input("Task graph contains a dependency cycle").into());
+    }
+
+    Ok(order)
+}
+
+/// Name a task is referred to by from its children: its node key inside a graph, its ID otherwise
+pub fn reference_name(task: &Task) -> &str {
+    task.node_key.as_deref().unwrap_or(&task.id)
+}
+
+/// Feed completed parents' results into a child's parameters.
+///
+/// Every parent result is exposed under `parameters.inputs.<parent>`. String
+/// parameters of the exact form `{{parent.field.path}}` are replaced by that
+/// field of the parent's result, e.g. `"file_path": "{{transcribe.srt_path}}"`.
+pub fn resolve_inputs(parameters: &serde_json::Value, parents: &[Task]) -> Result<serde_json::Value> {
+    let mut inputs = serde_json::Map::new();
+    for parent in parents {
+        let result = parent.result.as_deref().unwrap_or_default();
+        let value = serde_json::from_str(result)
+            .unwrap_or_else(|_| serde_json::Value::String(result.to_string()));
+        inputs.insert(reference_name(parent).to_string(), value);
+    }
+
+    let mut resolved = substitute(parameters, &inputs)?;
+    if let Some(object) = resolved.as_object_mut() {
+        object.insert("inputs".to_string(), serde_json::Value::Object(inputs));
+    }
+    Ok(resolved)
+}
+
+fn substitute(value: &serde_json::Value, inputs: &serde_json::Map<String, serde_json::Value>) -> Result<serde_json::Value> {
+    match value {
+        serde_json::Value::String(text) => {
+            let reference = match text.strip_prefix("{{").and_then(|t| t.strip_suffix("}}")) {
+                Some(reference) => reference.trim(),
+                None => return Ok(value.clone()),
+            };
+
+            let mut parts = reference.split('.');
+            let parent = parts.next().unwrap_or_default();
+            let mut current = inputs.get(parent)
+                .ok_or_else(|| VhqError::invalid_input(format!("Unknown parent in {}: {}", text, parent)))?;
+            for part in parts {
+                current = current.get(part)
+                    .ok_or_else(|| VhqError::invalid_input(format!("Parent output has no field {} in {}", part, text)))?;
+            }
+            Ok(current.clone())
+        }
+        serde_json::Value::Array(items) => {
+            Ok(serde_json::Value::Array(items.iter().map(|item| substitute(item, inputs)).collect::<Result<_>>()?))
+        }
+        serde_json::Value::Object(map) => {
+            let mut out = serde_json::Map::new();
+            for (key, item) in map {
+                out.insert(key.clone(), substitute(item, inputs)?);
+            }
+            Ok(serde_json::Value::Object(out))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(key: &str, depends_on: &[&str]) -> TaskNodeRequest {
+        TaskNodeRequest {
+            key: key.to_string(),
+            agent_name: "ghost_lag".to_string(),
+            file_path: None,
+            options: serde_json::json!({}),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_orders_nodes() {
+        let request = TaskGraphRequest {
+            name: None,
+            nodes: vec![node("srt", &["summarize"]), node("transcribe", &[]), node("summarize", &["transcribe"])],
+        };
+
+        assert_eq!(validate(&request).unwrap(), vec!["transcribe", "summarize", "srt"]);
+    }
+
+    #[test]
+    fn test_validate_rejects_cycles_and_unknown_parents() {
+        let cycle = TaskGraphRequest { name: None, nodes: vec![node("a", &["b"]), node("b", &["a"])] };
+        assert!(validate(&cycle).is_err());
+
+        let unknown = TaskGraphRequest { name: None, nodes: vec![node("a", &["missing"])] };
+        assert!(validate(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_resolve_inputs_substitutes_parent_fields() {
+        let mut parent: Task = serde_json::from_value(serde_json::json!({
+            "id": "1", "agent_name": "vitra_lag", "task_type": "file_processing",
+            "parameters": {}, "priority": 1, "status": "Completed", "created_at": "",
+            "started_at": null, "completed_at": null,
+            "result": "{\"srt_path\": \"talk.mp4.srt\"}", "error": null,
+            "attempt": 1, "max_attempts": 1, "backoff": {"type": "fixed", "delay_secs": 0},
+            "next_eligible_at": null, "depends_on": [], "graph_id": "g", "node_key": null
+        })).unwrap();
+        parent.node_key = Some("transcribe".to_string());
+
+        let parameters = serde_json::json!({"file_path": "{{transcribe.srt_path}}", "options": {}});
+        let resolved = resolve_inputs(&parameters, &[parent]).unwrap();
+
+        assert_eq!(resolved["file_path"], "talk.mp4.srt");
+        assert_eq!(resolved["inputs"]["transcribe"]["srt_path"], "talk.mp4.srt");
+    }
+}
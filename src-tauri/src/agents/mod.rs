@@ -1,6 +1,13 @@
 pub mod vitra;
 pub mod ghost;
 pub mod ceo;
+pub mod cancellation;
+pub mod dispatcher;
+pub mod graph;
+pub mod progress;
+pub mod retry;
+pub mod scheduler;
+pub mod templates;
 
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
@@ -8,6 +15,7 @@ use tracing::{info, error};
 
 use crate::commands::AgentStatus;
 use crate::database;
+use crate::error::VhqError;
 
 /// Common agent trait for all agents
 pub trait Agent {
@@ -48,7 +56,7 @@ impl AgentManager {
             
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Agent not found: {}", name))
+            Err(VhqError::NotFound { entity: "agent", id: name.to_string() }.into())
         }
     }
 
@@ -64,7 +72,7 @@ impl AgentManager {
             
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Agent not found: {}", name))
+            Err(VhqError::NotFound { entity: "agent", id: name.to_string() }.into())
         }
     }
 
@@ -77,7 +85,7 @@ impl AgentManager {
             info!("Processing file {} with agent {}", file_path, name);
             agent.process_file(file_path, options)
         } else {
-            Err(anyhow::anyhow!("Agent not found: {}", name))
+            Err(VhqError::NotFound { entity: "agent", id: name.to_string() }.into())
         }
     }
 
@@ -106,16 +114,17 @@ pub fn create_agent_status(
 pub mod utils {
     use std::path::Path;
     use anyhow::Result;
+    use crate::error::VhqError;
     use tracing::info;
 
     /// Validate file exists and is accessible
     pub fn validate_file(file_path: &str) -> Result<()> {
         let path = Path::new(file_path);
         if !path.exists() {
-            return Err(anyhow::anyhow!("File does not exist: {}", file_path));
+            return Err(VhqError::FileNotFound(file_path.to_string()).into());
         }
         if !path.is_file() {
-            return Err(anyhow::anyhow!("Path is not a file: {}", file_path));
+            return Err(VhqError::invalid_input(format!("Path is not a file: {}", file_path)).into());
         }
         Ok(())
     }
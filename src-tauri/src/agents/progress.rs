@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Tauri event carrying `TaskProgress` updates
+pub const PROGRESS_EVENT: &str = "task-progress";
+
+/// Minimum time between two updates that only move the percentage
+const MIN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Latest progress of a running task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub task_id: String,
+    /// 0 to 100
+    pub percent: f64,
+    pub stage: String,
+    /// Estimated seconds left, once there is enough progress to extrapolate from
+    pub eta_secs: Option<f64>,
+    pub updated_at: String,
+}
+
+type Listener = Arc<dyn Fn(&TaskProgress) + Send + Sync>;
+
+/// Collects progress of all running tasks and forwards it to a listener (the UI)
+#[derive(Clone, Default)]
+pub struct ProgressHub {
+    latest: Arc<Mutex<HashMap<String, TaskProgress>>>,
+    listener: Arc<Mutex<Option<Listener>>>,
+}
+
+impl std::fmt::Debug for ProgressHub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressHub")
+            .field("latest", &self.latest)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgressHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called with every progress update, e.g. to emit it as a Tauri event
+    pub fn set_listener(&self, listener: impl Fn(&TaskProgress) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    /// Reporter handed to the agent executing the given task
+    pub fn reporter(&self, task_id: &str) -> ProgressReporter {
+        ProgressReporter {
+            task_id: task_id.to_string(),
+            hub: Some(self.clone()),
+            started: Instant::now(),
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn get(&self, task_id: &str) -> Option<TaskProgress> {
+        self.latest.lock().unwrap().get(task_id).cloned()
+    }
+
+    /// Forget progress of tasks other than the given ones
+    pub fn retain(&self, task_ids: &[String]) {
+        self.latest.lock().unwrap().retain(|id, _| task_ids.contains(id));
+    }
+
+    fn publish(&self, progress: TaskProgress) {
+        self.latest.lock().unwrap().insert(progress.task_id.clone(), progress.clone());
+
+        let listener = self.listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener(&progress);
+        }
+    }
+}
+
+/// Handle agents use to report how far along their current task is
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    task_id: String,
+    hub: Option<ProgressHub>,
+    started: Instant,
+    /// Stage, percent and time of the last published update
+    last: Arc<Mutex<Option<(String, f64, Instant)>>>,
+}
+
+impl ProgressReporter {
+    /// Reporter that drops every update, for work not tracked as a task
+    pub fn disabled() -> Self {
+        Self {
+            task_id: String::new(),
+            hub: None,
+            started: Instant::now(),
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Report progress. Updates are throttled unless the stage changes or the task is done.
+    pub fn report(&self, stage: &str, percent: f64) {
+        let hub = match &self.hub {
+            Some(hub) => hub,
+            None => return,
+        };
+        let percent = percent.clamp(0.0, 100.0);
+        let now = Instant::now();
+
+        {
+            let mut last = self.last.lock().unwrap();
+            if let Some((last_stage, last_percent, at)) = last.as_ref() {
+                let same_stage = last_stage == stage;
+                let too_soon = now.duration_since(*at) < MIN_INTERVAL;
+                if same_stage && percent < 100.0 && (too_soon || (percent - last_percent).abs() < 0.5) {
+                    return;
+                }
+            }
+            *last = Some((stage.to_string(), percent, now));
+        }
+
+        hub.publish(TaskProgress {
+            task_id: self.task_id.clone(),
+            percent,
+            stage: stage.to_string(),
+            eta_secs: estimate_eta(self.started.elapsed(), percent),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// Report the final stage of a task that did not complete, keeping its last percentage
+    pub fn finish(&self, stage: &str) {
+        let hub = match &self.hub {
+            Some(hub) => hub,
+            None => return,
+        };
+        let percent = self.last.lock().unwrap().as_ref().map(|(_, percent, _)| *percent).unwrap_or(0.0);
+
+        hub.publish(TaskProgress {
+            task_id: self.task_id.clone(),
+            percent,
+            stage: stage.to_string(),
+            eta_secs: None,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+/// Linear extrapolation of the remaining time from the time spent so far
+fn estimate_eta(elapsed: Duration, percent: f64) -> Option<f64> {
+    if !(1.0..100.0).contains(&percent) {
+        return if percent >= 100.0 { Some(0.0) } else { None };
+    }
+    let elapsed = elapsed.as_secs_f64();
+    Some(elapsed * (100.0 - percent) / percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta_extrapolates_elapsed_time() {
+        assert_eq!(estimate_eta(Duration::from_secs(10), 25.0), Some(30.0));
+        assert_eq!(estimate_eta(Duration::from_secs(10), 0.5), None);
+        assert_eq!(estimate_eta(Duration::from_secs(10), 100.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_reporter_throttles_and_forwards() {
+        let hub = ProgressHub::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        hub.set_listener(move |progress| sink.lock().unwrap().push(progress.percent));
+
+        let reporter = hub.reporter("task-1");
+        reporter.report("transcribing", 10.0);
+        reporter.report("transcribing", 11.0);
+        reporter.report("generating_srt", 95.0);
+        reporter.report("generating_srt", 100.0);
+
+        assert_eq!(*received.lock().unwrap(), vec![10.0, 95.0, 100.0]);
+        assert_eq!(hub.get("task-1").unwrap().stage, "generating_srt");
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How long to wait before a failed task becomes eligible again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackoffStrategy {
+    /// Same delay before every retry
+    Fixed { delay_secs: u64 },
+    /// Delay doubles with each attempt, capped at `max_secs`
+    Exponential { base_secs: u64, max_secs: u64 },
+}
+
+impl Default for BackoffStrategy {
+    fn default() -> Self {
+        BackoffStrategy::Exponential { base_secs: 5, max_secs: 300 }
+    }
+}
+
+impl BackoffStrategy {
+    /// Delay before the retry that follows the given (1-based) failed attempt
+    pub fn delay(&self, attempt: i32) -> Duration {
+        let secs = match self {
+            BackoffStrategy::Fixed { delay_secs } => *delay_secs,
+            BackoffStrategy::Exponential { base_secs, max_secs } => {
+                let exponent = attempt.saturating_sub(1).clamp(0, 32) as u32;
+                base_secs.saturating_mul(2u64.saturating_pow(exponent)).min(*max_secs)
+            }
+        };
+        Duration::seconds(secs.min(i64::MAX as u64) as i64)
+    }
+
+    /// Time at which the next attempt may start
+    pub fn next_eligible_at(&self, attempt: i32, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + self.delay(attempt)
+    }
+}
+
+/// Default retry behaviour applied to tasks that don't specify their own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub backoff: BackoffStrategy,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: BackoffStrategy::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let backoff = BackoffStrategy::Exponential { base_secs: 5, max_secs: 60 };
+
+        assert_eq!(backoff.delay(1), Duration::seconds(5));
+        assert_eq!(backoff.delay(2), Duration::seconds(10));
+        assert_eq!(backoff.delay(4), Duration::seconds(40));
+        assert_eq!(backoff.delay(5), Duration::seconds(60));
+        assert_eq!(backoff.delay(100), Duration::seconds(60));
+    }
+
+    #[test]
+    fn test_fixed_backoff() {
+        let backoff = BackoffStrategy::Fixed { delay_secs: 30 };
+
+        assert_eq!(backoff.delay(1), Duration::seconds(30));
+        assert_eq!(backoff.delay(7), Duration::seconds(30));
+    }
+}
@@ -0,0 +1,454 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::{info, error};
+
+use super::ceo::Task;
+use super::retry::RetryPolicy;
+use crate::database::Database;
+use crate::error::VhqError;
+
+/// Runs closer than this to their due time are on schedule, older ones were missed
+const GRACE_PERIOD_SECS: i64 = 60;
+
+/// Upper bound on missed runs replayed by `CatchUpPolicy::RunAll`; the oldest ones are run
+const MAX_CATCH_UP_RUNS: usize = 24;
+
+/// How often due schedules are checked
+const TICK_SECS: u64 = 15;
+
+/// What to do with runs missed while the app was closed or the schedule was late
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop missed runs and wait for the next occurrence
+    Skip,
+    /// Run once for all missed occurrences
+    RunOnce,
+    /// Run every missed occurrence, up to a cap
+    RunAll,
+}
+
+impl CatchUpPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatchUpPolicy::Skip => "skip",
+            CatchUpPolicy::RunOnce => "run_once",
+            CatchUpPolicy::RunAll => "run_all",
+        }
+    }
+}
+
+impl FromStr for CatchUpPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "skip" => Ok(CatchUpPolicy::Skip),
+            "run_once" => Ok(CatchUpPolicy::RunOnce),
+            "run_all" => Ok(CatchUpPolicy::RunAll),
+            _ => Err(anyhow::anyhow!("Unknown catch-up policy: {}", value)),
+        }
+    }
+}
+
+/// A recurring (cron) or one-shot job that enqueues CEO tasks when due
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    /// Cron expression in local time, 5 fields (`min hour dom mon dow`) or 6 with seconds
+    pub cron_expression: Option<String>,
+    /// Due time of a one-shot schedule
+    pub run_at: Option<String>,
+    pub agent_name: String,
+    pub task_type: String,
+    pub parameters: serde_json::Value,
+    pub catch_up: CatchUpPolicy,
+    pub paused: bool,
+    /// None once a one-shot schedule has fired
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    pub name: String,
+    pub cron_expression: Option<String>,
+    pub run_at: Option<String>,
+    pub agent_name: String,
+    /// File to process, or a directory whose files are each processed
+    pub file_path: String,
+    #[serde(default)]
+    pub options: serde_json::Value,
+    pub catch_up: Option<CatchUpPolicy>,
+}
+
+/// Scheduler subsystem that turns due schedules into queued CEO tasks
+#[derive(Debug, Clone)]
+pub struct TaskScheduler {
+    pub db: Database,
+    pub retry_policy: RetryPolicy,
+}
+
+impl TaskScheduler {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn start(&self) -> Result<()> {
+        info!("Starting task scheduler");
+
+        let scheduler = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = scheduler.run_due_schedules(Utc::now()).await {
+                    error!("Failed to run due schedules: {}", e);
+                }
+
+                tokio::time::sleep(std::time::Duration::from_secs(TICK_SECS)).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    pub async fn create_schedule(&self, request: ScheduleRequest) -> Result<Schedule> {
+        let now = Utc::now();
+
+        let next_run_at = match (&request.cron_expression, &request.run_at) {
+            (Some(expression), None) => next_occurrence(&parse_cron(expression)?, now)
+                .ok_or_else(|| VhqError::invalid_input(format!("Cron expression never fires: {}", expression)))?,
+            (None, Some(run_at)) => DateTime::parse_from_rfc3339(run_at)
+                .map_err(|e| VhqError::invalid_input(format!("Invalid run_at {}: {}", run_at, e)))?
+                .with_timezone(&Utc),
+            _ => return Err(VhqError::invalid_input("Schedule needs exactly one of cron_expression or run_at").into()),
+        };
+
+        if !matches!(request.agent_name.as_str(), "vitra_lag" | "ghost_lag") {
+            return Err(VhqError::invalid_input(format!("Unknown agent: {}", request.agent_name)).into());
+        }
+
+        let schedule = Schedule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: request.name,
+            cron_expression: request.cron_expression,
+            run_at: request.run_at,
+            agent_name: request.agent_name,
+            task_type: "scheduled".to_string(),
+            parameters: serde_json::json!({
+                "file_path": request.file_path,
+                "options": request.options
+            }),
+            catch_up: request.catch_up.unwrap_or(CatchUpPolicy::RunOnce),
+            paused: false,
+            next_run_at: Some(next_run_at.to_rfc3339()),
+            last_run_at: None,
+            created_at: now.to_rfc3339(),
+        };
+
+        self.db.schedules().insert(&schedule).await?;
+        info!("Created schedule {} ({}), next run at {}", schedule.name, schedule.id, next_run_at);
+
+        Ok(schedule)
+    }
+
+    pub async fn list_schedules(&self) -> Result<Vec<Schedule>> {
+        Ok(self.db.schedules().list().await?)
+    }
+
+    /// Pause or resume a schedule. Resuming a cron schedule skips the paused period.
+    pub async fn set_paused(&self, schedule_id: &str, paused: bool) -> Result<Schedule> {
+        let mut schedule = self.db.schedules().get(schedule_id).await?
+            .ok_or_else(|| VhqError::NotFound { entity: "schedule", id: schedule_id.to_string() })?;
+
+        schedule.paused = paused;
+        if !paused {
+            if let Some(expression) = &schedule.cron_expression {
+                schedule.next_run_at = next_occurrence(&parse_cron(expression)?, Utc::now())
+                    .map(|next| next.to_rfc3339());
+            }
+        }
+
+        self.db.schedules().update(&schedule).await?;
+        info!("Schedule {} {}", schedule_id, if paused { "paused" } else { "resumed" });
+
+        Ok(schedule)
+    }
+
+    pub async fn delete_schedule(&self, schedule_id: &str) -> Result<()> {
+        if !self.db.schedules().delete(schedule_id).await? {
+            return Err(VhqError::NotFound { entity: "schedule", id: schedule_id.to_string() }.into());
+        }
+
+        info!("Deleted schedule {}", schedule_id);
+        Ok(())
+    }
+
+    /// Enqueue tasks for every schedule that is due and advance it to its next run
+    async fn run_due_schedules(&self, now: DateTime<Utc>) -> Result<()> {
+        for mut schedule in self.db.schedules().list().await? {
+            if schedule.paused {
+                continue;
+            }
+
+            let (mut runs, next_run_at) = match due_runs(&schedule, now) {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Schedule {} is invalid: {}", schedule.id, e);
+                    continue;
+                }
+            };
+            
+            // Every run over a directory queues all of its files, so missed runs collapse into the latest
+            if runs.len() > 1 && Path::new(Self::target(&schedule)).is_dir() {
+                runs.drain(..runs.len() - 1);
+            }
+
+            // Nothing changes until the schedule comes due
+            if next_run_at.map(|next| next.to_rfc3339()) == schedule.next_run_at {
+                continue;
+            }
+
+            // A failing schedule is retried on the next tick without holding up the others
+            let tasks: Result<Vec<Vec<Task>>> = runs.iter()
+                .map(|run| Self::build_tasks(&schedule, *run, &self.retry_policy))
+                .collect();
+            let tasks: Vec<Task> = match tasks {
+                Ok(tasks) => tasks.into_iter().flatten().collect(),
+                Err(e) => {
+                    error!("Schedule {} could not build its tasks: {}", schedule.id, e);
+                    continue;
+                }
+            };
+
+            if runs.is_empty() {
+                info!("Schedule {} skipped missed runs", schedule.name);
+            } else {
+                info!("Schedule {} fired {} run(s), queued {} task(s)", schedule.name, runs.len(), tasks.len());
+                schedule.last_run_at = Some(now.to_rfc3339());
+            }
+            schedule.next_run_at = next_run_at.map(|next| next.to_rfc3339());
+
+            if let Err(e) = self.db.schedules().record_run(&schedule, &tasks).await {
+                error!("Failed to record run of schedule {}: {}", schedule.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// File or directory the schedule's tasks process
+    fn target(schedule: &Schedule) -> &str {
+        schedule.parameters.get("file_path")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Tasks for one run: one for a file target, one per file for a directory target
+    fn build_tasks(schedule: &Schedule, run: DateTime<Utc>, retry_policy: &RetryPolicy) -> Result<Vec<Task>> {
+        let target = Self::target(schedule);
+
+        let files = if Path::new(target).is_dir() {
+            let mut files: Vec<String> = std::fs::read_dir(target)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![target.to_string()]
+        };
+
+        Ok(files.into_iter().map(|file_path| {
+            let mut parameters = schedule.parameters.clone();
+            parameters["file_path"] = serde_json::json!(file_path);
+            parameters["schedule_id"] = serde_json::json!(schedule.id);
+            parameters["scheduled_for"] = serde_json::json!(run.to_rfc3339());
+
+            Task::new(&schedule.agent_name, &schedule.task_type, parameters, retry_policy)
+        }).collect())
+    }
+}
+
+/// Parse a cron expression, accepting the common 5-field form without seconds
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule> {
+    let expression = expression.trim();
+    let full = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+
+    cron::Schedule::from_str(&full)
+        .map_err(|e| VhqError::invalid_input(format!("Invalid cron expression {}: {}", expression, e)).into())
+}
+
+/// First occurrence strictly after `after`, evaluated in local time
+pub fn next_occurrence(schedule: &cron::Schedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    schedule.after(&after.with_timezone(&Local)).next().map(|next| next.with_timezone(&Utc))
+}
+
+/// Latest occurrence at or before `at`, evaluated in local time
+pub fn previous_occurrence(schedule: &cron::Schedule, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    schedule.after(&(at + Duration::seconds(1)).with_timezone(&Local)).next_back().map(|previous| previous.with_timezone(&Utc))
+}
+
+/// Occurrences to fire now, and the due time that follows them
+pub type DueRuns = (Vec<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Runs to fire at `now` and the following due time, applying the catch-up policy
+pub fn due_runs(schedule: &Schedule, now: DateTime<Utc>) -> Result<DueRuns> {
+    let next = match &schedule.next_run_at {
+        Some(next) => DateTime::parse_from_rfc3339(next)?.with_timezone(&Utc),
+        None => return Ok((Vec::new(), None)),
+    };
+
+    if next > now {
+        return Ok((Vec::new(), Some(next)));
+    }
+
+    let grace = Duration::seconds(GRACE_PERIOD_SECS);
+    let missed = now - next > grace;
+    let cron = match &schedule.cron_expression {
+        Some(expression) => parse_cron(expression)?,
+        // A one-shot fires once, unless it was missed and missed runs are skipped
+        None if missed && schedule.catch_up == CatchUpPolicy::Skip => return Ok((Vec::new(), None)),
+        None => return Ok((vec![next], None)),
+    };
+
+    // Occurrences from `from` up to now. Only walked over bounded spans: a per-second
+    // cron has millions of occurrences after a long outage.
+    let occurrences = |from: DateTime<Utc>, limit: usize| -> Vec<DateTime<Utc>> {
+        std::iter::successors(Some(from), |at| next_occurrence(&cron, *at))
+            .take_while(|at| *at <= now)
+            .take(limit)
+            .collect()
+    };
+
+    let runs = if !missed {
+        occurrences(next, usize::MAX)
+    } else {
+        match schedule.catch_up {
+            CatchUpPolicy::Skip => next_occurrence(&cron, now - grace - Duration::seconds(1))
+                .map(|first| occurrences(first, usize::MAX))
+                .unwrap_or_default(),
+            CatchUpPolicy::RunOnce => vec![previous_occurrence(&cron, now).unwrap_or(next)],
+            CatchUpPolicy::RunAll => occurrences(next, MAX_CATCH_UP_RUNS),
+        }
+    };
+
+    Ok((runs, next_occurrence(&cron, now)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(cron_expression: Option<&str>, next_run_at: DateTime<Utc>, catch_up: CatchUpPolicy) -> Schedule {
+        Schedule {
+            id: "s".to_string(),
+            name: "test".to_string(),
+            cron_expression: cron_expression.map(String::from),
+            run_at: None,
+            agent_name: "ghost_lag".to_string(),
+            task_type: "scheduled".to_string(),
+            parameters: serde_json::json!({}),
+            catch_up,
+            paused: false,
+            next_run_at: Some(next_run_at.to_rfc3339()),
+            last_run_at: None,
+            created_at: next_run_at.to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_parse_cron_accepts_five_fields() {
+        assert!(parse_cron("0 2 * * *").is_ok());
+        assert!(parse_cron("0 0 2 * * *").is_ok());
+        assert!(parse_cron("not a cron").is_err());
+    }
+
+    #[test]
+    fn test_not_due_yet() {
+        let now = Utc::now();
+        let s = schedule(Some("0 * * * *"), now + Duration::minutes(5), CatchUpPolicy::RunAll);
+
+        let (runs, next) = due_runs(&s, now).unwrap();
+        assert!(runs.is_empty());
+        assert_eq!(next, Some(now + Duration::minutes(5)));
+    }
+
+    #[test]
+    fn test_missed_hourly_runs_follow_catch_up_policy() {
+        let now: DateTime<Utc> = "2026-03-02T12:30:00Z".parse().unwrap();
+        let first: DateTime<Utc> = "2026-03-02T07:00:00Z".parse().unwrap();
+
+        let (runs, next) = due_runs(&schedule(Some("0 * * * *"), first, CatchUpPolicy::Skip), now).unwrap();
+        assert!(runs.is_empty());
+        assert!(next.unwrap() > now);
+
+        let (runs, _) = due_runs(&schedule(Some("0 * * * *"), first, CatchUpPolicy::RunOnce), now).unwrap();
+        assert_eq!(runs, vec!["2026-03-02T12:00:00Z".parse::<DateTime<Utc>>().unwrap()]);
+
+        let (runs, _) = due_runs(&schedule(Some("0 * * * *"), first, CatchUpPolicy::RunAll), now).unwrap();
+        assert_eq!(runs.len(), 6);
+    }
+
+    #[test]
+    fn test_catch_up_stops_at_the_cap() {
+        let now: DateTime<Utc> = "2026-03-02T12:30:00Z".parse().unwrap();
+        // Every second for two months
+        let first: DateTime<Utc> = "2026-01-01T00:00:00Z".parse().unwrap();
+
+        let (runs, next) = due_runs(&schedule(Some("* * * * * *"), first, CatchUpPolicy::RunAll), now).unwrap();
+        assert_eq!(runs.len(), MAX_CATCH_UP_RUNS);
+        assert_eq!(runs[0], first);
+        assert_eq!(next, Some(now + Duration::seconds(1)));
+
+        let (runs, _) = due_runs(&schedule(Some("* * * * * *"), first, CatchUpPolicy::Skip), now).unwrap();
+        assert!(runs.iter().all(|run| now - *run <= Duration::seconds(GRACE_PERIOD_SECS)));
+        assert_eq!(runs.last(), Some(&now));
+    }
+
+    #[tokio::test]
+    async fn test_missed_runs_over_a_directory_queue_each_file_once() {
+        let dir = std::env::temp_dir().join(format!("vhq_schedule_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let db = Database::new(pool);
+        let now = Utc::now();
+        let mut missed = schedule(Some("0 * * * *"), now - Duration::hours(5), CatchUpPolicy::RunAll);
+        missed.parameters = serde_json::json!({ "file_path": dir.to_string_lossy() });
+        db.schedules().insert(&missed).await.unwrap();
+
+        TaskScheduler::new(db.clone()).run_due_schedules(now).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let queued = db.tasks().list_by_status(crate::agents::ceo::TaskStatus::Pending).await.unwrap();
+        assert_eq!(queued.len(), 2);
+    }
+
+    #[test]
+    fn test_one_shot_fires_once() {
+        let now = Utc::now();
+        let s = schedule(None, now - Duration::seconds(10), CatchUpPolicy::Skip);
+
+        let (runs, next) = due_runs(&s, now).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(next.is_none());
+    }
+}
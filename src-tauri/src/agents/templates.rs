@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::ghost::ContentTemplate;
+
+/// GHOST content templates, one JSON file per template, keyed by file name without `.json`
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSet {
+    templates: HashMap<String, ContentTemplate>,
+}
+
+impl TemplateSet {
+    /// Load every template in `dir`. A missing directory gives an empty set.
+    /// Returns the valid templates and a message per file that could not be used.
+    pub fn load(dir: &Path) -> (Self, Vec<String>) {
+        let mut set = TemplateSet::default();
+        let mut errors = Vec::new();
+
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (set, errors),
+            Err(e) => {
+                errors.push(format!("Failed to read templates directory {}: {}", dir.display(), e));
+                return (set, errors);
+            }
+        };
+
+        let mut paths: Vec<_> = entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            match load_template(&path) {
+                Ok(template) => {
+                    set.templates.insert(name, template);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        (set, errors)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ContentTemplate> {
+        self.templates.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+}
+
+fn load_template(path: &Path) -> Result<ContentTemplate, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let template: ContentTemplate = serde_json::from_str(&text).map_err(|e| format!("invalid template JSON: {}", e))?;
+
+    if template.prompt_template.trim().is_empty() {
+        return Err("prompt_template is empty".to_string());
+    }
+    let unused: Vec<&str> = template.variables.iter()
+        .map(String::as_str)
+        .filter(|variable| !template.prompt_template.contains(&format!("{{{}}}", variable)))
+        .collect();
+    if !unused.is_empty() {
+        return Err(format!("variables not used in prompt_template: {}", unused.join(", ")));
+    }
+
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loads_valid_templates_and_reports_invalid_ones() {
+        let dir = std::env::temp_dir().join(format!("vhq-templates-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("blog.json"), r#"{
+            "name": "Blog post", "prompt_template": "Write a blog post about {content}",
+            "variables": ["content"], "content_type": "article", "description": "Blog"
+        }"#).unwrap();
+        std::fs::write(dir.join("broken.json"), "{ not json").unwrap();
+        std::fs::write(dir.join("unused.json"), r#"{
+            "name": "Unused", "prompt_template": "Summarize", "variables": ["content"],
+            "content_type": "summary", "description": ""
+        }"#).unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let (set, errors) = TemplateSet::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get("blog").unwrap().name, "Blog post");
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].contains("broken.json"));
+        assert!(errors[1].contains("variables not used in prompt_template: content"));
+    }
+
+    #[test]
+    fn test_missing_directory_is_empty() {
+        let (set, errors) = TemplateSet::load(Path::new("does-not-exist-templates"));
+
+        assert_eq!(set.len(), 0);
+        assert!(errors.is_empty());
+    }
+}
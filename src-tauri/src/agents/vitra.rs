@@ -3,8 +3,16 @@ use tracing::info;
 use std::path::Path;
 use std::process::Command;
 use crate::commands::AgentStatus;
+use super::cancellation::{self, CancellationToken, PartialOutputs};
+use super::progress::ProgressReporter;
+use crate::config::VitraConfig;
+use crate::error::VhqError;
+use crate::metrics::ChildProcesses;
 use serde::{Deserialize, Serialize};
 
+/// Where whisper writes its transcripts
+pub const TRANSCRIPTS_DIR: &str = "transcripts";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionResult {
     pub file_path: String,
@@ -19,8 +27,8 @@ pub struct TranscriptionResult {
 #[derive(Debug, Clone)]
 pub struct VitraAgent {
     pub status: String,
-    pub memory_usage: i64,
-    pub cpu_usage: f64,
+    /// whisper processes, shared by every clone of this agent
+    pub children: ChildProcesses,
     pub last_activity: String,
     pub whisper_model: String,
     pub supported_languages: Vec<String>,
@@ -28,12 +36,15 @@ pub struct VitraAgent {
 
 impl VitraAgent {
     pub fn new() -> Self {
+        Self::with_config(&VitraConfig::default())
+    }
+
+    pub fn with_config(config: &VitraConfig) -> Self {
         Self {
             status: "stopped".to_string(),
-            memory_usage: 0,
-            cpu_usage: 0.0,
+            children: ChildProcesses::default(),
             last_activity: chrono::Utc::now().to_rfc3339(),
-            whisper_model: "base".to_string(),
+            whisper_model: config.whisper_model.clone(),
             supported_languages: vec![
                 "en".to_string(), "es".to_string(), "fr".to_string(), 
                 "de".to_string(), "it".to_string(), "pt".to_string(),
@@ -64,21 +75,35 @@ impl VitraAgent {
     }
 
     pub fn get_status(&self) -> AgentStatus {
+        let usage = self.children.sample();
         AgentStatus {
             name: "VITRA_LAG".to_string(),
             status: self.status.clone(),
             last_activity: self.last_activity.clone(),
-            memory_usage: self.memory_usage,
-            cpu_usage: self.cpu_usage,
+            memory_usage: usage.memory_bytes as i64,
+            cpu_usage: usage.cpu_percent,
         }
     }
 
     pub fn process_file(&self, file_path: &str, options: serde_json::Value) -> Result<String> {
+        self.process_file_cancellable(file_path, options, &CancellationToken::new(), &ProgressReporter::disabled())
+    }
+
+    /// Process a file, reporting progress as whisper emits segments. Stops whisper
+    /// and removes partial outputs once `cancel` fires.
+    pub fn process_file_cancellable(
+        &self,
+        file_path: &str,
+        options: serde_json::Value,
+        cancel: &CancellationToken,
+        progress: &ProgressReporter,
+    ) -> Result<String> {
         info!("VITRA LAG processing file: {}", file_path);
+        progress.report("preparing", 0.0);
         
         // Validate file exists
         if !Path::new(file_path).exists() {
-            return Err(anyhow::anyhow!("File not found: {}", file_path));
+            return Err(VhqError::FileNotFound(file_path.to_string()).into());
         }
 
         // Extract options
@@ -91,14 +116,21 @@ impl VitraAgent {
             .unwrap_or(&self.whisper_model);
 
         // Perform transcription
-        let result = self.transcribe_audio(file_path, language, model)?;
+        let mut outputs = PartialOutputs::new();
+        let result = self.transcribe_audio(file_path, language, model, cancel, progress, &mut outputs)?;
         
         // Generate SRT if requested
         let srt_path = if options.get("generate_srt").and_then(|v| v.as_bool()).unwrap_or(false) {
+            cancellation::check(cancel)?;
+            progress.report("generating_srt", 95.0);
+            outputs.track(format!("{}.srt", result.file_path));
             Some(self.generate_srt(&result)?)
         } else {
             None
         };
+        
+        cancellation::check(cancel)?;
+        outputs.keep();
 
         // Save result to database
         let final_result = TranscriptionResult {
@@ -124,39 +156,76 @@ impl VitraAgent {
         let output = Command::new("whisper")
             .arg("--help")
             .output()
-            .map_err(|_| anyhow::anyhow!("whisper.cpp not found. Please install whisper.cpp"))?;
+            .map_err(|e| VhqError::tool_missing("whisper", format!("{}. Please install whisper.cpp", e)))?;
         
         if !output.status.success() {
-            return Err(anyhow::anyhow!("whisper.cpp not working properly"));
+            return Err(VhqError::tool_failed("whisper", "not working properly").into());
         }
         
         info!("Whisper model initialized successfully");
         Ok(())
     }
 
-    fn transcribe_audio(&self, file_path: &str, language: &str, model: &str) -> Result<TranscriptionResult> {
+    fn transcribe_audio(
+        &self,
+        file_path: &str,
+        language: &str,
+        model: &str,
+        cancel: &CancellationToken,
+        progress: &ProgressReporter,
+        outputs: &mut PartialOutputs,
+    ) -> Result<TranscriptionResult> {
         info!("Transcribing audio file: {} with model: {}", file_path, model);
         
         let start_time = std::time::Instant::now();
         
         // Build whisper command
         let mut cmd = Command::new("whisper");
-        cmd.arg(file_path)
+        cmd.env("PYTHONUNBUFFERED", "1")
+            .arg(file_path)
+            .arg("--verbose").arg("True")
             .arg("--model").arg(model)
-            .arg("--output_dir").arg("transcripts")
+            .arg("--output_dir").arg(TRANSCRIPTS_DIR)
             .arg("--output_format").arg("json");
         
         if language != "auto" {
             cmd.arg("--language").arg(language);
         }
         
-        // Execute transcription
-        let output = cmd.output()
-            .map_err(|e| anyhow::anyhow!("Failed to execute whisper: {}", e))?;
+        // whisper names its output after the input file
+        if let Some(stem) = Path::new(file_path).file_stem() {
+            outputs.track(Path::new(TRANSCRIPTS_DIR).join(format!("{}.json", stem.to_string_lossy())));
+        }
+        
+        // Execute transcription, killing whisper if the task is cancelled
+        // Segment timestamps against the media length tell how far whisper got
+        let media_duration = media_duration(file_path);
+        progress.report("transcribing", 5.0);
+        let reporter = progress.clone();
+        let on_line = move |line: &str| {
+            if let (Some(end), Some(total)) = (segment_end(line), media_duration) {
+                reporter.report("transcribing", 5.0 + 85.0 * (end / total).min(1.0));
+            }
+        };
+        
+        let output = cancellation::command_output(&mut cmd, cancel, &self.children, on_line)
+            .map_err(|e| if cancel.is_cancelled() {
+                cancellation::cancelled()
+            } else if e.kind() == std::io::ErrorKind::NotFound {
+                VhqError::tool_missing("whisper", e).into()
+            } else if crate::error::is_locked(&e) {
+                VhqError::FileLocked(e.to_string()).into()
+            } else {
+                VhqError::tool_crashed("whisper", e).into()
+            })?;
         
         if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("Transcription failed: {}", error));
+            let error = format!("Transcription failed: {}", String::from_utf8_lossy(&output.stderr));
+            // Killed by a signal rather than exiting on bad input or a bad model
+            return Err(match output.status.code() {
+                None => VhqError::tool_crashed("whisper", error),
+                Some(_) => VhqError::tool_failed("whisper", error),
+            }.into());
         }
         
         let duration = start_time.elapsed().as_secs_f64();
@@ -208,6 +277,13 @@ impl VitraAgent {
         self.supported_languages.clone()
     }
 
+    /// Take over changed settings; running transcriptions keep the model they started with
+    pub fn apply_config(&mut self, config: &VitraConfig) {
+        if self.whisper_model != config.whisper_model {
+            self.set_model(config.whisper_model.clone());
+        }
+    }
+
     pub fn set_model(&mut self, model: String) {
         let model_clone = model.clone();
         self.whisper_model = model;
@@ -222,4 +298,35 @@ impl VitraAgent {
             "last_activity": self.last_activity
         })
     }
-} 
\ No newline at end of file
+}
+
+/// Length of a media file in seconds, if ffprobe is available
+fn media_duration(file_path: &str) -> Option<f64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(file_path)
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok().filter(|d: &f64| *d > 0.0)
+}
+
+/// End time in seconds of a whisper segment line like `[01:02.000 --> 01:05.500]  text`
+fn segment_end(line: &str) -> Option<f64> {
+    let range = line.trim_start().strip_prefix('[')?.split(']').next()?;
+    let end = range.split("-->").nth(1)?.trim();
+
+    end.split(':').try_fold(0.0, |total, part| Some(total * 60.0 + part.parse::<f64>().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_end_parses_whisper_timestamps() {
+        assert_eq!(segment_end("[00:05.000 --> 01:02.500]  Hello there"), Some(62.5));
+        assert_eq!(segment_end("[01:00:00.000 --> 01:00:10.000]  Later"), Some(3610.0));
+        assert_eq!(segment_end("Detecting language using up to the first 30 seconds."), None);
+    }
+}
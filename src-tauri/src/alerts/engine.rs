@@ -0,0 +1,484 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn, error};
+
+use super::sinks::AlertNotifier;
+use super::{Alert, AlertMetric, AlertRule, AlertRuleRequest, AlertSeverity, AlertSink, AlertState};
+use crate::agents::ceo::TaskStatus;
+use crate::database::Database;
+use crate::error::VhqError;
+use crate::health::HealthThresholds;
+use crate::metrics::{self, SystemSampler, SystemSnapshot};
+
+/// How often rules are evaluated
+const TICK_SECS: u64 = 15;
+
+/// Measurements rules are evaluated against
+#[derive(Debug, Clone, Default)]
+pub struct MetricSnapshot {
+    pub system: SystemSnapshot,
+    pub queue_depth: i64,
+    /// Failure rate per agent, only for agents with enough recently finished tasks
+    pub failure_rates: HashMap<String, f64>,
+}
+
+impl MetricSnapshot {
+    /// Current value of the rule's metric, None when it cannot be measured right now
+    pub fn value(&self, rule: &AlertRule) -> Option<f64> {
+        match rule.metric {
+            AlertMetric::MemoryPercent => {
+                let memory = self.system.memory;
+                (memory.total_bytes > 0).then(|| memory.used_bytes as f64 / memory.total_bytes as f64 * 100.0)
+            }
+            AlertMetric::CpuPercent => Some(self.system.cpu_percent),
+            AlertMetric::DiskFreeBytes => self.system.disks.iter().map(|disk| disk.available_bytes).min().map(|bytes| bytes as f64),
+            AlertMetric::QueueDepth => Some(self.queue_depth as f64),
+            AlertMetric::FailureRate => self.failure_rates.get(rule.agent_name.as_deref()?).copied(),
+        }
+    }
+}
+
+/// What an evaluation does to a rule's alert
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transition {
+    None,
+    Fire,
+    Resolve,
+}
+
+/// Evaluates alert rules against system metrics, the task queue and task outcomes,
+/// and routes the resulting alerts to their sinks. Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct AlertEngine {
+    db: Database,
+    pub notifier: AlertNotifier,
+    sampler: Arc<Mutex<SystemSampler>>,
+    /// Since when each rule has been breached without an open alert
+    pending: Arc<Mutex<HashMap<String, DateTime<Utc>>>>,
+    thresholds: Arc<Mutex<HealthThresholds>>,
+}
+
+impl AlertEngine {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            notifier: AlertNotifier::default(),
+            sampler: Arc::new(Mutex::new(SystemSampler::new(metrics::monitored_dirs(&[])))),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            thresholds: Arc::new(Mutex::new(HealthThresholds::default())),
+        }
+    }
+
+    /// Failure-rate window and sample size used from the next evaluation on
+    pub fn set_thresholds(&self, thresholds: HealthThresholds) {
+        *self.thresholds.lock().unwrap() = thresholds;
+    }
+
+    /// Filesystems `DiskFreeBytes` rules look at
+    pub fn set_monitored_dirs(&self, dirs: Vec<PathBuf>) {
+        self.sampler.lock().unwrap().set_dirs(dirs);
+    }
+
+    pub fn start(&self) -> Result<()> {
+        info!("Starting alert engine");
+
+        let engine = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = engine.evaluate(Utc::now()).await {
+                    error!("Failed to evaluate alert rules: {}", e);
+                }
+
+                tokio::time::sleep(std::time::Duration::from_secs(TICK_SECS)).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Evaluate every rule once, firing and resolving alerts as needed
+    pub async fn evaluate(&self, now: DateTime<Utc>) -> Result<()> {
+        let rules = self.db.alerts().list_rules().await?;
+        let snapshot = self.snapshot(now).await?;
+        let mut open: HashMap<String, Alert> = self.db.alerts().open_alerts().await?
+            .into_iter()
+            .map(|alert| (alert.rule_id.clone(), alert))
+            .collect();
+
+        for rule in &rules {
+            let value = match snapshot.value(rule).filter(|_| rule.enabled) {
+                Some(value) => value,
+                None => {
+                    // Disabled or no longer measurable, so nothing keeps its alert firing
+                    self.pending.lock().unwrap().remove(&rule.id);
+                    if let Some(alert) = open.remove(&rule.id) {
+                        self.close(alert, rule, now).await?;
+                    }
+                    continue;
+                }
+            };
+
+            let transition = {
+                let mut pending = self.pending.lock().unwrap();
+                transition(rule, value, open.contains_key(&rule.id), &mut pending, now)
+            };
+
+            match transition {
+                Transition::None => {}
+                Transition::Fire => {
+                    let alert = Alert {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        severity: rule.severity,
+                        state: AlertState::Firing,
+                        value,
+                        message: message(rule, value),
+                        fired_at: now.to_rfc3339(),
+                        acknowledged_at: None,
+                        resolved_at: None,
+                    };
+                    warn!("Alert fired: {}", alert.message);
+                    self.db.alerts().insert_alert(&alert).await?;
+                    self.notifier.deliver(&alert, &rule.sinks).await;
+                }
+                Transition::Resolve => {
+                    if let Some(alert) = open.remove(&rule.id) {
+                        self.close(alert, rule, now).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn close(&self, mut alert: Alert, rule: &AlertRule, now: DateTime<Utc>) -> Result<()> {
+        info!("Alert resolved: {}", rule.name);
+        alert.state = AlertState::Resolved;
+        alert.resolved_at = Some(now.to_rfc3339());
+        self.db.alerts().update_alert(&alert).await?;
+        self.notifier.deliver(&alert, &rule.sinks).await;
+        Ok(())
+    }
+
+    async fn snapshot(&self, now: DateTime<Utc>) -> Result<MetricSnapshot> {
+        let system = self.sampler.lock().unwrap().sample();
+
+        let queue_depth = self.db.tasks().count_by_status().await?
+            .get(TaskStatus::Pending.as_str())
+            .copied()
+            .unwrap_or(0);
+
+        let thresholds = self.thresholds.lock().unwrap().clone();
+        let since = now - Duration::seconds(thresholds.failure_window_secs);
+        let failure_rates = self.db.tasks().outcomes_since(&since.to_rfc3339()).await?
+            .into_iter()
+            .filter(|(_, (finished, _))| *finished >= thresholds.failure_min_tasks)
+            .map(|(agent, (finished, failed))| (agent, failed as f64 / finished as f64))
+            .collect();
+
+        Ok(MetricSnapshot { system, queue_depth, failure_rates })
+    }
+
+    pub async fn list_rules(&self) -> Result<Vec<AlertRule>> {
+        Ok(self.db.alerts().list_rules().await?)
+    }
+
+    pub async fn create_rule(&self, request: AlertRuleRequest) -> Result<AlertRule> {
+        validate(&request)?;
+
+        let rule = AlertRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: request.name,
+            metric: request.metric,
+            agent_name: request.agent_name,
+            comparison: request.comparison,
+            threshold: request.threshold,
+            duration_secs: request.duration_secs,
+            severity: request.severity.unwrap_or(AlertSeverity::Warning),
+            sinks: if request.sinks.is_empty() { vec![AlertSink::Event] } else { request.sinks },
+            enabled: request.enabled.unwrap_or(true),
+            created_at: Utc::now().to_rfc3339(),
+        };
+
+        self.db.alerts().insert_rule(&rule).await?;
+        info!("Created alert rule {} ({})", rule.name, rule.id);
+
+        Ok(rule)
+    }
+
+    /// Replace a rule's definition. Its open alert, if any, resolves once the new condition clears.
+    pub async fn update_rule(&self, rule_id: &str, request: AlertRuleRequest) -> Result<AlertRule> {
+        validate(&request)?;
+
+        let mut rule = self.db.alerts().get_rule(rule_id).await?
+            .ok_or_else(|| VhqError::NotFound { entity: "alert rule", id: rule_id.to_string() })?;
+
+        rule.name = request.name;
+        rule.metric = request.metric;
+        rule.agent_name = request.agent_name;
+        rule.comparison = request.comparison;
+        rule.threshold = request.threshold;
+        rule.duration_secs = request.duration_secs;
+        rule.severity = request.severity.unwrap_or(rule.severity);
+        if !request.sinks.is_empty() {
+            rule.sinks = request.sinks;
+        }
+        rule.enabled = request.enabled.unwrap_or(rule.enabled);
+
+        self.db.alerts().update_rule(&rule).await?;
+        self.pending.lock().unwrap().remove(rule_id);
+
+        Ok(rule)
+    }
+
+    /// Delete a rule and resolve its open alert
+    pub async fn delete_rule(&self, rule_id: &str) -> Result<()> {
+        if !self.db.alerts().delete_rule(rule_id).await? {
+            return Err(VhqError::NotFound { entity: "alert rule", id: rule_id.to_string() }.into());
+        }
+        self.pending.lock().unwrap().remove(rule_id);
+
+        let now = Utc::now().to_rfc3339();
+        for mut alert in self.db.alerts().open_alerts().await? {
+            if alert.rule_id == rule_id {
+                alert.state = AlertState::Resolved;
+                alert.resolved_at = Some(now.clone());
+                self.db.alerts().update_alert(&alert).await?;
+            }
+        }
+
+        info!("Deleted alert rule {}", rule_id);
+        Ok(())
+    }
+
+    /// Open alerts, or the whole history (newest first) when `include_resolved` is set
+    pub async fn list_alerts(&self, include_resolved: bool, limit: i64) -> Result<Vec<Alert>> {
+        if include_resolved {
+            Ok(self.db.alerts().history(limit).await?)
+        } else {
+            Ok(self.db.alerts().open_alerts().await?)
+        }
+    }
+
+    /// Mark a firing alert as seen. It stays open until its condition clears.
+    pub async fn acknowledge(&self, alert_id: &str) -> Result<Alert> {
+        let mut alert = self.get_alert(alert_id).await?;
+        if alert.state != AlertState::Firing {
+            return Err(VhqError::invalid_input(format!("Alert {} is {}", alert_id, alert.state.as_str())).into());
+        }
+
+        alert.state = AlertState::Acknowledged;
+        alert.acknowledged_at = Some(Utc::now().to_rfc3339());
+        self.db.alerts().update_alert(&alert).await?;
+        self.notify(&alert).await?;
+
+        Ok(alert)
+    }
+
+    /// Close an alert by hand. If its condition still holds, the rule fires again
+    /// after another `duration_secs`.
+    pub async fn resolve(&self, alert_id: &str) -> Result<Alert> {
+        let mut alert = self.get_alert(alert_id).await?;
+        if !alert.is_open() {
+            return Err(VhqError::invalid_input(format!("Alert {} is already resolved", alert_id)).into());
+        }
+
+        alert.state = AlertState::Resolved;
+        alert.resolved_at = Some(Utc::now().to_rfc3339());
+        self.db.alerts().update_alert(&alert).await?;
+        self.pending.lock().unwrap().remove(&alert.rule_id);
+        self.notify(&alert).await?;
+
+        Ok(alert)
+    }
+
+    async fn get_alert(&self, alert_id: &str) -> Result<Alert> {
+        Ok(self.db.alerts().get_alert(alert_id).await?
+            .ok_or_else(|| VhqError::NotFound { entity: "alert", id: alert_id.to_string() })?)
+    }
+
+    /// Route a state change made by the user to the alert's rule sinks
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        if let Some(rule) = self.db.alerts().get_rule(&alert.rule_id).await? {
+            self.notifier.deliver(alert, &rule.sinks).await;
+        }
+        Ok(())
+    }
+}
+
+/// Decide whether a rule fires or resolves. A breach must last `duration_secs` before
+/// firing, and a rule with an open alert never fires a second one.
+fn transition(
+    rule: &AlertRule,
+    value: f64,
+    open: bool,
+    pending: &mut HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Transition {
+    if !rule.comparison.breached(value, rule.threshold) {
+        pending.remove(&rule.id);
+        return if open { Transition::Resolve } else { Transition::None };
+    }
+
+    if open {
+        return Transition::None;
+    }
+
+    let since = *pending.entry(rule.id.clone()).or_insert(now);
+    if now - since >= Duration::seconds(rule.duration_secs) {
+        pending.remove(&rule.id);
+        Transition::Fire
+    } else {
+        Transition::None
+    }
+}
+
+fn message(rule: &AlertRule, value: f64) -> String {
+    let subject = match &rule.agent_name {
+        Some(agent) => format!("{} {}", agent, rule.metric.as_str()),
+        None => rule.metric.as_str().to_string(),
+    };
+    format!(
+        "{}: {} is {} ({} {})",
+        rule.name,
+        subject,
+        rule.metric.format(value),
+        rule.comparison.as_str(),
+        rule.metric.format(rule.threshold)
+    )
+}
+
+fn validate(request: &AlertRuleRequest) -> Result<()> {
+    if request.name.trim().is_empty() {
+        return Err(VhqError::invalid_input("Alert rule needs a name").into());
+    }
+    if !request.threshold.is_finite() {
+        return Err(VhqError::invalid_input("Alert threshold must be a number").into());
+    }
+    if request.duration_secs < 0 {
+        return Err(VhqError::invalid_input("Alert duration cannot be negative").into());
+    }
+    if request.metric == AlertMetric::FailureRate
+        && !matches!(request.agent_name.as_deref(), Some("vitra_lag" | "ghost_lag"))
+    {
+        return Err(VhqError::invalid_input("Failure rate rules need agent_name vitra_lag or ghost_lag").into());
+    }
+    for sink in &request.sinks {
+        if let AlertSink::Webhook { url } = sink {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(VhqError::invalid_input(format!("Invalid webhook URL: {}", url)).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::Comparison;
+
+    fn memory_rule(duration_secs: i64) -> AlertRule {
+        AlertRule {
+            id: "memory".to_string(),
+            name: "High memory usage".to_string(),
+            metric: AlertMetric::MemoryPercent,
+            agent_name: None,
+            comparison: Comparison::Above,
+            threshold: 80.0,
+            duration_secs,
+            severity: AlertSeverity::Warning,
+            sinks: vec![AlertSink::Event],
+            enabled: true,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_fires_after_breach_lasts_duration() {
+        let rule = memory_rule(120);
+        let mut pending = HashMap::new();
+        let start = Utc::now();
+
+        assert_eq!(transition(&rule, 85.0, false, &mut pending, start), Transition::None);
+        assert_eq!(transition(&rule, 90.0, false, &mut pending, start + Duration::seconds(60)), Transition::None);
+        assert_eq!(transition(&rule, 90.0, false, &mut pending, start + Duration::seconds(120)), Transition::Fire);
+
+        // A dip below the threshold restarts the clock
+        assert_eq!(transition(&rule, 85.0, false, &mut pending, start + Duration::seconds(200)), Transition::None);
+        assert_eq!(transition(&rule, 70.0, false, &mut pending, start + Duration::seconds(215)), Transition::None);
+        assert_eq!(transition(&rule, 85.0, false, &mut pending, start + Duration::seconds(320)), Transition::None);
+    }
+
+    #[test]
+    fn test_open_alert_is_not_duplicated_and_resolves() {
+        let rule = memory_rule(0);
+        let mut pending = HashMap::new();
+        let now = Utc::now();
+
+        assert_eq!(transition(&rule, 95.0, false, &mut pending, now), Transition::Fire);
+        assert_eq!(transition(&rule, 95.0, true, &mut pending, now), Transition::None);
+        assert_eq!(transition(&rule, 50.0, true, &mut pending, now), Transition::Resolve);
+        assert_eq!(transition(&rule, 50.0, false, &mut pending, now), Transition::None);
+    }
+
+    #[test]
+    fn test_failure_rate_needs_agent_sample() {
+        let mut rule = memory_rule(0);
+        rule.metric = AlertMetric::FailureRate;
+        rule.agent_name = Some("ghost_lag".to_string());
+        rule.name = "GHOST failure rate".to_string();
+        rule.threshold = 0.2;
+
+        let mut snapshot = MetricSnapshot::default();
+        assert_eq!(snapshot.value(&rule), None);
+
+        snapshot.failure_rates.insert("ghost_lag".to_string(), 0.25);
+        assert_eq!(snapshot.value(&rule), Some(0.25));
+        assert_eq!(
+            message(&rule, 0.25),
+            "GHOST failure rate: ghost_lag failure_rate is 25% (above 20%)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unmeasurable_or_disabled_rules_resolve_open_alerts() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let engine = AlertEngine::new(Database::new(pool));
+
+        let mut failure_rate = memory_rule(0);
+        failure_rate.id = "ghost".to_string();
+        failure_rate.metric = AlertMetric::FailureRate;
+        failure_rate.agent_name = Some("ghost_lag".to_string());
+        let mut disabled = memory_rule(0);
+        disabled.enabled = false;
+
+        for rule in [&failure_rate, &disabled] {
+            engine.db.alerts().insert_rule(rule).await.unwrap();
+            engine.db.alerts().insert_alert(&Alert {
+                id: format!("{}-alert", rule.id),
+                rule_id: rule.id.clone(),
+                rule_name: rule.name.clone(),
+                severity: rule.severity,
+                state: AlertState::Firing,
+                value: 0.5,
+                message: String::new(),
+                fired_at: Utc::now().to_rfc3339(),
+                acknowledged_at: None,
+                resolved_at: None,
+            }).await.unwrap();
+        }
+
+        // No GHOST tasks finished recently, so its failure rate cannot be measured
+        engine.evaluate(Utc::now()).await.unwrap();
+
+        assert!(engine.db.alerts().open_alerts().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub mod engine;
+pub mod sinks;
+
+pub use engine::AlertEngine;
+
+/// Tauri event carrying an `Alert` whenever it fires or changes state
+pub const ALERT_EVENT: &str = "alert";
+
+/// Measurement a rule watches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Used share of physical memory, 0 to 100
+    MemoryPercent,
+    /// Machine-wide CPU usage, 0 to 100
+    CpuPercent,
+    /// Available bytes on the fullest monitored filesystem
+    DiskFreeBytes,
+    /// Tasks waiting to run
+    QueueDepth,
+    /// Share of failed tasks (0 to 1) among the recently finished ones of `agent_name`
+    FailureRate,
+}
+
+impl AlertMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertMetric::MemoryPercent => "memory_percent",
+            AlertMetric::CpuPercent => "cpu_percent",
+            AlertMetric::DiskFreeBytes => "disk_free_bytes",
+            AlertMetric::QueueDepth => "queue_depth",
+            AlertMetric::FailureRate => "failure_rate",
+        }
+    }
+
+    /// Value formatted for alert messages
+    fn format(&self, value: f64) -> String {
+        match self {
+            AlertMetric::MemoryPercent | AlertMetric::CpuPercent => format!("{:.1}%", value),
+            AlertMetric::DiskFreeBytes => format!("{:.2} GB", value / 1_073_741_824.0),
+            AlertMetric::QueueDepth => format!("{}", value),
+            AlertMetric::FailureRate => format!("{:.0}%", value * 100.0),
+        }
+    }
+}
+
+impl FromStr for AlertMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "memory_percent" => Ok(AlertMetric::MemoryPercent),
+            "cpu_percent" => Ok(AlertMetric::CpuPercent),
+            "disk_free_bytes" => Ok(AlertMetric::DiskFreeBytes),
+            "queue_depth" => Ok(AlertMetric::QueueDepth),
+            "failure_rate" => Ok(AlertMetric::FailureRate),
+            _ => Err(anyhow::anyhow!("Unknown alert metric: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+impl Comparison {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Above => "above",
+            Comparison::Below => "below",
+        }
+    }
+
+    pub fn breached(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::Below => value < threshold,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "above" => Ok(Comparison::Above),
+            "below" => Ok(Comparison::Below),
+            _ => Err(anyhow::anyhow!("Unknown comparison: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for AlertSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "info" => Ok(AlertSeverity::Info),
+            "warning" => Ok(AlertSeverity::Warning),
+            "critical" => Ok(AlertSeverity::Critical),
+            _ => Err(anyhow::anyhow!("Unknown alert severity: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    /// Seen by the user but the condition still holds
+    Acknowledged,
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Firing => "firing",
+            AlertState::Acknowledged => "acknowledged",
+            AlertState::Resolved => "resolved",
+        }
+    }
+}
+
+impl FromStr for AlertState {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "firing" => Ok(AlertState::Firing),
+            "acknowledged" => Ok(AlertState::Acknowledged),
+            "resolved" => Ok(AlertState::Resolved),
+            _ => Err(anyhow::anyhow!("Unknown alert state: {}", value)),
+        }
+    }
+}
+
+/// Where a rule's alerts are delivered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertSink {
+    /// `ALERT_EVENT` to the UI
+    Event,
+    /// Native desktop notification, only when the alert fires
+    Desktop,
+    /// JSON `Alert` POSTed to the URL
+    Webhook { url: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub metric: AlertMetric,
+    /// Agent a failure-rate rule watches (registry name such as `ghost_lag`)
+    pub agent_name: Option<String>,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// How long the threshold must stay breached before the alert fires
+    pub duration_secs: i64,
+    pub severity: AlertSeverity,
+    pub sinks: Vec<AlertSink>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRuleRequest {
+    pub name: String,
+    pub metric: AlertMetric,
+    pub agent_name: Option<String>,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    #[serde(default)]
+    pub duration_secs: i64,
+    pub severity: Option<AlertSeverity>,
+    #[serde(default)]
+    pub sinks: Vec<AlertSink>,
+    pub enabled: Option<bool>,
+}
+
+/// One firing of a rule, kept as history once resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub severity: AlertSeverity,
+    pub state: AlertState,
+    /// Metric value when the alert fired
+    pub value: f64,
+    pub message: String,
+    pub fired_at: String,
+    pub acknowledged_at: Option<String>,
+    pub resolved_at: Option<String>,
+}
+
+impl Alert {
+    pub fn is_open(&self) -> bool {
+        self.state != AlertState::Resolved
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::error;
+
+use super::{Alert, AlertSink, AlertState};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Listener = Arc<dyn Fn(&Alert) + Send + Sync>;
+
+/// Delivers alerts to the sinks configured on their rule
+#[derive(Clone)]
+pub struct AlertNotifier {
+    listener: Arc<Mutex<Option<Listener>>>,
+    desktop: Arc<Mutex<Option<Listener>>>,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Debug for AlertNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlertNotifier").finish_non_exhaustive()
+    }
+}
+
+impl Default for AlertNotifier {
+    fn default() -> Self {
+        Self {
+            listener: Arc::new(Mutex::new(None)),
+            desktop: Arc::new(Mutex::new(None)),
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl AlertNotifier {
+    /// Called for alerts routed to `AlertSink::Event`, e.g. to emit them as Tauri events
+    pub fn set_listener(&self, listener: impl Fn(&Alert) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    /// Called for firing alerts routed to `AlertSink::Desktop`, e.g. to show a native notification
+    pub fn set_desktop_listener(&self, listener: impl Fn(&Alert) + Send + Sync + 'static) {
+        *self.desktop.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    /// Send the alert to every sink. Failures are logged; one broken sink does not stop the others.
+    pub async fn deliver(&self, alert: &Alert, sinks: &[AlertSink]) {
+        for sink in sinks {
+            match sink {
+                AlertSink::Event => {
+                    let listener = self.listener.lock().unwrap().clone();
+                    if let Some(listener) = listener {
+                        listener(alert);
+                    }
+                }
+                AlertSink::Desktop => {
+                    let listener = self.desktop.lock().unwrap().clone();
+                    if let Some(listener) = listener.filter(|_| alert.state == AlertState::Firing) {
+                        listener(alert);
+                    }
+                }
+                AlertSink::Webhook { url } => {
+                    let result = self.client.post(url).json(alert).send().await
+                        .and_then(|response| response.error_for_status());
+                    if let Err(e) = result {
+                        error!("Failed to deliver alert {} to webhook {}: {}", alert.id, url, e);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,8 +2,20 @@ use tauri::State;
 use anyhow::Result;
 use tracing::{info, error};
 use serde::{Deserialize, Serialize};
-use crate::agents::{vitra::VitraAgent, ghost::GhostAgent, ceo::CeoAgent};
-use std::sync::{Arc, Mutex};
+use crate::agents::ceo::Task;
+use crate::agents::graph::{TaskGraphRequest, TaskGraphStatus};
+use crate::agents::scheduler::{Schedule, ScheduleRequest};
+use crate::alerts::{Alert, AlertRule, AlertRuleRequest};
+use crate::database::file_processing::FileProcessingRecord;
+use crate::database::workflows::{WorkflowFilter, WorkflowRun};
+use crate::error::VhqError;
+use crate::health::HealthReport;
+use crate::n8n::{DeployedWorkflow, N8nClient, N8nExecution, N8nWorkflow, SyncReport, WebhookResponse};
+use crate::pipeline::import::{self, N8nImport};
+use crate::pipeline::PipelineFile;
+use crate::settings::Settings;
+use crate::state::AppState;
+use crate::system::WorkflowHistory;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AgentStatus {
@@ -14,15 +26,6 @@ pub struct AgentStatus {
     pub cpu_usage: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Settings {
-    pub theme: String,
-    pub auto_save: bool,
-    pub notifications: bool,
-    pub max_memory: i64,
-    pub cpu_limit: f64,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProcessFileRequest {
     pub file_path: String,
@@ -30,284 +33,448 @@ pub struct ProcessFileRequest {
     pub options: serde_json::Value,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskLimitsRequest {
+    /// Agent to update; the scheduler-wide limits are updated when omitted
+    pub agent_name: Option<String>,
+    pub max_concurrent_tasks: Option<i32>,
+    pub task_timeout: Option<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateSettingsRequest {
+    /// Changed keys only, e.g. `{ "vitra": { "whisper_model": "small" } }`
     pub settings: serde_json::Value,
 }
 
 #[tauri::command]
-pub async fn start_agent(agent_name: String) -> Result<AgentStatus, String> {
+pub async fn start_agent(state: State<'_, AppState>, agent_name: String) -> Result<AgentStatus, VhqError> {
     info!("Starting agent: {}", agent_name);
     
-    match agent_name.as_str() {
+    let status = match agent_name.as_str() {
         "vitra_lag" => {
+            let agent = state.vitra.clone();
             tokio::task::spawn_blocking(move || {
-                let mut agent = VitraAgent::new();
-                agent.start().map_err(|e| e.to_string())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r)
+                agent.blocking_write().start().map_err(VhqError::from)
+            }).await.map_err(VhqError::from).and_then(|r| r)
         }
         "ghost_lag" => {
+            let agent = state.ghost.clone();
             tokio::task::spawn_blocking(move || {
-                let mut agent = GhostAgent::new();
-                agent.start().map_err(|e| e.to_string())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r)
+                agent.blocking_write().start().map_err(VhqError::from)
+            }).await.map_err(VhqError::from).and_then(|r| r)
         }
         "ceo_lag" => {
+            let agent = state.ceo.clone();
             tokio::task::spawn_blocking(move || {
-                let mut agent = CeoAgent::new();
-                agent.start().map_err(|e| e.to_string())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r)
+                agent.blocking_write().start().map_err(VhqError::from)
+            }).await.map_err(VhqError::from).and_then(|r| r)
         }
-        _ => Err(format!("Unknown agent: {}", agent_name))
-    }
+        _ => Err(VhqError::invalid_input(format!("Unknown agent: {}", agent_name)))
+    }?;
+    
+    record_status(&state, &status).await;
+    Ok(status)
 }
 
 #[tauri::command]
-pub async fn stop_agent(agent_name: String) -> Result<AgentStatus, String> {
+pub async fn stop_agent(state: State<'_, AppState>, agent_name: String) -> Result<AgentStatus, VhqError> {
     info!("Stopping agent: {}", agent_name);
     
-    match agent_name.as_str() {
-        "vitra_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let mut agent = VitraAgent::new();
-                agent.stop().map_err(|e| e.to_string())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r)
-        }
-        "ghost_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let mut agent = GhostAgent::new();
-                agent.stop().map_err(|e| e.to_string())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r)
-        }
-        "ceo_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let mut agent = CeoAgent::new();
-                agent.stop().map_err(|e| e.to_string())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r)
-        }
-        _ => Err(format!("Unknown agent: {}", agent_name))
+    let status = match agent_name.as_str() {
+        "vitra_lag" => state.vitra.write().await.stop().map_err(VhqError::from),
+        "ghost_lag" => state.ghost.write().await.stop().map_err(VhqError::from),
+        "ceo_lag" => state.ceo.write().await.stop().map_err(VhqError::from),
+        _ => Err(VhqError::invalid_input(format!("Unknown agent: {}", agent_name)))
+    }?;
+    
+    record_status(&state, &status).await;
+    Ok(status)
+}
+
+/// Keep the last known status of each agent in the database
+async fn record_status(state: &AppState, status: &AgentStatus) {
+    if let Err(e) = state.db.agents().upsert_status(status).await {
+        error!("Failed to record status of {}: {}", status.name, e);
     }
 }
 
 #[tauri::command]
-pub async fn get_agent_status(agent_name: String) -> Result<AgentStatus, String> {
+pub async fn get_agent_status(state: State<'_, AppState>, agent_name: String) -> Result<AgentStatus, VhqError> {
     info!("Getting status for agent: {}", agent_name);
     
     match agent_name.as_str() {
-        "vitra_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let agent = VitraAgent::new();
-                Ok(agent.get_status())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
-        }
-        "ghost_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let agent = GhostAgent::new();
-                Ok(agent.get_status())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
-        }
-        "ceo_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let agent = CeoAgent::new();
-                Ok(agent.get_status())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
-        }
-        _ => Err(format!("Unknown agent: {}", agent_name))
+        "vitra_lag" => Ok(state.vitra.read().await.get_status()),
+        "ghost_lag" => Ok(state.ghost.read().await.get_status()),
+        "ceo_lag" => Ok(state.ceo.read().await.get_status()),
+        _ => Err(VhqError::invalid_input(format!("Unknown agent: {}", agent_name)))
     }
 }
 
 #[tauri::command]
-pub async fn process_file(request: ProcessFileRequest) -> Result<String, String> {
+pub async fn process_file(state: State<'_, AppState>, request: ProcessFileRequest) -> Result<String, VhqError> {
     info!("Processing file: {} with agent: {}", request.file_path, request.agent_type);
     
+    // Run on a snapshot so settings and stop requests are not held up by a long job
     match request.agent_type.as_str() {
         "vitra_lag" => {
+            let agent = state.vitra.read().await.clone();
             let file_path = request.file_path.clone();
             let options = request.options.clone();
             tokio::task::spawn_blocking(move || {
-                let agent = VitraAgent::new();
                 agent.process_file(&file_path, options)
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+            }).await.map_err(VhqError::from).and_then(|r| r.map_err(VhqError::from))
         }
         "ghost_lag" => {
+            let agent = state.ghost.read().await.clone();
             let file_path = request.file_path.clone();
             let options = request.options.clone();
             tokio::task::spawn_blocking(move || {
-                let agent = GhostAgent::new();
                 agent.process_file(&file_path, options)
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+            }).await.map_err(VhqError::from).and_then(|r| r.map_err(VhqError::from))
         }
         "ceo_lag" => {
-            let file_path = request.file_path.clone();
-            let options = request.options.clone();
-            tokio::task::spawn_blocking(move || {
-                let agent = CeoAgent::new();
-                agent.process_file(&file_path, options)
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+            state.ceo.read().await
+                .process_file(&request.file_path, request.options.clone())
+                .await
+                .map_err(VhqError::from)
         }
-        _ => Err(format!("Unknown agent: {}", request.agent_type))
+        _ => Err(VhqError::invalid_input(format!("Unknown agent: {}", request.agent_type)))
     }
 }
 
+/// Files processed through the CEO queue, newest first
+#[tauri::command]
+pub async fn get_file_processing_history(state: State<'_, AppState>, limit: Option<i64>) -> Result<Vec<FileProcessingRecord>, VhqError> {
+    info!("Getting file processing history");
+    
+    Ok(state.db.file_processing().history(limit.unwrap_or(100)).await?)
+}
+
 #[tauri::command]
-pub async fn get_agent_info(agent_name: String) -> Result<serde_json::Value, String> {
+pub async fn get_agent_info(state: State<'_, AppState>, agent_name: String) -> Result<serde_json::Value, VhqError> {
     info!("Getting info for agent: {}", agent_name);
     
     match agent_name.as_str() {
-        "vitra_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let agent = VitraAgent::new();
-                Ok(agent.get_model_info())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
-        }
-        "ghost_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let agent = GhostAgent::new();
-                Ok(agent.get_agent_info())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
-        }
-        "ceo_lag" => {
-            tokio::task::spawn_blocking(move || {
-                let agent = CeoAgent::new();
-                Ok(agent.get_agent_info())
-            }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
-        }
-        _ => Err(format!("Unknown agent: {}", agent_name))
+        "vitra_lag" => Ok(state.vitra.read().await.get_model_info()),
+        "ghost_lag" => Ok(state.ghost.read().await.get_agent_info()),
+        "ceo_lag" => Ok(state.ceo.read().await.get_agent_info()),
+        _ => Err(VhqError::invalid_input(format!("Unknown agent: {}", agent_name)))
     }
 }
 
 #[tauri::command]
-pub async fn get_queue_status() -> Result<serde_json::Value, String> {
+pub async fn get_queue_status(state: State<'_, AppState>) -> Result<serde_json::Value, VhqError> {
     info!("Getting queue status");
     
-    let agent = CeoAgent::new();
-    tokio::task::spawn_blocking(move || {
-        agent.get_queue_status()
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    state.ceo.read().await.get_queue_status().await.map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn get_system_metrics() -> Result<serde_json::Value, String> {
+pub async fn get_system_metrics(state: State<'_, AppState>) -> Result<serde_json::Value, VhqError> {
     info!("Getting system metrics");
     
-    let agent = CeoAgent::new();
-    tokio::task::spawn_blocking(move || {
-        agent.get_system_metrics()
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    state.ceo.read().await.get_system_metrics().map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn get_task_status(task_id: String) -> Result<serde_json::Value, String> {
+pub async fn get_task_status(state: State<'_, AppState>, task_id: String) -> Result<serde_json::Value, VhqError> {
     info!("Getting task status: {}", task_id);
     
-    let agent = CeoAgent::new();
-    tokio::task::spawn_blocking(move || {
-        agent.get_task_status(&task_id)
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    state.ceo.read().await.get_task_status(&task_id).await.map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn cancel_task(task_id: String) -> Result<(), String> {
+pub async fn cancel_task(state: State<'_, AppState>, task_id: String) -> Result<(), VhqError> {
     info!("Cancelling task: {}", task_id);
     
-    let agent = CeoAgent::new();
-    tokio::task::spawn_blocking(move || {
-        agent.cancel_task(&task_id)
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    state.ceo.read().await.cancel_task(&task_id).await.map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn clear_completed_tasks() -> Result<(), String> {
+pub async fn clear_completed_tasks(state: State<'_, AppState>) -> Result<(), VhqError> {
     info!("Clearing completed tasks");
     
-    let agent = CeoAgent::new();
-    tokio::task::spawn_blocking(move || {
-        agent.clear_completed_tasks()
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    state.ceo.read().await.clear_completed_tasks().await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn submit_task_graph(state: State<'_, AppState>, request: TaskGraphRequest) -> Result<TaskGraphStatus, VhqError> {
+    info!("Submitting task graph with {} nodes", request.nodes.len());
+    
+    state.ceo.read().await.submit_graph(request).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn get_task_graph_status(state: State<'_, AppState>, graph_id: String) -> Result<TaskGraphStatus, VhqError> {
+    info!("Getting task graph status: {}", graph_id);
+    
+    state.ceo.read().await.get_graph_status(&graph_id).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn get_dead_letter_tasks(state: State<'_, AppState>) -> Result<Vec<Task>, VhqError> {
+    info!("Getting dead-letter tasks");
+    
+    state.ceo.read().await.get_dead_letter_tasks().await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn replay_dead_letter_task(state: State<'_, AppState>, task_id: String) -> Result<(), VhqError> {
+    info!("Replaying dead-letter task: {}", task_id);
+    
+    state.ceo.read().await.replay_dead_letter_task(&task_id).await.map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn get_ghost_models() -> Result<Vec<String>, String> {
+pub async fn create_schedule(state: State<'_, AppState>, request: ScheduleRequest) -> Result<Schedule, VhqError> {
+    info!("Creating schedule: {}", request.name);
+    
+    state.scheduler.create_schedule(request).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn list_schedules(state: State<'_, AppState>) -> Result<Vec<Schedule>, VhqError> {
+    info!("Listing schedules");
+    
+    state.scheduler.list_schedules().await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn pause_schedule(state: State<'_, AppState>, schedule_id: String) -> Result<Schedule, VhqError> {
+    info!("Pausing schedule: {}", schedule_id);
+    
+    state.scheduler.set_paused(&schedule_id, true).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn resume_schedule(state: State<'_, AppState>, schedule_id: String) -> Result<Schedule, VhqError> {
+    info!("Resuming schedule: {}", schedule_id);
+    
+    state.scheduler.set_paused(&schedule_id, false).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn delete_schedule(state: State<'_, AppState>, schedule_id: String) -> Result<(), VhqError> {
+    info!("Deleting schedule: {}", schedule_id);
+    
+    state.scheduler.delete_schedule(&schedule_id).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn list_alert_rules(state: State<'_, AppState>) -> Result<Vec<AlertRule>, VhqError> {
+    info!("Listing alert rules");
+    
+    state.alerts.list_rules().await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn create_alert_rule(state: State<'_, AppState>, request: AlertRuleRequest) -> Result<AlertRule, VhqError> {
+    info!("Creating alert rule: {}", request.name);
+    
+    state.alerts.create_rule(request).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn update_alert_rule(state: State<'_, AppState>, rule_id: String, request: AlertRuleRequest) -> Result<AlertRule, VhqError> {
+    info!("Updating alert rule: {}", rule_id);
+    
+    state.alerts.update_rule(&rule_id, request).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn delete_alert_rule(state: State<'_, AppState>, rule_id: String) -> Result<(), VhqError> {
+    info!("Deleting alert rule: {}", rule_id);
+    
+    state.alerts.delete_rule(&rule_id).await.map_err(VhqError::from)
+}
+
+/// Open alerts, or the most recent `limit` (default 100) including resolved ones
+#[tauri::command]
+pub async fn get_alerts(state: State<'_, AppState>, include_resolved: bool, limit: Option<i64>) -> Result<Vec<Alert>, VhqError> {
+    info!("Getting alerts (include resolved: {})", include_resolved);
+    
+    state.alerts.list_alerts(include_resolved, limit.unwrap_or(100)).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn acknowledge_alert(state: State<'_, AppState>, alert_id: String) -> Result<Alert, VhqError> {
+    info!("Acknowledging alert: {}", alert_id);
+    
+    state.alerts.acknowledge(&alert_id).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn resolve_alert(state: State<'_, AppState>, alert_id: String) -> Result<Alert, VhqError> {
+    info!("Resolving alert: {}", alert_id);
+    
+    state.alerts.resolve(&alert_id).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn get_health_history(state: State<'_, AppState>) -> Result<Vec<HealthReport>, VhqError> {
+    info!("Getting health history");
+    
+    Ok(state.ceo.read().await.health.reports())
+}
+
+#[tauri::command]
+pub async fn update_task_limits(state: State<'_, AppState>, request: TaskLimitsRequest) -> Result<(), VhqError> {
+    info!("Updating task limits: {:?}", request);
+    
+    let ceo = state.ceo.read().await;
+    match request.agent_name.as_deref() {
+        Some(agent_name) => ceo.set_agent_limits(agent_name, request.max_concurrent_tasks, request.task_timeout),
+        None => ceo.set_limits(request.max_concurrent_tasks, request.task_timeout),
+    }.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn get_ghost_models(state: State<'_, AppState>) -> Result<Vec<String>, VhqError> {
     info!("Getting available Ghost models");
     
-    let agent = GhostAgent::new();
+    let agent = state.ghost.read().await.clone();
     tokio::task::spawn_blocking(move || {
         agent.get_available_models()
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    }).await.map_err(VhqError::from).and_then(|r| r.map_err(VhqError::from))
 }
 
 #[tauri::command]
-pub async fn get_vitra_languages() -> Result<Vec<String>, String> {
+pub async fn get_vitra_languages(state: State<'_, AppState>) -> Result<Vec<String>, VhqError> {
     info!("Getting supported VITRA languages");
     
-    let agent = VitraAgent::new();
-    tokio::task::spawn_blocking(move || {
-        Ok(agent.get_supported_languages())
-    }).await.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e: anyhow::Error| e.to_string()))
+    Ok(state.vitra.read().await.get_supported_languages())
 }
 
 #[tauri::command]
-pub async fn get_workflow_status() -> Result<Vec<serde_json::Value>, String> {
+pub async fn get_workflow_status(state: State<'_, AppState>, filter: Option<WorkflowFilter>) -> Result<WorkflowHistory, VhqError> {
     info!("Getting workflow status");
     
-    // Mock workflow status for now
-    let workflows = vec![
-        serde_json::json!({
-            "id": "1",
-            "name": "Video Transcription Pipeline",
-            "status": "running",
-            "progress": 0.75
-        }),
-        serde_json::json!({
-            "id": "2", 
-            "name": "Content Generation Workflow",
-            "status": "pending",
-            "progress": 0.0
-        })
-    ];
+    state.workflows.history(&state.db, &filter.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, VhqError> {
+    info!("Getting settings");
     
-    Ok(workflows)
+    state.settings.get().await.map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn update_settings(request: UpdateSettingsRequest) -> Result<(), String> {
-    info!("Updating settings");
+pub async fn update_settings(state: State<'_, AppState>, request: UpdateSettingsRequest) -> Result<Settings, VhqError> {
+    info!("Updating settings: {}", request.settings);
     
-    // Mock settings update
-    info!("Settings updated: {:?}", request.settings);
+    let (settings, config) = state.settings.update(request.settings).await?;
+    state.apply_config(&config).await?;
+    state.settings.notify(&settings);
     
-    Ok(())
+    Ok(settings)
 }
 
 #[tauri::command]
-pub async fn trigger_n8n_workflow(workflow_name: String, payload: serde_json::Value) -> Result<String, String> {
+pub async fn trigger_n8n_workflow(state: State<'_, AppState>, workflow_name: String, payload: serde_json::Value) -> Result<WebhookResponse, VhqError> {
     info!("Triggering n8n workflow: {}", workflow_name);
     
-    // TODO: Implement n8n workflow triggering
-    // This would typically make an HTTP request to n8n's webhook endpoint
-    Ok(format!("Workflow {} triggered successfully", workflow_name))
+    state.workflows.trigger_workflow(&workflow_name, payload).await.map_err(VhqError::from)
 }
 
 #[tauri::command]
-pub async fn get_n8n_workflows() -> Result<Vec<serde_json::Value>, String> {
+pub async fn get_n8n_workflows(state: State<'_, AppState>) -> Result<Vec<N8nWorkflow>, VhqError> {
     info!("Getting n8n workflows");
     
-    // TODO: Implement n8n workflow listing
-    // This would typically make an HTTP request to n8n's API
-    Ok(vec![])
+    N8nClient::new(&state.settings.config().n8n)?.list_workflows().await
 }
 
 #[tauri::command]
-pub async fn deploy_n8n_workflow(workflow_data: serde_json::Value) -> Result<String, String> {
+pub async fn get_n8n_workflow(state: State<'_, AppState>, workflow_id: String) -> Result<N8nWorkflow, VhqError> {
+    info!("Getting n8n workflow: {}", workflow_id);
+    
+    N8nClient::new(&state.settings.config().n8n)?.get_workflow(&workflow_id).await
+}
+
+#[tauri::command]
+pub async fn deploy_n8n_workflow(state: State<'_, AppState>, workflow_data: serde_json::Value) -> Result<DeployedWorkflow, VhqError> {
     info!("Deploying n8n workflow");
     
-    // TODO: Implement n8n workflow deployment
-    // This would typically make an HTTP request to n8n's API
-    Ok("Workflow deployed successfully".to_string())
+    N8nClient::new(&state.settings.config().n8n)?.deploy_workflow(&workflow_data).await
+}
+
+#[tauri::command]
+pub async fn sync_n8n_workflows(state: State<'_, AppState>, dry_run: bool) -> Result<SyncReport, VhqError> {
+    info!("Syncing bundled n8n workflows (dry run: {})", dry_run);
+    
+    state.workflows.sync(dry_run).await.map_err(VhqError::from)
+}
+
+#[tauri::command]
+pub async fn list_pipelines(state: State<'_, AppState>) -> Result<Vec<PipelineFile>, VhqError> {
+    info!("Listing native pipelines");
+    
+    Ok(state.workflows.list_pipelines())
+}
+
+#[tauri::command]
+pub async fn run_pipeline(state: State<'_, AppState>, name: String, trigger: Option<serde_json::Value>) -> Result<WorkflowRun, VhqError> {
+    info!("Running pipeline {} manually", name);
+    
+    let trigger = trigger.unwrap_or_else(|| serde_json::json!({}));
+    state.workflows.run_pipeline(&state, &name, trigger).await
+}
+
+/// Convert an n8n export in `n8n.workflows_dir` into a native pipeline. `save` writes a
+/// complete conversion to `pipelines.dir` and loads it, so it can be run right away.
+#[tauri::command]
+pub async fn import_n8n_workflow(state: State<'_, AppState>, file: String, save: bool) -> Result<N8nImport, VhqError> {
+    info!("Importing n8n workflow {} (save: {})", file, save);
+    
+    let config = state.settings.config();
+    let path = import::workflow_path(std::path::Path::new(&config.n8n.workflows_dir), &file)?;
+    let pipelines_dir = std::path::PathBuf::from(&config.pipelines.dir);
+    let import = tokio::task::spawn_blocking(move || import::import_file(&path, save.then_some(pipelines_dir.as_path()))).await??;
+    
+    if import.saved_to.is_some() {
+        state.workflows.start_pipelines(&state).await?;
+    }
+    Ok(import)
+}
+
+#[tauri::command]
+pub async fn set_n8n_workflow_active(state: State<'_, AppState>, workflow_id: String, active: bool) -> Result<N8nWorkflow, VhqError> {
+    info!("Setting n8n workflow {} active: {}", workflow_id, active);
+    
+    N8nClient::new(&state.settings.config().n8n)?.set_workflow_active(&workflow_id, active).await
+}
+
+#[tauri::command]
+pub async fn delete_n8n_workflow(state: State<'_, AppState>, workflow_id: String) -> Result<N8nWorkflow, VhqError> {
+    info!("Deleting n8n workflow: {}", workflow_id);
+    
+    N8nClient::new(&state.settings.config().n8n)?.delete_workflow(&workflow_id).await
+}
+
+#[tauri::command]
+pub async fn get_n8n_executions(
+    state: State<'_, AppState>,
+    workflow_id: Option<String>,
+    status: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<N8nExecution>, VhqError> {
+    info!("Getting n8n executions");
+    
+    N8nClient::new(&state.settings.config().n8n)?
+        .list_executions(workflow_id.as_deref(), status.as_deref(), limit.unwrap_or(20))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_n8n_execution(state: State<'_, AppState>, execution_id: String) -> Result<N8nExecution, VhqError> {
+    info!("Getting n8n execution: {}", execution_id);
+    
+    N8nClient::new(&state.settings.config().n8n)?.get_execution(&execution_id).await
 }
 
 #[tauri::command]
-pub async fn run_system_tests() -> Result<serde_json::Value, String> {
+pub async fn run_system_tests() -> Result<serde_json::Value, VhqError> {
     info!("Running system tests");
     
     use crate::tests::TestSuite;
@@ -331,9 +498,9 @@ pub async fn run_system_tests() -> Result<serde_json::Value, String> {
                     
                     Ok(result_json)
                 }
-                Err(e) => Err(format!("Test execution failed: {}", e))
+                Err(e) => Err(VhqError::Internal(format!("Test execution failed: {}", e)))
             }
         }
-        Err(e) => Err(format!("Test suite initialization failed: {}", e))
+        Err(e) => Err(VhqError::Internal(format!("Test suite initialization failed: {}", e)))
     }
 } 
\ No newline at end of file
@@ -0,0 +1,521 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::agents::ceo::RecoveryPolicy;
+use crate::agents::{ghost, vitra};
+use crate::database::{self, Database, DatabaseError};
+use crate::health::HealthThresholds;
+
+/// Prefix of environment variables overriding config keys, e.g. `VHQ_GHOST_OLLAMA_URL`
+const ENV_PREFIX: &str = "VHQ";
+
+/// Optional keys that are unset by default but hold text, so their environment
+/// variables are never read as numbers or booleans
+const OPTIONAL_STRINGS: &[&str] = &["n8n.api_key", "n8n.callback_secret"];
+
+/// Agent and service configuration.
+///
+/// Built from layers, each overriding the previous one: built-in defaults,
+/// `config.toml` in the app data directory, `VHQ_<SECTION>_<KEY>` environment
+/// variables, and overrides saved in the database.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub ghost: GhostConfig,
+    pub vitra: VitraConfig,
+    pub n8n: N8nConfig,
+    pub pipelines: PipelinesConfig,
+    pub tasks: TasksConfig,
+    pub health: HealthThresholds,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GhostConfig {
+    pub ollama_url: String,
+    pub default_model: String,
+    pub templates_dir: String,
+    pub output_dir: String,
+    pub max_concurrent_tasks: i32,
+    /// Seconds before a task is cancelled; the scheduler-wide timeout applies when unset
+    pub task_timeout_secs: Option<i32>,
+}
+
+impl Default for GhostConfig {
+    fn default() -> Self {
+        Self {
+            ollama_url: ghost::DEFAULT_OLLAMA_URL.to_string(),
+            default_model: "llama2".to_string(),
+            templates_dir: "templates".to_string(),
+            output_dir: ghost::DEFAULT_OUTPUT_DIR.to_string(),
+            max_concurrent_tasks: 2,
+            task_timeout_secs: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VitraConfig {
+    pub whisper_model: String,
+    pub max_concurrent_tasks: i32,
+    /// Seconds before a task is cancelled; the scheduler-wide timeout applies when unset
+    pub task_timeout_secs: Option<i32>,
+}
+
+impl Default for VitraConfig {
+    fn default() -> Self {
+        Self {
+            whisper_model: "base".to_string(),
+            // whisper saturates the CPU on its own
+            max_concurrent_tasks: 1,
+            task_timeout_secs: Some(1800),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct N8nConfig {
+    pub url: String,
+    /// Key for the public REST API, created in n8n under Settings > n8n API
+    pub api_key: Option<String>,
+    /// Call `/webhook-test/` URLs, which n8n only serves while the workflow is open in the editor
+    pub use_test_webhooks: bool,
+    /// Webhook path per workflow name; other workflows use their name as the path
+    pub webhooks: BTreeMap<String, String>,
+    /// Credentials the webhook nodes are configured to require
+    pub webhook_auth: Option<WebhookAuth>,
+    pub timeout_secs: u64,
+    /// Attempts per trigger, counting the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub retry_delay_ms: u64,
+    /// Bundled workflow definitions deployed by the sync
+    pub workflows_dir: String,
+    /// Deploy changed bundled workflows when the app starts; needs `api_key`
+    pub sync_on_startup: bool,
+    /// Values for `${NAME}` placeholders in the bundled workflows and native pipelines, e.g. `VHQ_AGENTS_PATH`.
+    /// Unset names fall back to the environment variable of the same name.
+    pub variables: BTreeMap<String, String>,
+    /// Address of the endpoint workflows report their progress to; changes apply after a restart
+    pub callback_bind: String,
+    /// Value workflows send in the `X-VHQ-Secret` header. The endpoint only runs when it is set.
+    pub callback_secret: Option<String>,
+}
+
+impl Default for N8nConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:5678".to_string(),
+            api_key: None,
+            use_test_webhooks: false,
+            webhooks: BTreeMap::new(),
+            webhook_auth: None,
+            timeout_secs: 30,
+            max_attempts: 3,
+            retry_delay_ms: 500,
+            workflows_dir: "../n8n-workflows".to_string(),
+            sync_on_startup: true,
+            variables: BTreeMap::new(),
+            callback_bind: "127.0.0.1:5680".to_string(),
+            callback_secret: None,
+        }
+    }
+}
+
+/// Native pipelines, run without n8n
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelinesConfig {
+    /// Directory of `.yaml`, `.yml` and `.json` pipeline definitions; read when the app starts
+    pub dir: String,
+    /// Start the file and schedule triggers; pipelines can always be run manually.
+    /// Off by default because the bundled pipelines watch the same folders as the n8n workflows.
+    pub triggers: bool,
+    /// Seconds an agent step waits for its task before the step fails
+    pub step_timeout_secs: u64,
+}
+
+impl Default for PipelinesConfig {
+    fn default() -> Self {
+        Self {
+            dir: "../pipelines".to_string(),
+            triggers: false,
+            step_timeout_secs: 3600,
+        }
+    }
+}
+
+/// CEO task queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TasksConfig {
+    /// What happens at startup to tasks the previous session left running: `requeue` or `mark_failed`
+    pub recovery_policy: RecoveryPolicy,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            recovery_policy: RecoveryPolicy::Requeue,
+        }
+    }
+}
+
+/// Authentication of n8n webhook nodes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WebhookAuth {
+    /// "Header Auth": a fixed header name and value
+    Header { name: String, value: String },
+    /// "Basic Auth"
+    Basic { username: String, password: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Read { path: String, source: std::io::Error },
+    #[error("Invalid configuration in {layer}: {message}")]
+    Parse { layer: String, message: String },
+    #[error("Invalid configuration: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+pub type ConfigResult<T> = std::result::Result<T, ConfigError>;
+
+/// Location of the user's `config.toml`
+pub fn config_path() -> PathBuf {
+    database::data_dir().join("config.toml")
+}
+
+/// Load the configuration from all layers and validate it
+pub async fn load(db: &Database) -> ConfigResult<AppConfig> {
+    let stored = db.settings().load_overrides().await?;
+    resolve(stored.as_ref())
+}
+
+/// Build the configuration from the file and environment, with `stored` settings on top.
+/// Keys of `stored` outside the config sections are ignored.
+pub fn resolve(stored: Option<&Value>) -> ConfigResult<AppConfig> {
+    let path = config_path();
+    let file = match std::fs::read_to_string(&path) {
+        Ok(text) => Some(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(ConfigError::Read { path: path.display().to_string(), source: e }),
+    };
+    let stored = stored.map(sections);
+
+    let config = build(&path, file.as_deref(), |name| std::env::var(name).ok(), stored)?;
+    info!("Configuration resolved (config file: {})", if file.is_some() { path.display().to_string() } else { "none".to_string() });
+
+    Ok(config)
+}
+
+/// Apply the layers on top of the defaults. `env` looks up environment variables.
+fn build(
+    path: &Path,
+    file: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+    stored: Option<Value>,
+) -> ConfigResult<AppConfig> {
+    let mut config = serde_json::to_value(AppConfig::default()).expect("config serializes");
+
+    if let Some(text) = file {
+        let layer = path.display().to_string();
+        let value: Value = toml::from_str(text)
+            .map_err(|e| ConfigError::Parse { layer: layer.clone(), message: e.to_string() })?;
+        merge(&mut config, value);
+        check(&config, &layer)?;
+    }
+
+    apply_env(&mut config, env)?;
+
+    if let Some(value) = stored {
+        merge(&mut config, value);
+        check(&config, "stored settings")?;
+    }
+
+    let config: AppConfig = serde_json::from_value(config)
+        .map_err(|e| ConfigError::Parse { layer: "configuration".to_string(), message: e.to_string() })?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// The parts of a settings document that belong to `AppConfig`
+fn sections(stored: &Value) -> Value {
+    let known = serde_json::to_value(AppConfig::default()).expect("config serializes");
+    let sections = stored.as_object()
+        .map(|stored| {
+            stored.iter()
+                .filter(|(key, _)| known.get(key.as_str()).is_some())
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Value::Object(sections)
+}
+
+/// Fail with the layer's name if the merged value no longer has the config's shape
+fn check(config: &Value, layer: &str) -> ConfigResult<()> {
+    serde_json::from_value::<AppConfig>(config.clone())
+        .map(|_| ())
+        .map_err(|e| ConfigError::Parse { layer: layer.to_string(), message: e.to_string() })
+}
+
+/// Recursively overlay objects; any other value replaces the base
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Override every known key that has a `VHQ_<SECTION>_<KEY>` variable, parsed as the key's type
+fn apply_env(config: &mut Value, env: impl Fn(&str) -> Option<String>) -> ConfigResult<()> {
+    let sections = match config.as_object_mut() {
+        Some(sections) => sections,
+        None => return Ok(()),
+    };
+
+    for (section, fields) in sections.iter_mut() {
+        let fields = match fields.as_object_mut() {
+            Some(fields) => fields,
+            None => continue,
+        };
+        for (key, current) in fields.iter_mut() {
+            let name = format!("{}_{}_{}", ENV_PREFIX, section, key).to_uppercase();
+            // Tables such as `n8n.webhooks` can only be set in the file or the app
+            if current.is_object() || current.is_array() {
+                continue;
+            }
+            let raw = match env(&name) {
+                Some(raw) => raw,
+                None => continue,
+            };
+
+            let invalid = |expected: &str| ConfigError::Parse {
+                layer: format!("environment variable {}", name),
+                message: format!("expected {}, got {:?}", expected, raw),
+            };
+            *current = match current {
+                Value::Bool(_) => Value::Bool(raw.parse().map_err(|_| invalid("true or false"))?),
+                Value::Number(_) => match raw.parse::<i64>() {
+                    Ok(number) => Value::from(number),
+                    Err(_) => {
+                        let number: f64 = raw.parse().map_err(|_| invalid("a number"))?;
+                        serde_json::Number::from_f64(number).map(Value::Number).ok_or_else(|| invalid("a number"))?
+                    }
+                },
+                Value::Null if OPTIONAL_STRINGS.contains(&format!("{}.{}", section, key).as_str()) => Value::String(raw.clone()),
+                // Other unset optional values take a number or, failing that, a string
+                Value::Null => serde_json::from_str(&raw).unwrap_or_else(|_| Value::String(raw.clone())),
+                _ => Value::String(raw.clone()),
+            };
+        }
+    }
+
+    Ok(())
+}
+
+impl AppConfig {
+    /// Directories each agent writes its outputs to, by registry name
+    pub fn output_dirs(&self) -> Vec<(String, PathBuf)> {
+        vec![
+            ("vitra_lag".to_string(), PathBuf::from(vitra::TRANSCRIPTS_DIR)),
+            ("ghost_lag".to_string(), PathBuf::from(&self.ghost.output_dir)),
+        ]
+    }
+
+    /// Report every invalid value at once
+    pub fn validate(&self) -> ConfigResult<()> {
+        let mut problems = Vec::new();
+
+        for (field, url) in [("ghost.ollama_url", &self.ghost.ollama_url), ("n8n.url", &self.n8n.url)] {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                Ok(_) => problems.push(format!("{} must be an http or https URL, got {:?}", field, url)),
+                Err(e) => problems.push(format!("{} is not a valid URL ({}): {:?}", field, e, url)),
+            }
+        }
+
+        for (field, value) in [
+            ("ghost.default_model", &self.ghost.default_model),
+            ("ghost.templates_dir", &self.ghost.templates_dir),
+            ("ghost.output_dir", &self.ghost.output_dir),
+            ("vitra.whisper_model", &self.vitra.whisper_model),
+            ("n8n.workflows_dir", &self.n8n.workflows_dir),
+            ("pipelines.dir", &self.pipelines.dir),
+        ] {
+            if value.trim().is_empty() {
+                problems.push(format!("{} must not be empty", field));
+            }
+        }
+
+        for (field, max) in [
+            ("ghost.max_concurrent_tasks", self.ghost.max_concurrent_tasks),
+            ("vitra.max_concurrent_tasks", self.vitra.max_concurrent_tasks),
+        ] {
+            if max < 1 {
+                problems.push(format!("{} must be at least 1, got {}", field, max));
+            }
+        }
+
+        for (field, timeout) in [
+            ("ghost.task_timeout_secs", self.ghost.task_timeout_secs),
+            ("vitra.task_timeout_secs", self.vitra.task_timeout_secs),
+        ] {
+            if let Some(timeout) = timeout.filter(|timeout| *timeout < 1) {
+                problems.push(format!("{} must be at least 1 second, got {}", field, timeout));
+            }
+        }
+
+        let health = &self.health;
+        for (field, warning, critical) in [
+            ("health.database_latency", health.database_latency_warning_ms, health.database_latency_critical_ms),
+            ("health.ollama_latency", health.ollama_latency_warning_ms, health.ollama_latency_critical_ms),
+            ("health.failure_rate", health.failure_rate_warning, health.failure_rate_critical),
+        ] {
+            if !(warning > 0.0 && warning <= critical) {
+                problems.push(format!("{} warning must be above 0 and at most the critical value, got {} and {}", field, warning, critical));
+            }
+        }
+        if health.failure_rate_critical > 1.0 {
+            problems.push(format!("health.failure_rate_critical must be at most 1, got {}", health.failure_rate_critical));
+        }
+        if self.pipelines.step_timeout_secs < 1 {
+            problems.push("pipelines.step_timeout_secs must be at least 1".to_string());
+        }
+        if health.failure_window_secs < 1 {
+            problems.push(format!("health.failure_window_secs must be at least 1, got {}", health.failure_window_secs));
+        }
+        if health.failure_min_tasks < 1 {
+            problems.push(format!("health.failure_min_tasks must be at least 1, got {}", health.failure_min_tasks));
+        }
+
+        if self.n8n.api_key.as_deref().is_some_and(|key| key.trim().is_empty()) {
+            problems.push("n8n.api_key must not be empty".to_string());
+        }
+        if self.n8n.callback_bind.parse::<std::net::SocketAddr>().is_err() {
+            problems.push(format!("n8n.callback_bind must be an address such as 127.0.0.1:5680, got {:?}", self.n8n.callback_bind));
+        }
+        if self.n8n.callback_secret.as_deref().is_some_and(|secret| secret.trim().is_empty()) {
+            problems.push("n8n.callback_secret must not be empty".to_string());
+        }
+        if self.n8n.timeout_secs == 0 {
+            problems.push("n8n.timeout_secs must be at least 1".to_string());
+        }
+        if self.n8n.max_attempts == 0 {
+            problems.push("n8n.max_attempts must be at least 1".to_string());
+        }
+        for (workflow, path) in &self.n8n.webhooks {
+            if path.trim_matches('/').trim().is_empty() {
+                problems.push(format!("n8n.webhooks.{} must not be empty", workflow));
+            }
+        }
+        if let Some(WebhookAuth::Header { name, .. }) = &self.n8n.webhook_auth {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                problems.push(format!("n8n.webhook_auth.name is not a valid header name: {:?}", name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path() -> PathBuf {
+        PathBuf::from("config.toml")
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let file = "[ghost]\nollama_url = \"http://gpu-box:11434\"\ndefault_model = \"mistral\"\n\n[n8n]\nurl = \"http://n8n.local:5678\"\n";
+        let env = |name: &str| match name {
+            "VHQ_GHOST_DEFAULT_MODEL" => Some("llama3".to_string()),
+            "VHQ_GHOST_MAX_CONCURRENT_TASKS" => Some("4".to_string()),
+            "VHQ_GHOST_TASK_TIMEOUT_SECS" => Some("600".to_string()),
+            "VHQ_TASKS_RECOVERY_POLICY" => Some("mark_failed".to_string()),
+            "VHQ_N8N_API_KEY" => Some("true".to_string()),
+            "VHQ_N8N_CALLBACK_SECRET" => Some("123456".to_string()),
+            _ => None,
+        };
+        let stored = serde_json::json!({ "n8n": { "url": "https://n8n.example.com" } });
+
+        let config = build(&path(), Some(file), env, Some(stored)).unwrap();
+
+        assert_eq!(config.ghost.ollama_url, "http://gpu-box:11434");
+        assert_eq!(config.ghost.default_model, "llama3");
+        assert_eq!(config.ghost.max_concurrent_tasks, 4);
+        assert_eq!(config.ghost.task_timeout_secs, Some(600));
+        assert_eq!(config.n8n.url, "https://n8n.example.com");
+        assert_eq!(config.n8n.api_key.as_deref(), Some("true"));
+        assert_eq!(config.n8n.callback_secret.as_deref(), Some("123456"));
+        assert_eq!(config.vitra, VitraConfig::default());
+        assert_eq!(config.tasks.recovery_policy, RecoveryPolicy::MarkFailed);
+    }
+
+    #[test]
+    fn test_unknown_keys_name_their_layer() {
+        let error = build(&path(), Some("[ghost]\nollama_ur = \"http://x\"\n"), |_| None, None).unwrap_err();
+        let message = error.to_string();
+
+        assert!(message.contains("config.toml"), "{}", message);
+        assert!(message.contains("ollama_ur"), "{}", message);
+    }
+
+    #[test]
+    fn test_validation_reports_every_problem() {
+        let env = |name: &str| match name {
+            "VHQ_GHOST_OLLAMA_URL" => Some("localhost:11434".to_string()),
+            "VHQ_VITRA_WHISPER_MODEL" => Some(" ".to_string()),
+            _ => None,
+        };
+
+        match build(&path(), None, env, None) {
+            Err(ConfigError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 2, "{:?}", problems);
+                assert!(problems[0].starts_with("ghost.ollama_url"));
+                assert!(problems[1].starts_with("vitra.whisper_model"));
+            }
+            other => panic!("expected validation errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_health_thresholds_come_from_file_and_are_checked() {
+        let file = "[health]\nfailure_rate_warning = 0.1\nfailure_min_tasks = 10\n";
+        let config = build(&path(), Some(file), |_| None, None).unwrap();
+        assert_eq!(config.health.failure_rate_warning, 0.1);
+        assert_eq!(config.health.failure_min_tasks, 10);
+        assert_eq!(config.health.failure_rate_critical, HealthThresholds::default().failure_rate_critical);
+
+        let file = "[health]\nollama_latency_warning_ms = 9000.0\n";
+        match build(&path(), Some(file), |_| None, None) {
+            Err(ConfigError::Invalid(problems)) => assert!(problems[0].starts_with("health.ollama_latency"), "{:?}", problems),
+            other => panic!("expected validation errors, got {:?}", other),
+        }
+    }
+}
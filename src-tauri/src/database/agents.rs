@@ -0,0 +1,71 @@
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use tracing::info;
+
+use super::DbResult;
+use crate::commands::AgentStatus;
+
+/// Last reported status of each agent (`agents` table)
+#[derive(Debug, Clone)]
+pub struct AgentRepository {
+    pool: SqlitePool,
+}
+
+impl AgentRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Update agent status
+    pub async fn upsert_status(&self, status: &AgentStatus) -> DbResult<()> {
+        let now = Utc::now().to_rfc3339();
+        
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO agents (name, status, last_activity, memory_usage, cpu_usage, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&status.name)
+        .bind(&status.status)
+        .bind(&status.last_activity)
+        .bind(status.memory_usage)
+        .bind(status.cpu_usage)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Updated agent status: {} -> {}", status.name, status.status);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Row;
+
+    #[tokio::test]
+    async fn test_upsert_keeps_one_row_per_agent() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = AgentRepository::new(pool.clone());
+
+        let mut status = AgentStatus {
+            name: "VITRA_LAG".to_string(),
+            status: "running".to_string(),
+            last_activity: "2026-10-01T08:00:00+00:00".to_string(),
+            memory_usage: 0,
+            cpu_usage: 0.0,
+        };
+        repository.upsert_status(&status).await.unwrap();
+        status.status = "stopped".to_string();
+        repository.upsert_status(&status).await.unwrap();
+
+        let rows = sqlx::query("SELECT status FROM agents WHERE name = 'VITRA_LAG'").fetch_all(&pool).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get::<String, _>("status"), "stopped");
+    }
+}
@@ -0,0 +1,230 @@
+use sqlx::{sqlite::{SqlitePool, SqliteRow}, Row};
+use std::str::FromStr;
+
+use super::{DatabaseError, DbResult};
+use crate::alerts::{Alert, AlertRule, AlertState};
+
+/// Alert rules (`alert_rules` table) and the alerts they raised (`alerts` table)
+#[derive(Debug, Clone)]
+pub struct AlertRepository {
+    pool: SqlitePool,
+}
+
+impl AlertRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert_rule(&self, rule: &AlertRule) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO alert_rules (id, name, metric, agent_name, comparison, threshold, duration_secs, severity, sinks, enabled, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&rule.id)
+        .bind(&rule.name)
+        .bind(rule.metric.as_str())
+        .bind(&rule.agent_name)
+        .bind(rule.comparison.as_str())
+        .bind(rule.threshold)
+        .bind(rule.duration_secs)
+        .bind(rule.severity.as_str())
+        .bind(serde_json::to_string(&rule.sinks)?)
+        .bind(rule.enabled)
+        .bind(&rule.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_rule(&self, rule: &AlertRule) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE alert_rules
+            SET name = ?, metric = ?, agent_name = ?, comparison = ?, threshold = ?, duration_secs = ?, severity = ?, sinks = ?, enabled = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&rule.name)
+        .bind(rule.metric.as_str())
+        .bind(&rule.agent_name)
+        .bind(rule.comparison.as_str())
+        .bind(rule.threshold)
+        .bind(rule.duration_secs)
+        .bind(rule.severity.as_str())
+        .bind(serde_json::to_string(&rule.sinks)?)
+        .bind(rule.enabled)
+        .bind(&rule.id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound { entity: "alert rule", id: rule.id.clone() });
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_rule(&self, rule_id: &str) -> DbResult<Option<AlertRule>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM alert_rules WHERE id = ?
+            "#
+        )
+        .bind(rule_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(rule_from_row).transpose()
+    }
+
+    pub async fn list_rules(&self) -> DbResult<Vec<AlertRule>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM alert_rules ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(rule_from_row).collect()
+    }
+
+    /// Remove a rule. Its alerts stay in the history.
+    pub async fn delete_rule(&self, rule_id: &str) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM alert_rules WHERE id = ?
+            "#
+        )
+        .bind(rule_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn insert_alert(&self, alert: &Alert) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO alerts (id, rule_id, rule_name, severity, state, value, message, fired_at, acknowledged_at, resolved_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&alert.id)
+        .bind(&alert.rule_id)
+        .bind(&alert.rule_name)
+        .bind(alert.severity.as_str())
+        .bind(alert.state.as_str())
+        .bind(alert.value)
+        .bind(&alert.message)
+        .bind(&alert.fired_at)
+        .bind(&alert.acknowledged_at)
+        .bind(&alert.resolved_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Save an alert's state and the times it changed
+    pub async fn update_alert(&self, alert: &Alert) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE alerts SET state = ?, acknowledged_at = ?, resolved_at = ? WHERE id = ?
+            "#
+        )
+        .bind(alert.state.as_str())
+        .bind(&alert.acknowledged_at)
+        .bind(&alert.resolved_at)
+        .bind(&alert.id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound { entity: "alert", id: alert.id.clone() });
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_alert(&self, alert_id: &str) -> DbResult<Option<Alert>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM alerts WHERE id = ?
+            "#
+        )
+        .bind(alert_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(alert_from_row).transpose()
+    }
+
+    /// Firing and acknowledged alerts, newest first
+    pub async fn open_alerts(&self) -> DbResult<Vec<Alert>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM alerts WHERE state != ? ORDER BY fired_at DESC
+            "#
+        )
+        .bind(AlertState::Resolved.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(alert_from_row).collect()
+    }
+
+    /// All alerts, newest first
+    pub async fn history(&self, limit: i64) -> DbResult<Vec<Alert>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM alerts ORDER BY fired_at DESC LIMIT ?
+            "#
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(alert_from_row).collect()
+    }
+}
+
+fn parse_column<T: FromStr>(column: &'static str, value: String) -> DbResult<T> {
+    value.parse().map_err(|_| DatabaseError::InvalidValue { column, value })
+}
+
+fn rule_from_row(row: &SqliteRow) -> DbResult<AlertRule> {
+    let sinks: String = row.get("sinks");
+
+    Ok(AlertRule {
+        id: row.get("id"),
+        name: row.get("name"),
+        metric: parse_column("metric", row.get("metric"))?,
+        agent_name: row.get("agent_name"),
+        comparison: parse_column("comparison", row.get("comparison"))?,
+        threshold: row.get("threshold"),
+        duration_secs: row.get("duration_secs"),
+        severity: parse_column("severity", row.get("severity"))?,
+        sinks: serde_json::from_str(&sinks)?,
+        enabled: row.get("enabled"),
+        created_at: row.get("created_at"),
+    })
+}
+
+fn alert_from_row(row: &SqliteRow) -> DbResult<Alert> {
+    Ok(Alert {
+        id: row.get("id"),
+        rule_id: row.get("rule_id"),
+        rule_name: row.get("rule_name"),
+        severity: parse_column("severity", row.get("severity"))?,
+        state: parse_column("state", row.get("state"))?,
+        value: row.get("value"),
+        message: row.get("message"),
+        fired_at: row.get("fired_at"),
+        acknowledged_at: row.get("acknowledged_at"),
+        resolved_at: row.get("resolved_at"),
+    })
+}
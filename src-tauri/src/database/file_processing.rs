@@ -0,0 +1,141 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqlitePool, Row};
+use tracing::info;
+
+use super::DbResult;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileProcessingRecord {
+    pub file_path: String,
+    pub agent_type: String,
+    pub status: String,
+    pub result: Option<String>,
+    pub error_message: Option<String>,
+    pub processing_time_ms: Option<i64>,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// History of processed files (`file_processing` table)
+#[derive(Debug, Clone)]
+pub struct FileProcessingRepository {
+    pool: SqlitePool,
+}
+
+impl FileProcessingRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Log file processing activity
+    pub async fn log(&self, file_path: &str, agent_type: &str) -> DbResult<()> {
+        let now = Utc::now().to_rfc3339();
+        
+        sqlx::query(
+            r#"
+            INSERT INTO file_processing (file_path, agent_type, status, created_at)
+            VALUES (?, ?, 'pending', ?)
+            "#
+        )
+        .bind(file_path)
+        .bind(agent_type)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Logged file processing: {} with {}", file_path, agent_type);
+        Ok(())
+    }
+
+    /// Update file processing status
+    pub async fn update_status(
+        &self,
+        file_path: &str,
+        status: &str,
+        result: Option<&str>,
+        error_message: Option<&str>,
+        processing_time_ms: Option<i64>,
+    ) -> DbResult<()> {
+        let now = Utc::now().to_rfc3339();
+        
+        sqlx::query(
+            r#"
+            UPDATE file_processing 
+            SET status = ?, result = ?, error_message = ?, processing_time_ms = ?, completed_at = ?
+            WHERE file_path = ? AND status = 'pending'
+            "#
+        )
+        .bind(status)
+        .bind(result)
+        .bind(error_message)
+        .bind(processing_time_ms)
+        .bind(&now)
+        .bind(file_path)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Updated file processing status: {} -> {}", file_path, status);
+        Ok(())
+    }
+
+    /// Get recent file processing history
+    pub async fn history(&self, limit: i64) -> DbResult<Vec<FileProcessingRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT file_path, agent_type, status, result, error_message, processing_time_ms, created_at, completed_at
+            FROM file_processing 
+            ORDER BY created_at DESC 
+            LIMIT ?
+            "#
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(FileProcessingRecord {
+                file_path: row.get("file_path"),
+                agent_type: row.get("agent_type"),
+                status: row.get("status"),
+                result: row.get("result"),
+                error_message: row.get("error_message"),
+                processing_time_ms: row.get("processing_time_ms"),
+                created_at: row.get("created_at"),
+                completed_at: row.get("completed_at"),
+            });
+        }
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_update_finishes_pending_entry_only() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = FileProcessingRepository::new(pool);
+
+        repository.log("/audio/talk.wav", "vitra_lag").await.unwrap();
+        repository.update_status("/audio/talk.wav", "failed", None, Some("whisper crashed"), Some(120)).await.unwrap();
+        repository.log("/audio/talk.wav", "vitra_lag").await.unwrap();
+        repository.update_status("/audio/talk.wav", "completed", Some("{}"), None, Some(900)).await.unwrap();
+
+        let mut history = repository.history(10).await.unwrap();
+        history.sort_by_key(|record| record.processing_time_ms);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status, "failed");
+        assert_eq!(history[0].error_message.as_deref(), Some("whisper crashed"));
+        assert_eq!(history[1].status, "completed");
+        assert_eq!(history[1].result.as_deref(), Some("{}"));
+        assert!(history.iter().all(|record| record.agent_type == "vitra_lag" && record.completed_at.is_some()));
+
+        assert_eq!(repository.history(1).await.unwrap().len(), 1);
+    }
+}
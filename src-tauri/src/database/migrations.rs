@@ -0,0 +1,371 @@
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use tracing::info;
+
+use super::{DatabaseError, DbResult};
+
+/// One schema change. Applied migrations are never edited; add a new one instead.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+/// Every migration, in the order they are applied. Versions must be increasing.
+///
+/// The first three use `IF NOT EXISTS` so databases created before migrations
+/// existed (which already have those tables but no `schema_version`) adopt them cleanly.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS agents (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                last_activity TEXT NOT NULL,
+                memory_usage INTEGER NOT NULL,
+                cpu_usage REAL NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS file_processing (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                file_path TEXT NOT NULL,
+                agent_type TEXT NOT NULL,
+                status TEXT NOT NULL,
+                result TEXT,
+                error_message TEXT,
+                processing_time_ms INTEGER,
+                created_at TEXT NOT NULL,
+                completed_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS workflows (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                progress REAL NOT NULL,
+                current_step TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "persistent task queue",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                agent_name TEXT NOT NULL,
+                task_type TEXT NOT NULL,
+                parameters TEXT NOT NULL,
+                priority INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                started_at TEXT,
+                completed_at TEXT,
+                result TEXT,
+                error TEXT,
+                attempt INTEGER NOT NULL DEFAULT 0,
+                max_attempts INTEGER NOT NULL DEFAULT 1,
+                backoff TEXT NOT NULL DEFAULT '{"type":"fixed","delay_secs":0}',
+                next_eligible_at TEXT,
+                depends_on TEXT NOT NULL DEFAULT '[]',
+                graph_id TEXT,
+                node_key TEXT
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks (status, priority, created_at)",
+            "CREATE INDEX IF NOT EXISTS idx_tasks_graph ON tasks (graph_id)",
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "task schedules",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS schedules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                cron_expression TEXT,
+                run_at TEXT,
+                agent_name TEXT NOT NULL,
+                task_type TEXT NOT NULL,
+                parameters TEXT NOT NULL,
+                catch_up TEXT NOT NULL,
+                paused INTEGER NOT NULL DEFAULT 0,
+                next_run_at TEXT,
+                last_run_at TEXT,
+                created_at TEXT NOT NULL
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "one status row per agent",
+        statements: &[
+            // Without a unique name, INSERT OR REPLACE appended a row on every status update
+            "DELETE FROM agents WHERE id NOT IN (SELECT MAX(id) FROM agents GROUP BY name)",
+            "CREATE UNIQUE INDEX idx_agents_name ON agents (name)",
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "alert rules and alerts",
+        statements: &[
+            r#"
+            CREATE TABLE alert_rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                metric TEXT NOT NULL,
+                agent_name TEXT,
+                comparison TEXT NOT NULL,
+                threshold REAL NOT NULL,
+                duration_secs INTEGER NOT NULL DEFAULT 0,
+                severity TEXT NOT NULL,
+                sinks TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE alerts (
+                id TEXT PRIMARY KEY,
+                rule_id TEXT NOT NULL,
+                rule_name TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                state TEXT NOT NULL,
+                value REAL NOT NULL,
+                message TEXT NOT NULL,
+                fired_at TEXT NOT NULL,
+                acknowledged_at TEXT,
+                resolved_at TEXT
+            )
+            "#,
+            "CREATE INDEX idx_alerts_rule_state ON alerts (rule_id, state)",
+            "CREATE INDEX idx_alerts_fired_at ON alerts (fired_at)",
+            // Starter rules, editable from the UI
+            r#"
+            INSERT INTO alert_rules (id, name, metric, agent_name, comparison, threshold, duration_secs, severity, sinks, enabled, created_at) VALUES
+                ('default-memory', 'High memory usage', 'memory_percent', NULL, 'above', 80, 120, 'warning', '[{"type":"event"},{"type":"desktop"}]', 1, strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')),
+                ('default-queue', 'Task queue backlog', 'queue_depth', NULL, 'above', 50, 0, 'warning', '[{"type":"event"}]', 1, strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')),
+                ('default-ghost-failures', 'GHOST failure rate', 'failure_rate', 'ghost_lag', 'above', 0.2, 0, 'critical', '[{"type":"event"},{"type":"desktop"}]', 1, strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')),
+                ('default-disk', 'Low disk space', 'disk_free_bytes', NULL, 'below', 5368709120, 0, 'critical', '[{"type":"event"},{"type":"desktop"}]', 1, strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now'))
+            "#,
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "workflow run errors and steps",
+        statements: &[
+            "ALTER TABLE workflows ADD COLUMN error TEXT",
+            "ALTER TABLE workflows ADD COLUMN finished_at TEXT",
+            "CREATE INDEX idx_workflows_created_at ON workflows (created_at)",
+            r#"
+            CREATE TABLE workflow_steps (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                workflow_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                finished_at TEXT,
+                duration_ms INTEGER,
+                error TEXT
+            )
+            "#,
+            "CREATE INDEX idx_workflow_steps_workflow ON workflow_steps (workflow_id, id)",
+        ],
+    },
+];
+
+/// Newest schema version this binary knows about
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Apply all pending migrations in a single transaction and return the resulting version.
+/// Fails without touching anything if the database was written by a newer binary.
+pub async fn migrate(pool: &SqlitePool) -> DbResult<i64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#
+    ).execute(&mut *tx).await?;
+
+    let current: i64 = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+        .fetch_one(&mut *tx)
+        .await?
+        .get("version");
+
+    let latest = latest_version();
+    if current > latest {
+        return Err(DatabaseError::SchemaTooNew { found: current, supported: latest });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        info!("Applying database migration {}: {}", migration.version, migration.description);
+
+        for statement in migration.statements {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    if current < latest {
+        info!("Database schema upgraded from version {} to {}", current, latest);
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    /// Tables as created by the original `database::init`, before the task queue existed
+    const BASELINE_SCHEMA: &[&str] = &[
+        "CREATE TABLE agents (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL, status TEXT NOT NULL, last_activity TEXT NOT NULL, memory_usage INTEGER NOT NULL, cpu_usage REAL NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        "CREATE TABLE file_processing (id INTEGER PRIMARY KEY AUTOINCREMENT, file_path TEXT NOT NULL, agent_type TEXT NOT NULL, status TEXT NOT NULL, result TEXT, error_message TEXT, processing_time_ms INTEGER, created_at TEXT NOT NULL, completed_at TEXT)",
+        "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL, updated_at TEXT NOT NULL)",
+        "CREATE TABLE workflows (id TEXT PRIMARY KEY, name TEXT NOT NULL, status TEXT NOT NULL, progress REAL NOT NULL, current_step TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+    ];
+
+    /// Tables that `database::init` added for the task queue and schedules before migrations were introduced
+    const QUEUE_SCHEMA: &[&str] = &[
+        r#"CREATE TABLE tasks (id TEXT PRIMARY KEY, agent_name TEXT NOT NULL, task_type TEXT NOT NULL, parameters TEXT NOT NULL, priority INTEGER NOT NULL, status TEXT NOT NULL, created_at TEXT NOT NULL, started_at TEXT, completed_at TEXT, result TEXT, error TEXT, attempt INTEGER NOT NULL DEFAULT 0, max_attempts INTEGER NOT NULL DEFAULT 1, backoff TEXT NOT NULL DEFAULT '{"type":"fixed","delay_secs":0}', next_eligible_at TEXT, depends_on TEXT NOT NULL DEFAULT '[]', graph_id TEXT, node_key TEXT)"#,
+        "CREATE INDEX idx_tasks_status ON tasks (status, priority, created_at)",
+        "CREATE INDEX idx_tasks_graph ON tasks (graph_id)",
+        "CREATE TABLE schedules (id TEXT PRIMARY KEY, name TEXT NOT NULL, cron_expression TEXT, run_at TEXT, agent_name TEXT NOT NULL, task_type TEXT NOT NULL, parameters TEXT NOT NULL, catch_up TEXT NOT NULL, paused INTEGER NOT NULL DEFAULT 0, next_run_at TEXT, last_run_at TEXT, created_at TEXT NOT NULL)",
+    ];
+
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    async fn count(pool: &SqlitePool, sql: &str) -> i64 {
+        sqlx::query(sql).fetch_one(pool).await.unwrap().get(0)
+    }
+
+    #[test]
+    fn test_versions_are_increasing() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_upgrades_baseline_schema_to_latest() {
+        let pool = memory_pool().await;
+        for statement in BASELINE_SCHEMA {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        sqlx::query("INSERT INTO file_processing (file_path, agent_type, status, created_at) VALUES ('/tmp/a.wav', 'vitra_lag', 'completed', '')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO workflows (id, name, status, progress, created_at, updated_at) VALUES ('wf-1', 'import', 'completed', 100.0, '', '')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(migrate(&pool).await.unwrap(), latest_version());
+
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM schema_version").await, MIGRATIONS.len() as i64);
+        for table in ["tasks", "schedules", "alert_rules", "alerts", "workflow_steps"] {
+            let sql = format!("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '{}'", table);
+            assert_eq!(count(&pool, &sql).await, 1, "missing table {}", table);
+        }
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM file_processing").await, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM workflows WHERE id = 'wf-1' AND error IS NULL").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_upgrades_queue_schema_and_keeps_data() {
+        let pool = memory_pool().await;
+        for statement in BASELINE_SCHEMA.iter().chain(QUEUE_SCHEMA) {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        for (status, updated_at) in [("stopped", "2026-01-01"), ("running", "2026-01-02")] {
+            sqlx::query("INSERT INTO agents (name, status, last_activity, memory_usage, cpu_usage, created_at, updated_at) VALUES ('VITRA_LAG', ?, '', 0, 0.0, '', ?)")
+                .bind(status)
+                .bind(updated_at)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        sqlx::query("INSERT INTO settings (key, value, updated_at) VALUES ('app_settings', '{}', '')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(migrate(&pool).await.unwrap(), latest_version());
+
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM schema_version").await, MIGRATIONS.len() as i64);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM settings").await, 1);
+        let status: String = sqlx::query("SELECT status FROM agents WHERE name = 'VITRA_LAG'")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get("status");
+        assert_eq!(status, "running");
+
+        // Running again is a no-op
+        assert_eq!(migrate(&pool).await.unwrap(), latest_version());
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM schema_version").await, MIGRATIONS.len() as i64);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_newer_schema() {
+        let pool = memory_pool().await;
+        migrate(&pool).await.unwrap();
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, 'from the future', '')")
+            .bind(latest_version() + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        match migrate(&pool).await {
+            Err(DatabaseError::SchemaTooNew { found, supported }) => {
+                assert_eq!(found, latest_version() + 1);
+                assert_eq!(supported, latest_version());
+            }
+            other => panic!("expected SchemaTooNew, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous};
+use anyhow::Result;
+use tracing::{info, error};
+use std::path::PathBuf;
+use std::time::Duration;
+use std::fs;
+
+pub mod agents;
+pub mod alerts;
+pub mod file_processing;
+pub mod migrations;
+pub mod schedules;
+pub mod settings;
+pub mod tasks;
+pub mod workflows;
+
+use agents::AgentRepository;
+use alerts::AlertRepository;
+use file_processing::FileProcessingRepository;
+use schedules::ScheduleRepository;
+use settings::SettingsRepository;
+use tasks::TaskRepository;
+use workflows::WorkflowRepository;
+
+/// How long a connection waits for a lock held by another writer
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAX_CONNECTIONS: u32 = 8;
+
+/// Errors returned by the repositories
+#[derive(Debug, thiserror::Error)]
+pub enum DatabaseError {
+    #[error("Database error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+    #[error("Invalid JSON in database: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{entity} not found: {id}")]
+    NotFound { entity: &'static str, id: String },
+    #[error("Invalid {column} value in database: {value}")]
+    InvalidValue { column: &'static str, value: String },
+    #[error("Database schema version {found} is newer than this build supports (up to {supported})")]
+    SchemaTooNew { found: i64, supported: i64 },
+}
+
+pub type DbResult<T> = std::result::Result<T, DatabaseError>;
+
+/// Handle to the shared connection pool. Cheap to clone; every clone uses the same pool.
+#[derive(Debug, Clone)]
+pub struct Database {
+    pool: SqlitePool,
+}
+
+impl Database {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub fn agents(&self) -> AgentRepository {
+        AgentRepository::new(self.pool.clone())
+    }
+
+    pub fn file_processing(&self) -> FileProcessingRepository {
+        FileProcessingRepository::new(self.pool.clone())
+    }
+
+    pub fn settings(&self) -> SettingsRepository {
+        SettingsRepository::new(self.pool.clone())
+    }
+
+    pub fn workflows(&self) -> WorkflowRepository {
+        WorkflowRepository::new(self.pool.clone())
+    }
+
+    pub fn tasks(&self) -> TaskRepository {
+        TaskRepository::new(self.pool.clone())
+    }
+
+    pub fn schedules(&self) -> ScheduleRepository {
+        ScheduleRepository::new(self.pool.clone())
+    }
+
+    pub fn alerts(&self) -> AlertRepository {
+        AlertRepository::new(self.pool.clone())
+    }
+}
+
+/// Directory holding the database and other application data
+pub fn data_dir() -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("VHQ_LAG_v2");
+    path
+}
+
+fn get_database_path() -> PathBuf {
+    let mut path = data_dir();
+    
+    // Ensure directory exists
+    if let Err(e) = fs::create_dir_all(&path) {
+        error!("Failed to create database directory: {}", e);
+        // Fallback to current directory
+        return PathBuf::from("vhq_lag_v2.db");
+    }
+    
+    path.push("vhq_lag_v2.db");
+    path
+}
+
+/// Open the shared connection pool and bring the schema up to date
+pub async fn init() -> Result<Database> {
+    let db_path = get_database_path();
+    info!("Initializing database at: {}", db_path.display());
+    
+    // Create database directory if it doesn't exist
+    if let Some(parent) = db_path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            error!("Failed to create database directory: {}", e);
+            return Err(anyhow::anyhow!("Failed to create database directory: {}", e));
+        }
+    }
+    
+    // WAL lets readers run alongside the writer; the busy timeout makes
+    // concurrent writers wait for the lock instead of failing immediately
+    let options = SqliteConnectOptions::new()
+        .filename(&db_path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT);
+    
+    let pool = SqlitePoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .connect_with(options)
+        .await?;
+    
+    migrations::migrate(&pool).await?;
+    
+    info!("Database initialized successfully");
+    Ok(Database::new(pool))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clones_share_the_pool() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        migrations::migrate(&pool).await.unwrap();
+        let db = Database::new(pool);
+
+        db.clone().settings().save_overrides(&serde_json::json!({ "theme": "dark" })).await.unwrap();
+
+        assert_eq!(db.settings().load_overrides().await.unwrap(), Some(serde_json::json!({ "theme": "dark" })));
+        assert!(db.tasks().get("missing").await.unwrap().is_none());
+    }
+}
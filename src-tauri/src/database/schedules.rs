@@ -0,0 +1,142 @@
+use sqlx::{sqlite::{SqliteConnection, SqlitePool, SqliteRow}, Row};
+
+use super::{tasks, DatabaseError, DbResult};
+use crate::agents::ceo::Task;
+use crate::agents::scheduler::Schedule;
+
+/// Recurring and one-shot task schedules (`schedules` table)
+#[derive(Debug, Clone)]
+pub struct ScheduleRepository {
+    pool: SqlitePool,
+}
+
+impl ScheduleRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Persist a new schedule
+    pub async fn insert(&self, schedule: &Schedule) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO schedules (id, name, cron_expression, run_at, agent_name, task_type, parameters, catch_up, paused, next_run_at, last_run_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&schedule.id)
+        .bind(&schedule.name)
+        .bind(&schedule.cron_expression)
+        .bind(&schedule.run_at)
+        .bind(&schedule.agent_name)
+        .bind(&schedule.task_type)
+        .bind(serde_json::to_string(&schedule.parameters)?)
+        .bind(schedule.catch_up.as_str())
+        .bind(schedule.paused)
+        .bind(&schedule.next_run_at)
+        .bind(&schedule.last_run_at)
+        .bind(&schedule.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Save a schedule's pause flag and run times
+    pub async fn update(&self, schedule: &Schedule) -> DbResult<()> {
+        let rows = update_row(&mut *self.pool.acquire().await?, schedule).await?;
+        if rows == 0 {
+            return Err(DatabaseError::NotFound { entity: "schedule", id: schedule.id.clone() });
+        }
+
+        Ok(())
+    }
+
+    /// Queue a schedule run's tasks and advance the schedule in one transaction,
+    /// so a crash can neither lose nor repeat a run
+    pub async fn record_run(&self, schedule: &Schedule, tasks: &[Task]) -> DbResult<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        for task in tasks {
+            tasks::insert_row(&mut tx, task).await?;
+        }
+        update_row(&mut tx, schedule).await?;
+        
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, schedule_id: &str) -> DbResult<Option<Schedule>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM schedules WHERE id = ?
+            "#
+        )
+        .bind(schedule_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(from_row).transpose()
+    }
+
+    pub async fn list(&self) -> DbResult<Vec<Schedule>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM schedules ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(from_row).collect()
+    }
+
+    /// Remove a schedule. Tasks it already queued are kept.
+    pub async fn delete(&self, schedule_id: &str) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM schedules WHERE id = ?
+            "#
+        )
+        .bind(schedule_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+async fn update_row(conn: &mut SqliteConnection, schedule: &Schedule) -> DbResult<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE schedules SET paused = ?, next_run_at = ?, last_run_at = ? WHERE id = ?
+        "#
+    )
+    .bind(schedule.paused)
+    .bind(&schedule.next_run_at)
+    .bind(&schedule.last_run_at)
+    .bind(&schedule.id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+fn from_row(row: &SqliteRow) -> DbResult<Schedule> {
+    let parameters: String = row.get("parameters");
+    let catch_up: String = row.get("catch_up");
+
+    Ok(Schedule {
+        id: row.get("id"),
+        name: row.get("name"),
+        cron_expression: row.get("cron_expression"),
+        run_at: row.get("run_at"),
+        agent_name: row.get("agent_name"),
+        task_type: row.get("task_type"),
+        parameters: serde_json::from_str(&parameters)?,
+        catch_up: catch_up.parse().map_err(|_| DatabaseError::InvalidValue { column: "catch_up", value: catch_up.clone() })?,
+        paused: row.get("paused"),
+        next_run_at: row.get("next_run_at"),
+        last_run_at: row.get("last_run_at"),
+        created_at: row.get("created_at"),
+    })
+}
@@ -0,0 +1,80 @@
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::{sqlite::SqlitePool, Row};
+use tracing::info;
+
+use super::DbResult;
+
+/// Application settings (`settings` table)
+#[derive(Debug, Clone)]
+pub struct SettingsRepository {
+    pool: SqlitePool,
+}
+
+impl SettingsRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Save the settings changed from the app. Only changed keys are stored, so
+    /// `config.toml` and environment variables still apply to everything else.
+    pub async fn save_overrides(&self, overrides: &Value) -> DbResult<()> {
+        let now = Utc::now().to_rfc3339();
+        
+        let settings_json = serde_json::to_string(overrides)?;
+        
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO settings (key, value, updated_at)
+            VALUES ('app_settings', ?, ?)
+            "#
+        )
+        .bind(&settings_json)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Settings saved successfully");
+        Ok(())
+    }
+
+    /// Load the settings changed from the app
+    pub async fn load_overrides(&self) -> DbResult<Option<Value>> {
+        let row = sqlx::query(
+            r#"
+            SELECT value FROM settings WHERE key = 'app_settings'
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => {
+                let value: String = row.get("value");
+                Ok(Some(serde_json::from_str(&value)?))
+            }
+            None => Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_overrides_round_trip() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = SettingsRepository::new(pool);
+
+        assert_eq!(repository.load_overrides().await.unwrap(), None);
+
+        repository.save_overrides(&serde_json::json!({ "theme": "light" })).await.unwrap();
+        let overrides = serde_json::json!({ "vitra": { "whisper_model": "small" } });
+        repository.save_overrides(&overrides).await.unwrap();
+
+        assert_eq!(repository.load_overrides().await.unwrap(), Some(overrides));
+    }
+}
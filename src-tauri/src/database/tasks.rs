@@ -0,0 +1,463 @@
+use chrono::Utc;
+use sqlx::{sqlite::{SqliteConnection, SqlitePool, SqliteRow}, Row};
+use std::collections::HashMap;
+use tracing::info;
+
+use super::{DatabaseError, DbResult};
+use crate::agents::ceo::{Task, TaskStatus, RecoveryPolicy};
+
+/// Persistent CEO task queue (`tasks` table)
+#[derive(Debug, Clone)]
+pub struct TaskRepository {
+    pool: SqlitePool,
+}
+
+impl TaskRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert a new task into the persistent queue
+    pub async fn insert(&self, task: &Task) -> DbResult<()> {
+        self.insert_many(std::slice::from_ref(task)).await
+    }
+
+    /// Insert several tasks atomically, e.g. all nodes of a task graph
+    pub async fn insert_many(&self, tasks: &[Task]) -> DbResult<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        for task in tasks {
+            insert_row(&mut tx, task).await?;
+        }
+        
+        tx.commit().await?;
+
+        info!("Persisted {} task(s)", tasks.len());
+        Ok(())
+    }
+
+    /// Replace a task's parameters, e.g. after upstream outputs were passed in
+    pub async fn update_parameters(&self, task_id: &str, parameters: &serde_json::Value) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE tasks SET parameters = ? WHERE id = ?
+            "#
+        )
+        .bind(serde_json::to_string(parameters)?)
+        .bind(task_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound { entity: "task", id: task_id.to_string() });
+        }
+
+        Ok(())
+    }
+
+    /// Record the outcome of a running task, or re-queue it for retry. Returns false if it is no longer running.
+    pub async fn finish(&self, task: &Task) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE tasks
+            SET status = ?, started_at = ?, completed_at = ?, result = ?, error = ?, next_eligible_at = ?
+            WHERE id = ? AND status = ?
+            "#
+        )
+        .bind(task.status.as_str())
+        .bind(&task.started_at)
+        .bind(&task.completed_at)
+        .bind(&task.result)
+        .bind(&task.error)
+        .bind(&task.next_eligible_at)
+        .bind(&task.id)
+        .bind(TaskStatus::Running.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Atomically move a pending task to running and mirror the change on `task`.
+    /// Returns false if another worker claimed it first.
+    pub async fn claim(&self, task: &mut Task) -> DbResult<bool> {
+        let now = Utc::now().to_rfc3339();
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE tasks SET status = ?, started_at = ?, attempt = attempt + 1
+            WHERE id = ? AND status = ?
+            "#
+        )
+        .bind(TaskStatus::Running.as_str())
+        .bind(&now)
+        .bind(&task.id)
+        .bind(TaskStatus::Pending.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        task.status = TaskStatus::Running;
+        task.started_at = Some(now);
+        task.attempt += 1;
+        Ok(true)
+    }
+
+    /// Mark a pending or running task as cancelled, optionally recording why.
+    /// Returns false if the task had already finished.
+    pub async fn cancel(&self, task_id: &str, reason: Option<&str>) -> DbResult<bool> {
+        let now = Utc::now().to_rfc3339();
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE tasks SET status = ?, completed_at = ?, error = COALESCE(?, error)
+            WHERE id = ? AND status IN (?, ?)
+            "#
+        )
+        .bind(TaskStatus::Cancelled.as_str())
+        .bind(&now)
+        .bind(reason)
+        .bind(task_id)
+        .bind(TaskStatus::Pending.as_str())
+        .bind(TaskStatus::Running.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Reset a dead-lettered task to pending with a fresh attempt budget. Returns false if it is not dead-lettered.
+    pub async fn replay(&self, task_id: &str) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE tasks
+            SET status = ?, attempt = 0, next_eligible_at = NULL, started_at = NULL, completed_at = NULL, error = NULL
+            WHERE id = ? AND status = ?
+            "#
+        )
+        .bind(TaskStatus::Pending.as_str())
+        .bind(task_id)
+        .bind(TaskStatus::DeadLetter.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Get a single task by ID
+    pub async fn get(&self, task_id: &str) -> DbResult<Option<Task>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM tasks WHERE id = ?
+            "#
+        )
+        .bind(task_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| from_row(&row)).transpose()
+    }
+
+    /// Get all tasks with the given status, highest priority and oldest first
+    pub async fn list_by_status(&self, status: TaskStatus) -> DbResult<Vec<Task>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM tasks WHERE status = ?
+            ORDER BY priority DESC, created_at ASC
+            "#
+        )
+        .bind(status.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(from_row).collect()
+    }
+
+    /// Get the tasks with the given IDs
+    pub async fn list_by_ids(&self, task_ids: &[String]) -> DbResult<Vec<Task>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM tasks WHERE id IN (SELECT value FROM json_each(?))
+            "#
+        )
+        .bind(serde_json::to_string(task_ids)?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(from_row).collect()
+    }
+
+    /// Get the tasks that directly depend on the given task
+    pub async fn list_dependents(&self, task_id: &str) -> DbResult<Vec<Task>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM tasks
+            WHERE EXISTS (SELECT 1 FROM json_each(tasks.depends_on) WHERE json_each.value = ?)
+            "#
+        )
+        .bind(task_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(from_row).collect()
+    }
+
+    /// Get all tasks of a task graph in submission order
+    pub async fn list_by_graph(&self, graph_id: &str) -> DbResult<Vec<Task>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM tasks WHERE graph_id = ? ORDER BY rowid ASC
+            "#
+        )
+        .bind(graph_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(from_row).collect()
+    }
+
+    /// Count tasks grouped by status
+    pub async fn count_by_status(&self) -> DbResult<HashMap<String, i64>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT status, COUNT(*) AS count FROM tasks GROUP BY status
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("status"), row.get("count"))).collect())
+    }
+
+    /// Finished and failed task counts per agent, for tasks completed since the given time
+    pub async fn outcomes_since(&self, since: &str) -> DbResult<HashMap<String, (i64, i64)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT agent_name,
+                   COUNT(*) AS finished,
+                   SUM(CASE WHEN status = ? THEN 0 ELSE 1 END) AS failed
+            FROM tasks
+            WHERE status IN (?, ?, ?) AND completed_at >= ?
+            GROUP BY agent_name
+            "#
+        )
+        .bind(TaskStatus::Completed.as_str())
+        .bind(TaskStatus::Completed.as_str())
+        .bind(TaskStatus::Failed.as_str())
+        .bind(TaskStatus::DeadLetter.as_str())
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("agent_name"), (row.get("finished"), row.get("failed")))).collect())
+    }
+
+    /// Delete completed, failed and cancelled tasks, keeping parents that
+    /// pending or running graph tasks still take their inputs from
+    pub async fn delete_finished(&self) -> DbResult<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM tasks
+            WHERE status IN (?, ?, ?)
+              AND NOT EXISTS (
+                  SELECT 1 FROM tasks AS child, json_each(child.depends_on)
+                  WHERE json_each.value = tasks.id AND child.status IN (?, ?)
+              )
+            "#
+        )
+        .bind(TaskStatus::Completed.as_str())
+        .bind(TaskStatus::Failed.as_str())
+        .bind(TaskStatus::Cancelled.as_str())
+        .bind(TaskStatus::Pending.as_str())
+        .bind(TaskStatus::Running.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        info!("Deleted {} finished tasks", result.rows_affected());
+        Ok(result.rows_affected())
+    }
+
+    /// Resolve tasks left in `Running` by a previous session according to the recovery policy.
+    /// Requeued tasks keep their attempt count, so one that keeps crashing the app ends in the
+    /// dead-letter queue instead of running on every launch.
+    pub async fn recover_running(&self, policy: RecoveryPolicy) -> DbResult<u64> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        
+        let requeued = match policy {
+            RecoveryPolicy::Requeue => {
+                sqlx::query(
+                    r#"
+                    UPDATE tasks SET status = ?, started_at = NULL
+                    WHERE status = ? AND attempt < max_attempts
+                    "#
+                )
+                .bind(TaskStatus::Pending.as_str())
+                .bind(TaskStatus::Running.as_str())
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+            }
+            RecoveryPolicy::MarkFailed => 0,
+        };
+        
+        let resolved = sqlx::query(
+            r#"
+            UPDATE tasks SET status = ?, completed_at = ?, error = ?
+            WHERE status = ?
+            "#
+        )
+        .bind(match policy {
+            RecoveryPolicy::Requeue => TaskStatus::DeadLetter.as_str(),
+            RecoveryPolicy::MarkFailed => TaskStatus::Failed.as_str(),
+        })
+        .bind(&now)
+        .bind("Interrupted by application shutdown")
+        .bind(TaskStatus::Running.as_str())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        tx.commit().await?;
+
+        info!("Recovered {} interrupted tasks ({:?}), {} requeued", requeued + resolved, policy, requeued);
+        Ok(requeued + resolved)
+    }
+}
+
+pub(super) async fn insert_row(conn: &mut SqliteConnection, task: &Task) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO tasks (id, agent_name, task_type, parameters, priority, status, created_at, started_at, completed_at, result, error, attempt, max_attempts, backoff, next_eligible_at, depends_on, graph_id, node_key)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&task.id)
+    .bind(&task.agent_name)
+    .bind(&task.task_type)
+    .bind(serde_json::to_string(&task.parameters)?)
+    .bind(task.priority)
+    .bind(task.status.as_str())
+    .bind(&task.created_at)
+    .bind(&task.started_at)
+    .bind(&task.completed_at)
+    .bind(&task.result)
+    .bind(&task.error)
+    .bind(task.attempt)
+    .bind(task.max_attempts)
+    .bind(serde_json::to_string(&task.backoff)?)
+    .bind(&task.next_eligible_at)
+    .bind(serde_json::to_string(&task.depends_on)?)
+    .bind(&task.graph_id)
+    .bind(&task.node_key)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+fn from_row(row: &SqliteRow) -> DbResult<Task> {
+    let parameters: String = row.get("parameters");
+    let status: String = row.get("status");
+    let backoff: String = row.get("backoff");
+    let depends_on: String = row.get("depends_on");
+    
+    Ok(Task {
+        id: row.get("id"),
+        agent_name: row.get("agent_name"),
+        task_type: row.get("task_type"),
+        parameters: serde_json::from_str(&parameters)?,
+        priority: row.get("priority"),
+        status: status.parse().map_err(|_| DatabaseError::InvalidValue { column: "status", value: status.clone() })?,
+        created_at: row.get("created_at"),
+        started_at: row.get("started_at"),
+        completed_at: row.get("completed_at"),
+        result: row.get("result"),
+        error: row.get("error"),
+        attempt: row.get("attempt"),
+        max_attempts: row.get("max_attempts"),
+        backoff: serde_json::from_str(&backoff)?,
+        next_eligible_at: row.get("next_eligible_at"),
+        depends_on: serde_json::from_str(&depends_on)?,
+        graph_id: row.get("graph_id"),
+        node_key: row.get("node_key"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::retry::RetryPolicy;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_finish_keeps_claimed_start_time() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = TaskRepository::new(pool);
+
+        let mut task = Task::new("ghost_lag", "generate", serde_json::json!({}), &RetryPolicy::default());
+        repository.insert(&task).await.unwrap();
+        assert!(repository.claim(&mut task).await.unwrap());
+        assert!(!repository.claim(&mut task.clone()).await.unwrap());
+        assert_eq!(task.attempt, 1);
+
+        task.status = TaskStatus::Completed;
+        task.completed_at = Some(Utc::now().to_rfc3339());
+        assert!(repository.finish(&task).await.unwrap());
+
+        let stored = repository.get(&task.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, TaskStatus::Completed);
+        assert!(stored.started_at.is_some());
+        assert_eq!(stored.started_at, task.started_at);
+        assert_eq!(stored.attempt, 1);
+    }
+
+    #[tokio::test]
+    async fn test_requeue_dead_letters_tasks_out_of_attempts() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = TaskRepository::new(pool);
+
+        let mut retried = Task::new("vitra_lag", "transcribe", serde_json::json!({}), &RetryPolicy::default());
+        let mut last_attempt = Task::new("vitra_lag", "transcribe", serde_json::json!({ "options": { "max_attempts": 1 } }), &RetryPolicy::default());
+        for task in [&mut retried, &mut last_attempt] {
+            repository.insert(task).await.unwrap();
+            assert!(repository.claim(task).await.unwrap());
+        }
+
+        assert_eq!(repository.recover_running(RecoveryPolicy::Requeue).await.unwrap(), 2);
+
+        let retried = repository.get(&retried.id).await.unwrap().unwrap();
+        assert_eq!(retried.status, TaskStatus::Pending);
+        assert_eq!(retried.attempt, 1);
+        let last_attempt = repository.get(&last_attempt.id).await.unwrap().unwrap();
+        assert_eq!(last_attempt.status, TaskStatus::DeadLetter);
+        assert_eq!(last_attempt.error.as_deref(), Some("Interrupted by application shutdown"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_finished_keeps_parents_of_waiting_tasks() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = TaskRepository::new(pool);
+
+        let task = |status: TaskStatus| {
+            let mut task = Task::new("ghost_lag", "generate", serde_json::json!({}), &RetryPolicy::default());
+            task.status = status;
+            task
+        };
+        let parent = task(TaskStatus::Completed);
+        let done = task(TaskStatus::Completed);
+        let mut child = task(TaskStatus::Pending);
+        child.depends_on = vec![parent.id.clone()];
+        repository.insert_many(&[parent.clone(), done.clone(), child.clone()]).await.unwrap();
+
+        assert_eq!(repository.delete_finished().await.unwrap(), 1);
+        assert!(repository.get(&done.id).await.unwrap().is_none());
+        assert!(repository.get(&parent.id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::{SqlitePool, SqliteRow}, Row};
+use tracing::info;
+
+use super::DbResult;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRecord {
+    pub id: String,
+    pub name: String,
+    pub status: String,
+    pub progress: f64,
+    pub current_step: Option<String>,
+    /// Why the run failed
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// When the run completed or failed
+    pub finished_at: Option<String>,
+}
+
+/// One node of a workflow run (`workflow_steps` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub id: i64,
+    pub workflow_id: String,
+    pub name: String,
+    /// running, completed or failed
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// A stored run with its steps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    #[serde(flatten)]
+    pub record: WorkflowRecord,
+    /// Until `finished_at`, absent while the run is going on
+    pub duration_ms: Option<i64>,
+    pub steps: Vec<WorkflowStep>,
+}
+
+impl WorkflowRun {
+    pub fn new(record: WorkflowRecord, steps: Vec<WorkflowStep>) -> Self {
+        Self {
+            duration_ms: record.finished_at.as_deref().and_then(|finished| elapsed_ms(&record.created_at, finished)),
+            record,
+            steps,
+        }
+    }
+}
+
+/// Which runs to list. Bounds are RFC 3339 timestamps compared with `created_at`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkflowFilter {
+    pub status: Option<String>,
+    pub name: Option<String>,
+    /// Inclusive
+    pub since: Option<String>,
+    /// Exclusive
+    pub until: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Workflow runs and their progress (`workflows` table)
+#[derive(Debug, Clone)]
+pub struct WorkflowRepository {
+    pool: SqlitePool,
+}
+
+impl WorkflowRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert a workflow or replace the stored one with the same ID
+    pub async fn upsert(&self, workflow: &WorkflowRecord) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workflows (id, name, status, progress, current_step, error, created_at, updated_at, finished_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                status = excluded.status,
+                progress = excluded.progress,
+                current_step = excluded.current_step,
+                error = excluded.error,
+                updated_at = excluded.updated_at,
+                finished_at = excluded.finished_at
+            "#
+        )
+        .bind(&workflow.id)
+        .bind(&workflow.name)
+        .bind(&workflow.status)
+        .bind(workflow.progress)
+        .bind(&workflow.current_step)
+        .bind(&workflow.error)
+        .bind(&workflow.created_at)
+        .bind(&workflow.updated_at)
+        .bind(&workflow.finished_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!("Saved workflow {} ({})", workflow.name, workflow.status);
+        Ok(())
+    }
+
+    pub async fn get(&self, workflow_id: &str) -> DbResult<Option<WorkflowRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM workflows WHERE id = ?
+            "#
+        )
+        .bind(workflow_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(from_row))
+    }
+
+    /// Runs matching `filter` with their steps, newest first
+    pub async fn history(&self, filter: &WorkflowFilter) -> DbResult<Vec<WorkflowRun>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM workflows
+            WHERE (?1 IS NULL OR status = ?1)
+              AND (?2 IS NULL OR name = ?2)
+              AND (?3 IS NULL OR created_at >= ?3)
+              AND (?4 IS NULL OR created_at < ?4)
+            ORDER BY created_at DESC
+            LIMIT ?5
+            "#
+        )
+        .bind(&filter.status)
+        .bind(&filter.name)
+        .bind(&filter.since)
+        .bind(&filter.until)
+        .bind(filter.limit.unwrap_or(100))
+        .fetch_all(&self.pool)
+        .await?;
+        let records: Vec<WorkflowRecord> = rows.iter().map(from_row).collect();
+
+        let ids: Vec<&str> = records.iter().map(|record| record.id.as_str()).collect();
+        let step_rows = sqlx::query(
+            r#"
+            SELECT * FROM workflow_steps WHERE workflow_id IN (SELECT value FROM json_each(?)) ORDER BY id
+            "#
+        )
+        .bind(serde_json::to_string(&ids)?)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut steps: Vec<WorkflowStep> = step_rows.iter().map(step_from_row).collect();
+
+        Ok(records.into_iter().map(|record| {
+            let (own, rest) = steps.drain(..).partition(|step| step.workflow_id == record.id);
+            steps = rest;
+            WorkflowRun::new(record, own)
+        }).collect())
+    }
+
+    /// The step a run is currently in
+    pub async fn open_step(&self, workflow_id: &str) -> DbResult<Option<WorkflowStep>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM workflow_steps WHERE workflow_id = ? AND finished_at IS NULL ORDER BY id DESC LIMIT 1
+            "#
+        )
+        .bind(workflow_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(step_from_row))
+    }
+
+    pub async fn start_step(&self, workflow_id: &str, name: &str, started_at: &str) -> DbResult<WorkflowStep> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO workflow_steps (workflow_id, name, status, started_at) VALUES (?, ?, 'running', ?)
+            "#
+        )
+        .bind(workflow_id)
+        .bind(name)
+        .bind(started_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(WorkflowStep {
+            id: result.last_insert_rowid(),
+            workflow_id: workflow_id.to_string(),
+            name: name.to_string(),
+            status: "running".to_string(),
+            started_at: started_at.to_string(),
+            finished_at: None,
+            duration_ms: None,
+            error: None,
+        })
+    }
+
+    /// Store the outcome of a step and return it updated
+    pub async fn finish_step(&self, step: &WorkflowStep, status: &str, error: Option<&str>, finished_at: &str) -> DbResult<WorkflowStep> {
+        let finished = WorkflowStep {
+            status: status.to_string(),
+            finished_at: Some(finished_at.to_string()),
+            duration_ms: elapsed_ms(&step.started_at, finished_at),
+            error: error.map(str::to_string),
+            ..step.clone()
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE workflow_steps SET status = ?, error = ?, finished_at = ?, duration_ms = ? WHERE id = ?
+            "#
+        )
+        .bind(&finished.status)
+        .bind(&finished.error)
+        .bind(&finished.finished_at)
+        .bind(finished.duration_ms)
+        .bind(step.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(finished)
+    }
+
+}
+
+fn from_row(row: &SqliteRow) -> WorkflowRecord {
+    WorkflowRecord {
+        id: row.get("id"),
+        name: row.get("name"),
+        status: row.get("status"),
+        progress: row.get("progress"),
+        current_step: row.get("current_step"),
+        error: row.get("error"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        finished_at: row.get("finished_at"),
+    }
+}
+
+fn step_from_row(row: &SqliteRow) -> WorkflowStep {
+    WorkflowStep {
+        id: row.get("id"),
+        workflow_id: row.get("workflow_id"),
+        name: row.get("name"),
+        status: row.get("status"),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+        duration_ms: row.get("duration_ms"),
+        error: row.get("error"),
+    }
+}
+
+/// Milliseconds between two RFC 3339 timestamps
+fn elapsed_ms(start: &str, end: &str) -> Option<i64> {
+    let start = chrono::DateTime::parse_from_rfc3339(start).ok()?;
+    let end = chrono::DateTime::parse_from_rfc3339(end).ok()?;
+    Some((end - start).num_milliseconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn run(id: &str, status: &str, created_at: &str) -> WorkflowRecord {
+        WorkflowRecord {
+            id: id.to_string(),
+            name: "VITRA Transcription Pipeline".to_string(),
+            status: status.to_string(),
+            progress: 0.0,
+            current_step: None,
+            error: None,
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            finished_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_filters_runs_and_times_steps() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let repository = WorkflowRepository::new(pool);
+
+        let mut failed = run("1", "failed", "2026-10-01T08:00:00+00:00");
+        failed.finished_at = Some("2026-10-01T08:00:05+00:00".to_string());
+        repository.upsert(&failed).await.unwrap();
+        repository.upsert(&run("2", "running", "2026-10-02T08:00:00+00:00")).await.unwrap();
+
+        let step = repository.start_step("1", "Execute VITRA", "2026-10-01T08:00:01+00:00").await.unwrap();
+        repository.finish_step(&step, "failed", Some("exit code 1"), "2026-10-01T08:00:03.500+00:00").await.unwrap();
+        repository.start_step("2", "File Trigger", "2026-10-02T08:00:00+00:00").await.unwrap();
+
+        let all = repository.history(&WorkflowFilter::default()).await.unwrap();
+        assert_eq!(all.iter().map(|run| run.record.id.as_str()).collect::<Vec<_>>(), ["2", "1"]);
+        assert_eq!(repository.open_step("2").await.unwrap().unwrap().name, "File Trigger");
+
+        let filter = WorkflowFilter {
+            status: Some("failed".to_string()),
+            until: Some("2026-10-02T00:00:00+00:00".to_string()),
+            ..WorkflowFilter::default()
+        };
+        let failed = repository.history(&filter).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].duration_ms, Some(5000));
+        assert_eq!(failed[0].steps[0].duration_ms, Some(2500));
+        assert_eq!(failed[0].steps[0].error.as_deref(), Some("exit code 1"));
+    }
+}
@@ -0,0 +1,269 @@
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
+use crate::config::ConfigError;
+use crate::database::DatabaseError;
+
+/// Errors surfaced to the frontend and to the task retry logic.
+///
+/// Agents still return `anyhow::Result`; they raise a `VhqError` where the cause is
+/// known, and `From<anyhow::Error>` recovers it at the command and task boundaries.
+#[derive(Debug, thiserror::Error)]
+pub enum VhqError {
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("File not found: {0}")]
+    FileNotFound(String),
+    #[error("{entity} not found: {id}")]
+    NotFound { entity: &'static str, id: String },
+    /// External program (whisper, ffprobe, ...) is not installed
+    #[error("{tool} is not available: {message}")]
+    ToolMissing { tool: String, message: String },
+    /// External program ran but failed. `transient` when it crashed or could not
+    /// be run rather than rejecting its input, so trying again may help.
+    #[error("{tool} failed: {message}")]
+    ToolFailed { tool: String, message: String, transient: bool },
+    /// File held open or locked by another process
+    #[error("File is locked by another process: {0}")]
+    FileLocked(String),
+    /// HTTP backend (Ollama, n8n, ...) unreachable or answering with an error.
+    /// `status` is absent when no response was received.
+    #[error("{service} error: {message}")]
+    Backend { service: String, status: Option<u16>, message: String },
+    #[error("{operation} timed out after {secs}s")]
+    Timeout { operation: String, secs: u64 },
+    #[error("Task cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Database(DatabaseError),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl VhqError {
+    pub fn invalid_input(message: impl Display) -> Self {
+        VhqError::InvalidInput(message.to_string())
+    }
+
+    pub fn tool_missing(tool: &str, message: impl Display) -> Self {
+        VhqError::ToolMissing { tool: tool.to_string(), message: message.to_string() }
+    }
+
+    /// Tool rejected its input, e.g. a bad model name or an unreadable file
+    pub fn tool_failed(tool: &str, message: impl Display) -> Self {
+        VhqError::ToolFailed { tool: tool.to_string(), message: message.to_string(), transient: false }
+    }
+
+    /// Tool crashed, was killed or could not be started
+    pub fn tool_crashed(tool: &str, message: impl Display) -> Self {
+        VhqError::ToolFailed { tool: tool.to_string(), message: message.to_string(), transient: true }
+    }
+
+    pub fn backend(service: &str, status: Option<u16>, message: impl Display) -> Self {
+        VhqError::Backend { service: service.to_string(), status, message: message.to_string() }
+    }
+
+    /// Backend error from a failed HTTP request
+    pub fn request(service: &str, error: &reqwest::Error) -> Self {
+        Self::backend(service, error.status().map(|s| s.as_u16()), error)
+    }
+
+    /// Stable identifier the frontend can match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            VhqError::InvalidInput(_) => "invalid_input",
+            VhqError::FileNotFound(_) => "file_not_found",
+            VhqError::NotFound { .. } => "not_found",
+            VhqError::ToolMissing { .. } => "tool_missing",
+            VhqError::ToolFailed { .. } => "tool_failed",
+            VhqError::FileLocked(_) => "file_locked",
+            VhqError::Backend { .. } => "backend_error",
+            VhqError::Timeout { .. } => "timeout",
+            VhqError::Cancelled => "cancelled",
+            VhqError::Database(_) => "database_error",
+            VhqError::Internal(_) => "internal",
+        }
+    }
+
+    /// Whether the same operation may succeed if tried again later
+    pub fn retryable(&self) -> bool {
+        match self {
+            VhqError::ToolFailed { transient, .. } => *transient,
+            VhqError::FileLocked(_) | VhqError::Timeout { .. } => true,
+            // No response at all, rate limiting or a server-side failure
+            VhqError::Backend { status, .. } => match status {
+                None => true,
+                Some(status) => *status == 429 || *status >= 500,
+            },
+            VhqError::Database(DatabaseError::Sqlx(sqlx::Error::PoolTimedOut)) => true,
+            VhqError::Database(DatabaseError::Sqlx(sqlx::Error::Database(e))) => {
+                // SQLITE_BUSY and SQLITE_LOCKED, including their extended codes
+                e.code()
+                    .and_then(|code| code.parse::<i32>().ok())
+                    .map(|code| matches!(code & 0xff, 5 | 6))
+                    .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Structured context for the variant, `null` when there is none
+    pub fn details(&self) -> serde_json::Value {
+        match self {
+            VhqError::FileNotFound(path) => serde_json::json!({ "path": path }),
+            VhqError::NotFound { entity, id } => serde_json::json!({ "entity": entity, "id": id }),
+            VhqError::ToolMissing { tool, .. } | VhqError::ToolFailed { tool, .. } => {
+                serde_json::json!({ "tool": tool })
+            }
+            VhqError::Backend { service, status, .. } => {
+                serde_json::json!({ "service": service, "status": status })
+            }
+            VhqError::Timeout { operation, secs } => {
+                serde_json::json!({ "operation": operation, "secs": secs })
+            }
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Shape of a `VhqError` as seen by the frontend
+#[derive(Serialize)]
+struct ErrorPayload {
+    code: &'static str,
+    message: String,
+    details: serde_json::Value,
+    retryable: bool,
+}
+
+impl Serialize for VhqError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorPayload {
+            code: self.code(),
+            message: self.to_string(),
+            details: self.details(),
+            retryable: self.retryable(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl From<DatabaseError> for VhqError {
+    fn from(error: DatabaseError) -> Self {
+        match error {
+            DatabaseError::NotFound { entity, id } => VhqError::NotFound { entity, id },
+            error => VhqError::Database(error),
+        }
+    }
+}
+
+impl From<ConfigError> for VhqError {
+    fn from(error: ConfigError) -> Self {
+        match error {
+            ConfigError::Parse { .. } | ConfigError::Invalid(_) => VhqError::InvalidInput(error.to_string()),
+            ConfigError::Database(error) => error.into(),
+            error => VhqError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for VhqError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<VhqError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<DatabaseError>() {
+            Ok(error) => return error.into(),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<std::io::Error>() {
+            Ok(error) => return error.into(),
+            Err(error) => error,
+        };
+        match error.downcast::<sqlx::Error>() {
+            Ok(error) => VhqError::Database(DatabaseError::Sqlx(error)),
+            Err(error) => VhqError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for VhqError {
+    fn from(error: std::io::Error) -> Self {
+        if is_locked(&error) {
+            VhqError::FileLocked(error.to_string())
+        } else {
+            VhqError::Internal(error.to_string())
+        }
+    }
+}
+
+/// Whether an io error means another process holds the file
+pub fn is_locked(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    matches!(error.kind(), ErrorKind::ResourceBusy | ErrorKind::ExecutableFileBusy)
+        || (cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33)))
+}
+
+impl From<tokio::task::JoinError> for VhqError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        VhqError::Internal(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(VhqError::backend("Ollama", None, "connection refused").retryable());
+        assert!(VhqError::backend("Ollama", Some(503), "overloaded").retryable());
+        assert!(!VhqError::backend("Ollama", Some(404), "model not found").retryable());
+        assert!(VhqError::tool_crashed("whisper", "segmentation fault").retryable());
+        assert!(!VhqError::tool_failed("whisper", "model 'huge' not found").retryable());
+        assert!(VhqError::Timeout { operation: "Task".to_string(), secs: 300 }.retryable());
+        assert!(!VhqError::FileNotFound("missing.mp3".to_string()).retryable());
+        assert!(!VhqError::tool_missing("whisper", "not installed").retryable());
+        assert!(!VhqError::Cancelled.retryable());
+    }
+
+    #[test]
+    fn test_recovered_from_anyhow() {
+        let error: anyhow::Error = VhqError::FileNotFound("a.mp3".to_string()).into();
+        assert_eq!(VhqError::from(error.context("Processing failed")).code(), "file_not_found");
+
+        let error: anyhow::Error = DatabaseError::NotFound { entity: "task", id: "t1".to_string() }.into();
+        assert_eq!(VhqError::from(error).code(), "not_found");
+
+        assert_eq!(VhqError::from(anyhow::anyhow!("boom")).code(), "internal");
+    }
+
+    #[test]
+    fn test_locked_files_are_retryable() {
+        let busy: anyhow::Error = std::io::Error::from(std::io::ErrorKind::ResourceBusy).into();
+        let error = VhqError::from(busy.context("Failed to open recording"));
+        assert_eq!(error.code(), "file_locked");
+        assert!(error.retryable());
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(!VhqError::from(denied).retryable());
+
+        if cfg!(windows) {
+            assert!(VhqError::from(std::io::Error::from_raw_os_error(32)).retryable());
+        }
+    }
+
+    #[test]
+    fn test_serializes_for_frontend() {
+        let error = VhqError::backend("Ollama", Some(500), "model crashed");
+
+        assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({
+            "code": "backend_error",
+            "message": "Ollama error: model crashed",
+            "details": { "service": "Ollama", "status": 500 },
+            "retryable": true,
+        }));
+    }
+}
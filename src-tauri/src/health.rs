@@ -0,0 +1,344 @@
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::database::Database;
+
+/// Number of health reports kept in memory
+const HISTORY_LEN: usize = 20;
+
+/// How long to wait for Ollama before calling it unreachable
+const OLLAMA_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthStatus {
+    Healthy,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+impl HealthStatus {
+    fn severity(self) -> u8 {
+        match self {
+            HealthStatus::Healthy => 0,
+            HealthStatus::Unknown => 1,
+            HealthStatus::Warning => 2,
+            HealthStatus::Critical => 3,
+        }
+    }
+
+    /// The more severe of the two
+    pub fn worst(self, other: HealthStatus) -> HealthStatus {
+        if other.severity() > self.severity() { other } else { self }
+    }
+
+    /// 1.0 for healthy down to 0.0 for critical
+    pub fn score(self) -> f64 {
+        match self {
+            HealthStatus::Healthy => 1.0,
+            HealthStatus::Unknown | HealthStatus::Warning => 0.5,
+            HealthStatus::Critical => 0.0,
+        }
+    }
+}
+
+/// Limits above which a probe reports `Warning` or `Critical`; the `[health]` config section
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthThresholds {
+    pub database_latency_warning_ms: f64,
+    pub database_latency_critical_ms: f64,
+    pub ollama_latency_warning_ms: f64,
+    pub ollama_latency_critical_ms: f64,
+    /// Share of failed tasks (0 to 1) among an agent's recently finished ones
+    pub failure_rate_warning: f64,
+    pub failure_rate_critical: f64,
+    /// Only tasks finished within this many seconds count towards the failure rate
+    pub failure_window_secs: i64,
+    /// Fewer finished tasks than this are too few to judge an agent by
+    pub failure_min_tasks: i64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            database_latency_warning_ms: 200.0,
+            database_latency_critical_ms: 2000.0,
+            ollama_latency_warning_ms: 1000.0,
+            ollama_latency_critical_ms: 5000.0,
+            failure_rate_warning: 0.25,
+            failure_rate_critical: 0.5,
+            failure_window_secs: 3600,
+            failure_min_tasks: 4,
+        }
+    }
+}
+
+/// Outcome of a single probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub name: String,
+    pub status: HealthStatus,
+    pub latency_ms: Option<f64>,
+    pub message: String,
+    /// Agent the probe is about, if any (registry name such as `vitra_lag`)
+    pub agent: Option<String>,
+    /// Between 0 and 1; for failure rates this is the success rate
+    pub score: f64,
+}
+
+impl ProbeResult {
+    fn new(name: &str, agent: Option<&str>, status: HealthStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            latency_ms: None,
+            message: message.into(),
+            agent: agent.map(String::from),
+            score: status.score(),
+        }
+    }
+
+    fn with_latency(mut self, latency_ms: f64) -> Self {
+        self.latency_ms = Some(latency_ms);
+        self
+    }
+}
+
+/// Result of one round of probes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub probes: Vec<ProbeResult>,
+    pub checked_at: String,
+}
+
+impl HealthReport {
+    pub fn new(probes: Vec<ProbeResult>) -> Self {
+        Self {
+            status: probes.iter().fold(HealthStatus::Healthy, |status, probe| status.worst(probe.status)),
+            probes,
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Health score of an agent: the lowest score among the probes about it
+    pub fn agent_score(&self, agent: &str) -> Option<f64> {
+        self.probes.iter()
+            .filter(|probe| probe.agent.as_deref() == Some(agent))
+            .map(|probe| probe.score)
+            .reduce(f64::min)
+    }
+}
+
+/// The last few health reports, newest last. Clones share the same history.
+#[derive(Debug, Clone, Default)]
+pub struct HealthHistory {
+    reports: Arc<Mutex<VecDeque<HealthReport>>>,
+}
+
+impl HealthHistory {
+    pub fn push(&self, report: HealthReport) {
+        let mut reports = self.reports.lock().unwrap();
+        if reports.len() == HISTORY_LEN {
+            reports.pop_front();
+        }
+        reports.push_back(report);
+    }
+
+    pub fn latest(&self) -> Option<HealthReport> {
+        self.reports.lock().unwrap().back().cloned()
+    }
+
+    pub fn reports(&self) -> Vec<HealthReport> {
+        self.reports.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Runs the health probes against the database, Ollama, whisper and the output directories
+#[derive(Debug, Clone)]
+pub struct HealthChecker {
+    pub db: Database,
+    pub ollama_url: String,
+    /// Directories agents write to, by agent registry name
+    pub output_dirs: Vec<(String, PathBuf)>,
+    pub thresholds: HealthThresholds,
+}
+
+impl HealthChecker {
+    pub async fn run(&self) -> HealthReport {
+        let mut probes = vec![
+            self.probe_database().await,
+            self.probe_ollama().await,
+            probe_whisper(),
+        ];
+        for (agent, dir) in &self.output_dirs {
+            probes.push(probe_writable(agent, dir));
+        }
+        probes.extend(self.probe_failure_rates().await);
+
+        HealthReport::new(probes)
+    }
+
+    async fn probe_database(&self) -> ProbeResult {
+        let started = Instant::now();
+        let result = sqlx::query("SELECT 1").execute(self.db.pool()).await;
+        let latency = elapsed_ms(started);
+
+        match result {
+            Ok(_) => {
+                let status = latency_status(latency, self.thresholds.database_latency_warning_ms, self.thresholds.database_latency_critical_ms);
+                ProbeResult::new("database", Some("ceo_lag"), status, format!("Round-trip in {:.0}ms", latency))
+            }
+            Err(e) => ProbeResult::new("database", Some("ceo_lag"), HealthStatus::Critical, format!("Query failed: {}", e)),
+        }
+        .with_latency(latency)
+    }
+
+    async fn probe_ollama(&self) -> ProbeResult {
+        let client = reqwest::Client::new();
+        let started = Instant::now();
+        let result = client.get(format!("{}/api/tags", self.ollama_url))
+            .timeout(OLLAMA_TIMEOUT)
+            .send()
+            .await;
+        let latency = elapsed_ms(started);
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                let status = latency_status(latency, self.thresholds.ollama_latency_warning_ms, self.thresholds.ollama_latency_critical_ms);
+                ProbeResult::new("ollama", Some("ghost_lag"), status, format!("Responded in {:.0}ms", latency))
+            }
+            Ok(response) => ProbeResult::new("ollama", Some("ghost_lag"), HealthStatus::Critical, format!("Responded with {}", response.status())),
+            Err(e) => ProbeResult::new("ollama", Some("ghost_lag"), HealthStatus::Critical, format!("Unreachable: {}", e)),
+        }
+        .with_latency(latency)
+    }
+
+    async fn probe_failure_rates(&self) -> Vec<ProbeResult> {
+        let since = Utc::now() - Duration::seconds(self.thresholds.failure_window_secs);
+        let outcomes = match self.db.tasks().outcomes_since(&since.to_rfc3339()).await {
+            Ok(outcomes) => outcomes,
+            Err(e) => {
+                return vec![ProbeResult::new("failure_rate", None, HealthStatus::Unknown, format!("Could not load task outcomes: {}", e))];
+            }
+        };
+
+        let mut agents: Vec<_> = outcomes.into_iter().collect();
+        agents.sort_by(|a, b| a.0.cmp(&b.0));
+        agents.into_iter()
+            .map(|(agent, (finished, failed))| failure_rate_probe(&agent, finished, failed, &self.thresholds))
+            .collect()
+    }
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+fn latency_status(latency_ms: f64, warning_ms: f64, critical_ms: f64) -> HealthStatus {
+    if latency_ms >= critical_ms {
+        HealthStatus::Critical
+    } else if latency_ms >= warning_ms {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Healthy
+    }
+}
+
+fn failure_rate_probe(agent: &str, finished: i64, failed: i64, thresholds: &HealthThresholds) -> ProbeResult {
+    let rate = if finished > 0 { failed as f64 / finished as f64 } else { 0.0 };
+    let status = if finished < thresholds.failure_min_tasks {
+        HealthStatus::Healthy
+    } else if rate >= thresholds.failure_rate_critical {
+        HealthStatus::Critical
+    } else if rate >= thresholds.failure_rate_warning {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Healthy
+    };
+
+    let mut probe = ProbeResult::new(
+        "failure_rate",
+        Some(agent),
+        status,
+        format!("{} of {} recent tasks failed", failed, finished),
+    );
+    probe.score = 1.0 - rate;
+    probe
+}
+
+fn probe_whisper() -> ProbeResult {
+    match find_executable("whisper") {
+        Some(path) => ProbeResult::new("whisper", Some("vitra_lag"), HealthStatus::Healthy, format!("Found at {}", path.display())),
+        None => ProbeResult::new("whisper", Some("vitra_lag"), HealthStatus::Critical, "whisper is not on PATH"),
+    }
+}
+
+/// Create the directory if needed and write and remove a scratch file in it
+fn probe_writable(agent: &str, dir: &Path) -> ProbeResult {
+    let name = format!("output_dir:{}", dir.display());
+    let scratch = dir.join(format!(".health-{}", uuid::Uuid::new_v4()));
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&scratch, b"ok"))
+        .and_then(|_| std::fs::remove_file(&scratch));
+
+    match result {
+        Ok(()) => ProbeResult::new(&name, Some(agent), HealthStatus::Healthy, "Writable"),
+        Err(e) => ProbeResult::new(&name, Some(agent), HealthStatus::Critical, format!("Not writable: {}", e)),
+    }
+}
+
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .flat_map(|dir| [dir.join(name), dir.join(format!("{}.exe", name))])
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_rate_thresholds() {
+        let thresholds = HealthThresholds::default();
+
+        assert_eq!(failure_rate_probe("vitra_lag", 3, 3, &thresholds).status, HealthStatus::Healthy);
+        assert_eq!(failure_rate_probe("vitra_lag", 10, 1, &thresholds).status, HealthStatus::Healthy);
+        assert_eq!(failure_rate_probe("vitra_lag", 10, 3, &thresholds).status, HealthStatus::Warning);
+
+        let probe = failure_rate_probe("vitra_lag", 10, 6, &thresholds);
+        assert_eq!(probe.status, HealthStatus::Critical);
+        assert!((probe.score - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_takes_worst_probe() {
+        let report = HealthReport::new(vec![
+            ProbeResult::new("database", Some("ceo_lag"), HealthStatus::Healthy, ""),
+            ProbeResult::new("ollama", Some("ghost_lag"), HealthStatus::Warning, ""),
+            ProbeResult::new("output_dir:generated_content", Some("ghost_lag"), HealthStatus::Healthy, ""),
+        ]);
+
+        assert_eq!(report.status, HealthStatus::Warning);
+        assert_eq!(report.agent_score("ghost_lag"), Some(0.5));
+        assert_eq!(report.agent_score("ceo_lag"), Some(1.0));
+        assert_eq!(report.agent_score("vitra_lag"), None);
+    }
+
+    #[test]
+    fn test_history_keeps_latest_reports() {
+        let history = HealthHistory::default();
+        for _ in 0..HISTORY_LEN + 5 {
+            history.push(HealthReport::new(Vec::new()));
+        }
+
+        assert_eq!(history.reports().len(), HISTORY_LEN);
+        assert_eq!(history.latest().unwrap().status, HealthStatus::Healthy);
+    }
+}
@@ -2,11 +2,29 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use tracing::{info, error};
+use tauri::Emitter;
+use tauri_plugin_notification::NotificationExt;
 
+use agents::progress::PROGRESS_EVENT;
+use alerts::ALERT_EVENT;
+use n8n::callback::WORKFLOW_EVENT;
+use reload::RELOAD_EVENT;
+use settings::SETTINGS_EVENT;
+
+mod alerts;
 mod commands;
+mod config;
 mod database;
+mod error;
+mod health;
+mod metrics;
+mod n8n;
+mod pipeline;
+mod reload;
+mod settings;
 mod agents;
 mod system;
+mod state;
 mod tests;
 
 #[tokio::main]
@@ -16,36 +34,182 @@ async fn main() {
     info!("Starting VHQ LAG v2...");
 
     // Initialize database with error handling
-    match database::init().await {
-        Ok(_) => info!("Database initialized successfully"),
+    // Every component shares this pool, so there is nothing to run without it
+    let db = match database::init().await {
+        Ok(db) => db,
         Err(e) => {
             error!("Failed to initialize database: {}", e);
-            // Continue anyway for now
+            std::process::exit(1);
+        }
+    };
+
+    // Invalid configuration is reported in full instead of running with surprising values
+    let config = match config::load(&db).await {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let app_state = state::AppState::new(db, config.clone());
+    
+    // Limits from the configuration replace the built-in agent defaults
+    if let Err(e) = app_state.apply_config(&config).await {
+        error!("Failed to apply configuration: {}", e);
+    }
+    
+    // Resolve tasks interrupted by the previous session
+    if let Err(e) = app_state.ceo.read().await.recover_interrupted_tasks().await {
+        error!("Failed to recover interrupted tasks: {}", e);
+    }
+    
+    if let Err(e) = app_state.scheduler.start() {
+        error!("Failed to start task scheduler: {}", e);
+    }
+    
+    if let Err(e) = app_state.alerts.start() {
+        error!("Failed to start alert engine: {}", e);
+    }
+
+    // n8n may be slow or down; the app does not wait for the workflow sync
+    let workflows = app_state.workflows.clone();
+    tokio::spawn(async move {
+        if let Err(e) = workflows.start().await {
+            error!("Failed to sync bundled n8n workflows: {}", e);
         }
+    });
+
+    // Workflows report their executions back to this endpoint
+    let callbacks = n8n::callback::CallbackServer::new(app_state.clone());
+    if let Err(e) = callbacks.start().await {
+        error!("Failed to start n8n callback endpoint: {}", e);
+    }
+
+    // Native pipelines run without n8n; their triggers start with them
+    if let Err(e) = app_state.workflows.start_pipelines(&app_state).await {
+        error!("Failed to start pipelines: {}", e);
+    }
+
+    // Progress updates of running tasks are pushed to the UI as events
+    let progress = app_state.ceo.read().await.progress.clone();
+    let alert_notifier = app_state.alerts.notifier.clone();
+    let settings = app_state.settings.clone();
+    let workflows = app_state.workflows.clone();
+    
+    // Edits to config.toml and GHOST templates apply without a restart
+    let reloader = reload::HotReloader::new(app_state.clone());
+    if let Err(e) = reloader.start() {
+        error!("Failed to start configuration watcher: {}", e);
     }
 
     tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             commands::start_agent,
             commands::stop_agent,
             commands::get_agent_status,
             commands::get_agent_info,
             commands::process_file,
+            commands::get_file_processing_history,
             commands::get_queue_status,
             commands::get_system_metrics,
             commands::get_task_status,
             commands::cancel_task,
             commands::clear_completed_tasks,
+            commands::get_health_history,
+            commands::update_task_limits,
+            commands::get_dead_letter_tasks,
+            commands::replay_dead_letter_task,
+            commands::submit_task_graph,
+            commands::get_task_graph_status,
+            commands::create_schedule,
+            commands::list_schedules,
+            commands::pause_schedule,
+            commands::resume_schedule,
+            commands::delete_schedule,
+            commands::list_alert_rules,
+            commands::create_alert_rule,
+            commands::update_alert_rule,
+            commands::delete_alert_rule,
+            commands::get_alerts,
+            commands::acknowledge_alert,
+            commands::resolve_alert,
             commands::get_ghost_models,
             commands::get_vitra_languages,
             commands::get_workflow_status,
+            commands::get_settings,
             commands::update_settings,
             commands::trigger_n8n_workflow,
             commands::get_n8n_workflows,
+            commands::get_n8n_workflow,
             commands::deploy_n8n_workflow,
+            commands::set_n8n_workflow_active,
+            commands::delete_n8n_workflow,
+            commands::get_n8n_executions,
+            commands::get_n8n_execution,
+            commands::sync_n8n_workflows,
+            commands::list_pipelines,
+            commands::run_pipeline,
+            commands::import_n8n_workflow,
             commands::run_system_tests
         ])
-        .setup(|_app| {
+        .setup(move |app| {
+            let handle = app.handle().clone();
+            progress.set_listener(move |update| {
+                if let Err(e) = handle.emit(PROGRESS_EVENT, update) {
+                    error!("Failed to emit task progress: {}", e);
+                }
+            });
+            
+            let handle = app.handle().clone();
+            alert_notifier.set_listener(move |alert| {
+                if let Err(e) = handle.emit(ALERT_EVENT, alert) {
+                    error!("Failed to emit alert: {}", e);
+                }
+            });
+            
+            let handle = app.handle().clone();
+            alert_notifier.set_desktop_listener(move |alert| {
+                let shown = handle.notification()
+                    .builder()
+                    .title(format!("VHQ LAG: {}", alert.rule_name))
+                    .body(&alert.message)
+                    .show();
+                if let Err(e) = shown {
+                    error!("Failed to show desktop notification: {}", e);
+                }
+            });
+            
+            let handle = app.handle().clone();
+            settings.set_listener(move |settings| {
+                if let Err(e) = handle.emit(SETTINGS_EVENT, settings) {
+                    error!("Failed to emit settings: {}", e);
+                }
+            });
+            
+            let handle = app.handle().clone();
+            reloader.set_listener(move |event| {
+                if let Err(e) = handle.emit(RELOAD_EVENT, event) {
+                    error!("Failed to emit reload result: {}", e);
+                }
+            });
+            
+            let handle = app.handle().clone();
+            callbacks.set_listener(move |workflow| {
+                if let Err(e) = handle.emit(WORKFLOW_EVENT, workflow) {
+                    error!("Failed to emit workflow update: {}", e);
+                }
+            });
+            
+            let handle = app.handle().clone();
+            workflows.set_pipeline_listener(move |workflow| {
+                if let Err(e) = handle.emit(WORKFLOW_EVENT, workflow) {
+                    error!("Failed to emit pipeline update: {}", e);
+                }
+            });
+            
             info!("VHQ LAG v2 initialized successfully");
             Ok(())
         })
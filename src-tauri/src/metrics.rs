@@ -0,0 +1,333 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::database;
+
+/// Machine-wide CPU time in clock ticks, from the first line of `/proc/stat`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpuTimes {
+    pub idle: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+}
+
+/// Space on the filesystem holding a monitored directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub path: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    /// Identifies the filesystem so directories sharing one are counted once
+    #[serde(skip)]
+    fs_id: u64,
+}
+
+impl DiskUsage {
+    pub fn percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes as f64 / self.total_bytes as f64 * 100.0
+    }
+}
+
+/// CPU and memory used by a group of processes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ProcessUsage {
+    pub memory_bytes: u64,
+    /// Percent of one core, so several busy processes can exceed 100
+    pub cpu_percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    pub cpu_percent: f64,
+    pub memory: MemoryUsage,
+    pub disks: Vec<DiskUsage>,
+}
+
+impl SystemSnapshot {
+    /// Usage of the fullest monitored filesystem
+    pub fn disk_percent(&self) -> f64 {
+        self.disks.iter().map(DiskUsage::percent).fold(0.0, f64::max)
+    }
+
+    /// Used bytes summed over the distinct monitored filesystems
+    pub fn disk_used_bytes(&self) -> u64 {
+        let mut seen = HashSet::new();
+        self.disks.iter()
+            .filter(|disk| seen.insert(disk.fs_id))
+            .map(|disk| disk.used_bytes)
+            .sum()
+    }
+}
+
+/// Directories whose filesystems are watched: app data and the agents' output directories
+pub fn monitored_dirs(output_dirs: &[(String, PathBuf)]) -> Vec<PathBuf> {
+    std::iter::once(database::data_dir())
+        .chain(output_dirs.iter().map(|(_, dir)| dir.clone()))
+        .collect()
+}
+
+/// Samples machine-wide usage. CPU usage is measured between two consecutive samples,
+/// so the first sample reports 0.
+#[derive(Debug)]
+pub struct SystemSampler {
+    dirs: Vec<PathBuf>,
+    last_cpu: Option<CpuTimes>,
+}
+
+impl SystemSampler {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs, last_cpu: None }
+    }
+
+    /// Watch other directories from the next sample on, e.g. after the configuration changed
+    pub fn set_dirs(&mut self, dirs: Vec<PathBuf>) {
+        self.dirs = dirs;
+    }
+
+    pub fn sample(&mut self) -> SystemSnapshot {
+        let cpu = std::fs::read_to_string("/proc/stat").ok().and_then(|stat| parse_cpu_times(&stat));
+        let cpu_percent = match (self.last_cpu, cpu) {
+            (Some(previous), Some(current)) => cpu_percent(previous, current),
+            _ => 0.0,
+        };
+        self.last_cpu = cpu;
+
+        SystemSnapshot {
+            cpu_percent,
+            memory: std::fs::read_to_string("/proc/meminfo").ok()
+                .and_then(|meminfo| parse_meminfo(&meminfo))
+                .unwrap_or_default(),
+            disks: self.dirs.iter().filter_map(|dir| disk_usage(dir)).collect(),
+        }
+    }
+}
+
+/// Child processes spawned by an agent, such as whisper. Clones share the same set.
+#[derive(Debug, Clone, Default)]
+pub struct ChildProcesses {
+    state: Arc<Mutex<ChildState>>,
+}
+
+#[derive(Debug, Default)]
+struct ChildState {
+    pids: HashSet<u32>,
+    /// CPU ticks per process at the previous sample
+    last_ticks: HashMap<u32, u64>,
+    last_sample: Option<Instant>,
+}
+
+impl ChildProcesses {
+    /// Track a process until the returned guard is dropped
+    pub fn track(&self, pid: u32) -> TrackedChild {
+        self.state.lock().unwrap().pids.insert(pid);
+        TrackedChild { children: self.clone(), pid }
+    }
+
+    /// Memory of the tracked processes and their descendants, and their CPU usage
+    /// since the previous sample
+    pub fn sample(&self) -> ProcessUsage {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = state.last_sample.map(|at| now.duration_since(at).as_secs_f64());
+        state.last_sample = Some(now);
+
+        if state.pids.is_empty() {
+            state.last_ticks.clear();
+            return ProcessUsage::default();
+        }
+
+        let mut usage = ProcessUsage::default();
+        let mut ticks = HashMap::new();
+        let mut busy_ticks = 0;
+        for pid in descendants(&state.pids) {
+            if let Some((_, pid_ticks)) = read_pid_stat(pid) {
+                // Processes that appeared since the previous sample spent all their time in this interval
+                busy_ticks += pid_ticks.saturating_sub(state.last_ticks.get(&pid).copied().unwrap_or(0));
+                ticks.insert(pid, pid_ticks);
+            }
+            usage.memory_bytes += process_memory(pid).unwrap_or(0);
+        }
+        state.last_ticks = ticks;
+
+        if let Some(elapsed) = elapsed.filter(|secs| *secs > 0.0) {
+            usage.cpu_percent = busy_ticks as f64 / clock_ticks() / elapsed * 100.0;
+        }
+        usage
+    }
+}
+
+/// Stops tracking a child process when dropped
+#[derive(Debug)]
+pub struct TrackedChild {
+    children: ChildProcesses,
+    pid: u32,
+}
+
+impl Drop for TrackedChild {
+    fn drop(&mut self) {
+        self.children.state.lock().unwrap().pids.remove(&self.pid);
+    }
+}
+
+fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let values: Vec<u64> = line.split_whitespace().skip(1).filter_map(|v| v.parse().ok()).collect();
+    if values.len() < 4 {
+        return None;
+    }
+
+    // user nice system idle iowait irq softirq steal; guest time is already part of user
+    let idle = values[3] + values.get(4).copied().unwrap_or(0);
+    let total = values.iter().take(8).sum();
+    Some(CpuTimes { idle, total })
+}
+
+fn cpu_percent(previous: CpuTimes, current: CpuTimes) -> f64 {
+    let total = current.total.saturating_sub(previous.total);
+    if total == 0 {
+        return 0.0;
+    }
+    let idle = current.idle.saturating_sub(previous.idle).min(total);
+    (total - idle) as f64 / total as f64 * 100.0
+}
+
+fn parse_meminfo(meminfo: &str) -> Option<MemoryUsage> {
+    let field = |name: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        let kb: u64 = line[name.len()..].trim_start_matches(':').split_whitespace().next()?.parse().ok()?;
+        Some(kb * 1024)
+    };
+
+    let total_bytes = field("MemTotal")?;
+    let available_bytes = field("MemAvailable")?;
+    Some(MemoryUsage { total_bytes, used_bytes: total_bytes.saturating_sub(available_bytes) })
+}
+
+/// Parent PID and total CPU ticks (user + system) from `/proc/<pid>/stat`
+fn parse_pid_stat(stat: &str) -> Option<(u32, u64)> {
+    // The command name is parenthesized and may itself contain spaces or parentheses
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let ppid = fields.get(1)?.parse().ok()?;
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some((ppid, utime + stime))
+}
+
+fn read_pid_stat(pid: u32) -> Option<(u32, u64)> {
+    parse_pid_stat(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+/// Resident memory of a process in bytes
+fn process_memory(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line["VmRSS:".len()..].split_whitespace().next()?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// The given processes plus everything they spawned, e.g. ffmpeg started by whisper
+fn descendants(roots: &HashSet<u32>) -> HashSet<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let pid = match entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            if let Some((ppid, _)) = read_pid_stat(pid) {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
+    }
+
+    let mut found = roots.clone();
+    let mut queue: Vec<u32> = roots.iter().copied().collect();
+    while let Some(pid) = queue.pop() {
+        for child in children.get(&pid).into_iter().flatten() {
+            if found.insert(*child) {
+                queue.push(*child);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(unix)]
+fn clock_ticks() -> f64 {
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 { ticks as f64 } else { 100.0 }
+}
+
+#[cfg(not(unix))]
+fn clock_ticks() -> f64 {
+    100.0
+}
+
+#[cfg(unix)]
+fn disk_usage(path: &Path) -> Option<DiskUsage> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let block_size = stat.f_frsize as u64;
+    let total_bytes = stat.f_blocks as u64 * block_size;
+    let free_bytes = stat.f_bfree as u64 * block_size;
+    Some(DiskUsage {
+        path: path.display().to_string(),
+        total_bytes,
+        used_bytes: total_bytes.saturating_sub(free_bytes),
+        available_bytes: stat.f_bavail as u64 * block_size,
+        fs_id: stat.f_fsid as u64,
+    })
+}
+
+#[cfg(not(unix))]
+fn disk_usage(_path: &Path) -> Option<DiskUsage> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_percent_from_proc_stat() {
+        let before = parse_cpu_times("cpu  100 0 100 700 100 0 0 0 50 0\ncpu0 1 2 3 4\n").unwrap();
+        let after = parse_cpu_times("cpu  250 0 150 850 150 0 0 0 80 0\n").unwrap();
+
+        assert_eq!(before, CpuTimes { idle: 800, total: 1000 });
+        assert_eq!(cpu_percent(before, after), 50.0);
+        assert_eq!(cpu_percent(after, after), 0.0);
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = "MemTotal:       16000 kB\nMemFree:         2000 kB\nMemAvailable:    6000 kB\n";
+        let memory = parse_meminfo(meminfo).unwrap();
+
+        assert_eq!(memory.total_bytes, 16000 * 1024);
+        assert_eq!(memory.used_bytes, 10000 * 1024);
+    }
+
+    #[test]
+    fn test_parse_pid_stat_with_spaces_in_name() {
+        let stat = "4242 (python3 (whisper)) S 4000 4242 4000 0 -1 4194304 500 0 0 0 120 30 0 0 20 0 4 0 100";
+        assert_eq!(parse_pid_stat(stat), Some((4000, 150)));
+    }
+}
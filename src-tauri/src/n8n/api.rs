@@ -0,0 +1,347 @@
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::{parse_body, response_error, N8nClient};
+use crate::error::VhqError;
+
+/// Header the public REST API reads the key from
+const API_KEY_HEADER: &str = "X-N8N-API-KEY";
+
+/// Largest page the public REST API serves
+const PAGE_LIMIT: u32 = 250;
+
+/// Workflow as stored in n8n
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct N8nWorkflow {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub nodes: Vec<Value>,
+    #[serde(default)]
+    pub connections: Value,
+    #[serde(default)]
+    pub settings: Value,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Result of deploying a workflow definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployedWorkflow {
+    pub workflow: N8nWorkflow,
+    /// False when a workflow with the same name was updated instead
+    pub created: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct N8nExecution {
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
+    #[serde(deserialize_with = "string_or_number")]
+    pub workflow_id: String,
+    /// success, error, running, waiting, canceled, ...
+    pub status: Option<String>,
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub finished: bool,
+    pub started_at: Option<String>,
+    pub stopped_at: Option<String>,
+    /// Per-node run data, only present when requested
+    pub data: Option<Value>,
+}
+
+/// One page of a list endpoint
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page<T> {
+    data: Vec<T>,
+    next_cursor: Option<String>,
+}
+
+impl N8nClient {
+    /// Every workflow, following pagination
+    pub async fn list_workflows(&self) -> Result<Vec<N8nWorkflow>, VhqError> {
+        let mut workflows = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut query = vec![("limit", PAGE_LIMIT.to_string())];
+            if let Some(cursor) = &cursor {
+                query.push(("cursor", cursor.clone()));
+            }
+            let page: Page<N8nWorkflow> = self.api(Method::GET, "/workflows", &query, None, "listing workflows").await?;
+            workflows.extend(page.data);
+
+            match page.next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(workflows)
+    }
+
+    pub async fn get_workflow(&self, id: &str) -> Result<N8nWorkflow, VhqError> {
+        self.api(Method::GET, &format!("/workflows/{}", id), &[], None, "getting workflow").await
+            .map_err(|e| not_found(e, "n8n workflow", id))
+    }
+
+    /// Create the workflow, or update the one with the same name so re-deploying
+    /// a definition doesn't create duplicates. An `active` flag in the definition
+    /// is applied after saving.
+    pub async fn deploy_workflow(&self, definition: &Value) -> Result<DeployedWorkflow, VhqError> {
+        let name = definition.get("name").and_then(Value::as_str).map(str::trim).unwrap_or_default();
+        if name.is_empty() {
+            return Err(VhqError::invalid_input("Workflow definition needs a name"));
+        }
+        if !definition.get("nodes").is_some_and(Value::is_array) {
+            return Err(VhqError::invalid_input(format!("Workflow {} needs a nodes array", name)));
+        }
+
+        let existing: Vec<N8nWorkflow> = self.list_workflows().await?
+            .into_iter()
+            .filter(|workflow| workflow.name == name)
+            .collect();
+        if existing.len() > 1 {
+            warn!("{} n8n workflows are named {}; updating {}", existing.len(), name, existing[0].id);
+        }
+
+        self.save_workflow(definition, existing.first()).await
+    }
+
+    /// Update `existing`, or create the workflow when there is none
+    pub(super) async fn save_workflow(&self, definition: &Value, existing: Option<&N8nWorkflow>) -> Result<DeployedWorkflow, VhqError> {
+        let name = definition.get("name").and_then(Value::as_str).map(str::trim).unwrap_or_default();
+
+        // The API rejects read-only fields such as id, active, tags or versionId
+        let body = serde_json::json!({
+            "name": name,
+            "nodes": definition["nodes"],
+            "connections": definition.get("connections").cloned().unwrap_or_else(|| serde_json::json!({})),
+            "settings": definition.get("settings").cloned().unwrap_or_else(|| serde_json::json!({})),
+        });
+
+        let (mut workflow, created) = match existing {
+            Some(current) => {
+                let path = format!("/workflows/{}", current.id);
+                let workflow: N8nWorkflow = self.api(Method::PUT, &path, &[], Some(&body), "updating workflow").await?;
+                (workflow, false)
+            }
+            None => {
+                let workflow: N8nWorkflow = self.api(Method::POST, "/workflows", &[], Some(&body), "creating workflow").await?;
+                (workflow, true)
+            }
+        };
+        info!("{} n8n workflow {} ({})", if created { "Created" } else { "Updated" }, workflow.name, workflow.id);
+
+        if let Some(active) = definition.get("active").and_then(Value::as_bool) {
+            if active != workflow.active {
+                workflow = self.set_workflow_active(&workflow.id, active).await.map_err(|e| {
+                    VhqError::backend("n8n", None, format!("workflow {} was deployed as {} but its activation failed: {}", name, workflow.id, e))
+                })?;
+            }
+        }
+
+        Ok(DeployedWorkflow { workflow, created })
+    }
+
+    /// Activate or deactivate a workflow. n8n refuses to activate workflows without a trigger node.
+    pub async fn set_workflow_active(&self, id: &str, active: bool) -> Result<N8nWorkflow, VhqError> {
+        let action = if active { "activate" } else { "deactivate" };
+        let workflow: N8nWorkflow = self.api(Method::POST, &format!("/workflows/{}/{}", id, action), &[], None, &format!("{} workflow", action)).await
+            .map_err(|e| not_found(e, "n8n workflow", id))?;
+        info!("n8n workflow {} {}d", workflow.name, action);
+        Ok(workflow)
+    }
+
+    pub async fn delete_workflow(&self, id: &str) -> Result<N8nWorkflow, VhqError> {
+        let workflow: N8nWorkflow = self.api(Method::DELETE, &format!("/workflows/{}", id), &[], None, "deleting workflow").await
+            .map_err(|e| not_found(e, "n8n workflow", id))?;
+        info!("Deleted n8n workflow {} ({})", workflow.name, workflow.id);
+        Ok(workflow)
+    }
+
+    /// Most recent executions first, optionally of one workflow and with one status
+    pub async fn list_executions(&self, workflow_id: Option<&str>, status: Option<&str>, limit: u32) -> Result<Vec<N8nExecution>, VhqError> {
+        let mut query = vec![("limit", limit.clamp(1, PAGE_LIMIT).to_string())];
+        if let Some(workflow_id) = workflow_id {
+            query.push(("workflowId", workflow_id.to_string()));
+        }
+        if let Some(status) = status {
+            query.push(("status", status.to_string()));
+        }
+
+        let page: Page<N8nExecution> = self.api(Method::GET, "/executions", &query, None, "listing executions").await?;
+        Ok(page.data)
+    }
+
+    /// One execution including its per-node run data
+    pub async fn get_execution(&self, id: &str) -> Result<N8nExecution, VhqError> {
+        let query = [("includeData", "true".to_string())];
+        self.api(Method::GET, &format!("/executions/{}", id), &query, None, "getting execution").await
+            .map_err(|e| not_found(e, "n8n execution", id))
+    }
+
+    /// Call the public REST API under `/api/v1`
+    async fn api<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&Value>,
+        context: &str,
+    ) -> Result<T, VhqError> {
+        let api_key = self.config.api_key.as_deref().ok_or_else(|| {
+            VhqError::invalid_input("n8n.api_key is not set; create an API key in n8n under Settings > n8n API")
+        })?;
+
+        let url = format!("{}/api/v1{}", self.base_url(), path);
+        let mut request = self.http.request(method, &url).header(API_KEY_HEADER, api_key).query(query);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let operation = format!("n8n {}", context);
+        let response = request.send().await.map_err(|e| self.request_error(&operation, &e))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| self.request_error(&operation, &e))?;
+
+        if !status.is_success() {
+            return Err(response_error(status, &parse_body(&text), context));
+        }
+
+        serde_json::from_str(&text).map_err(|e| {
+            VhqError::backend("n8n", Some(status.as_u16()), format!("{}: unexpected response ({})", context, e))
+        })
+    }
+}
+
+/// Turn a 404 from the API into a `NotFound` for `id`
+fn not_found(error: VhqError, entity: &'static str, id: &str) -> VhqError {
+    match error {
+        VhqError::Backend { status: Some(404), .. } => VhqError::NotFound { entity, id: id.to_string() },
+        error => error,
+    }
+}
+
+/// n8n returns execution ids as numbers and workflow ids as strings
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        other => Err(serde::de::Error::custom(format!("expected a string or number id, got {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::N8nConfig;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client(server: &MockServer) -> N8nClient {
+        N8nClient::new(&N8nConfig {
+            url: server.uri(),
+            api_key: Some("key".to_string()),
+            ..N8nConfig::default()
+        }).unwrap()
+    }
+
+    fn workflow(id: &str, name: &str, active: bool) -> Value {
+        serde_json::json!({ "id": id, "name": name, "active": active, "nodes": [], "connections": {}, "settings": {} })
+    }
+
+    #[tokio::test]
+    async fn test_lists_workflows_across_pages() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .and(header(API_KEY_HEADER, "key"))
+            .and(query_param("cursor", "page-2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [workflow("2", "GHOST Content Pipeline", false)], "nextCursor": null
+            })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [workflow("1", "VITRA Transcription Pipeline", true)], "nextCursor": "page-2"
+            })))
+            .mount(&server)
+            .await;
+
+        let workflows = client(&server).list_workflows().await.unwrap();
+
+        let names: Vec<&str> = workflows.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["VITRA Transcription Pipeline", "GHOST Content Pipeline"]);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_updates_workflow_with_same_name() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [workflow("7", "VITRA Transcription Pipeline", false)], "nextCursor": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/workflows/7"))
+            .and(body_json(serde_json::json!({
+                "name": "VITRA Transcription Pipeline", "nodes": [], "connections": {}, "settings": { "executionOrder": "v1" }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(workflow("7", "VITRA Transcription Pipeline", false)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/workflows/7/activate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(workflow("7", "VITRA Transcription Pipeline", true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/workflows"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let definition = serde_json::json!({
+            "id": "vitra-pipeline", "name": "VITRA Transcription Pipeline", "active": true, "tags": [],
+            "nodes": [], "connections": {}, "settings": { "executionOrder": "v1" }, "versionId": "1"
+        });
+        let deployed = client(&server).deploy_workflow(&definition).await.unwrap();
+
+        assert!(!deployed.created);
+        assert_eq!(deployed.workflow.id, "7");
+        assert!(deployed.workflow.active);
+    }
+
+    #[tokio::test]
+    async fn test_missing_workflow_and_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v1/workflows/42"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({ "message": "Not Found" })))
+            .mount(&server)
+            .await;
+
+        let error = client(&server).delete_workflow("42").await.unwrap_err();
+        assert_eq!(error.code(), "not_found");
+
+        let without_key = N8nClient::new(&N8nConfig { url: server.uri(), ..N8nConfig::default() }).unwrap();
+        assert_eq!(without_key.list_workflows().await.unwrap_err().code(), "invalid_input");
+    }
+}
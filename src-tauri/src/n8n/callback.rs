@@ -0,0 +1,276 @@
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn, error};
+
+use crate::database::workflows::WorkflowRecord;
+use crate::error::VhqError;
+use crate::state::AppState;
+
+/// Tauri event carrying the `WorkflowRecord` of a run after every callback
+pub const WORKFLOW_EVENT: &str = "workflow-updated";
+
+/// Path n8n's HTTP Request nodes POST to
+pub const CALLBACK_PATH: &str = "/n8n/callback";
+
+/// Header carrying `n8n.callback_secret`
+const SECRET_HEADER: &str = "x-vhq-secret";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackKind {
+    Started,
+    Progress,
+    Completed,
+    Failed,
+}
+
+/// Update a workflow sends about one of its executions, e.g.
+/// `{"event": "progress", "execution_id": "{{ $execution.id }}", "workflow": "{{ $workflow.name }}", "step": "Execute VITRA", "progress": 0.5}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallbackEvent {
+    pub event: CallbackKind,
+    pub execution_id: String,
+    pub workflow: String,
+    pub step: Option<String>,
+    /// Between 0 and 1
+    pub progress: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl CallbackEvent {
+    fn validate(&self) -> Result<(), VhqError> {
+        if self.execution_id.trim().is_empty() || self.workflow.trim().is_empty() {
+            return Err(VhqError::invalid_input("execution_id and workflow must not be empty"));
+        }
+        if let Some(progress) = self.progress.filter(|progress| !(0.0..=1.0).contains(progress)) {
+            return Err(VhqError::invalid_input(format!("progress must be between 0 and 1, got {}", progress)));
+        }
+        Ok(())
+    }
+
+    /// The stored run after this update; a run is started when none was stored,
+    /// since n8n may have missed reporting the start
+    pub fn apply(&self, current: Option<WorkflowRecord>, now: &str) -> WorkflowRecord {
+        let mut record = current.unwrap_or_else(|| WorkflowRecord {
+            id: self.execution_id.clone(),
+            name: self.workflow.clone(),
+            status: String::new(),
+            progress: 0.0,
+            current_step: None,
+            error: None,
+            created_at: now.to_string(),
+            updated_at: now.to_string(),
+            finished_at: None,
+        });
+
+        record.name = self.workflow.clone();
+        record.updated_at = now.to_string();
+        if self.step.is_some() {
+            record.current_step = self.step.clone();
+        }
+        let (status, progress) = match self.event {
+            CallbackKind::Started => ("running", self.progress.unwrap_or(0.0)),
+            CallbackKind::Progress => ("running", self.progress.unwrap_or(record.progress)),
+            CallbackKind::Completed => ("completed", 1.0),
+            CallbackKind::Failed => ("failed", self.progress.unwrap_or(record.progress)),
+        };
+        record.status = status.to_string();
+        record.progress = progress;
+        match self.event {
+            CallbackKind::Completed | CallbackKind::Failed => record.finished_at = Some(now.to_string()),
+            _ => record.finished_at = None,
+        }
+        if self.event == CallbackKind::Failed {
+            record.error = self.error.clone();
+        }
+
+        record
+    }
+}
+
+type Listener = Arc<dyn Fn(&WorkflowRecord) + Send + Sync>;
+
+/// Local HTTP endpoint n8n workflows report their executions to.
+/// Updates are stored in the `workflows` table and tracked by the `WorkflowManager`.
+#[derive(Clone)]
+pub struct CallbackServer {
+    state: AppState,
+    listener: Arc<Mutex<Option<Listener>>>,
+}
+
+impl CallbackServer {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state,
+            listener: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Called with the run after every accepted update, e.g. to emit it as a Tauri event
+    pub fn set_listener(&self, listener: impl Fn(&WorkflowRecord) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    pub async fn start(&self) -> Result<()> {
+        let config = self.state.settings.config().n8n;
+        if config.callback_secret.is_none() {
+            info!("n8n.callback_secret is not set; the workflow callback endpoint is disabled");
+            return Ok(());
+        }
+
+        let listener = tokio::net::TcpListener::bind(&config.callback_bind).await?;
+        info!("Accepting n8n callbacks on http://{}{}", listener.local_addr()?, CALLBACK_PATH);
+
+        let app = Router::new()
+            .route(CALLBACK_PATH, post(handle_callback))
+            .with_state(self.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!("n8n callback endpoint stopped: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Persist an update and track it in the `WorkflowManager`
+    pub async fn record(&self, event: &CallbackEvent) -> Result<WorkflowRecord> {
+        event.validate()?;
+
+        let repository = self.state.db.workflows();
+        let current = repository.get(&event.execution_id).await?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let record = event.apply(current, &now);
+        repository.upsert(&record).await?;
+        self.record_step(event, &now).await?;
+
+        if let Some(error) = &event.error {
+            warn!("n8n workflow {} ({}) reported an error: {}", event.workflow, event.execution_id, error);
+        }
+        self.state.workflows.record_execution(&event.workflow, &record.status, event.event == CallbackKind::Started).await;
+
+        let listener = self.listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener(&record);
+        }
+        Ok(record)
+    }
+
+    /// Close the step the run left and open the one it entered
+    async fn record_step(&self, event: &CallbackEvent, now: &str) -> Result<()> {
+        let repository = self.state.db.workflows();
+        let open = repository.open_step(&event.execution_id).await?;
+
+        let entered = match event.event {
+            CallbackKind::Started | CallbackKind::Progress => event.step.as_deref(),
+            CallbackKind::Completed | CallbackKind::Failed => None,
+        };
+        if let (Some(open), Some(entered)) = (&open, entered) {
+            if open.name == entered {
+                return Ok(());
+            }
+        }
+
+        if let Some(open) = &open {
+            match event.event {
+                CallbackKind::Failed => repository.finish_step(open, "failed", event.error.as_deref(), now).await?,
+                _ => repository.finish_step(open, "completed", None, now).await?,
+            };
+        }
+        if let Some(entered) = entered {
+            repository.start_step(&event.execution_id, entered, now).await?;
+        }
+        Ok(())
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        let secret = match self.state.settings.config().n8n.callback_secret {
+            Some(secret) => secret,
+            None => return false,
+        };
+        headers.get(SECRET_HEADER)
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), secret.as_bytes()))
+    }
+}
+
+async fn handle_callback(
+    State(server): State<CallbackServer>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<WorkflowRecord>, (StatusCode, String)> {
+    // Checked before the body is looked at so unauthenticated callers learn nothing
+    if !server.authorized(&headers) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or missing X-VHQ-Secret header".to_string()));
+    }
+
+    let event: CallbackEvent = serde_json::from_slice(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid callback: {}", e)))?;
+
+    match server.record(&event).await {
+        Ok(record) => Ok(Json(record)),
+        Err(e) => {
+            let e = VhqError::from(e);
+            let status = match e {
+                VhqError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+                _ => {
+                    error!("Failed to record n8n callback: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            Err((status, e.to_string()))
+        }
+    }
+}
+
+/// Compare secrets without leaking the length of the matching prefix through timing
+fn constant_time_eq(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len() && given.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: CallbackKind, step: Option<&str>, progress: Option<f64>) -> CallbackEvent {
+        CallbackEvent {
+            event: kind,
+            execution_id: "1042".to_string(),
+            workflow: "VITRA Transcription Pipeline".to_string(),
+            step: step.map(str::to_string),
+            progress,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_run_follows_started_progress_and_failure() {
+        let started = event(CallbackKind::Started, Some("File Trigger"), None).apply(None, "t1");
+        assert_eq!((started.status.as_str(), started.progress, started.created_at.as_str()), ("running", 0.0, "t1"));
+
+        let progress = event(CallbackKind::Progress, Some("Execute VITRA"), Some(0.5)).apply(Some(started), "t2");
+        assert_eq!(progress.current_step.as_deref(), Some("Execute VITRA"));
+        assert_eq!(progress.progress, 0.5);
+
+        let failed = event(CallbackKind::Failed, None, None).apply(Some(progress), "t3");
+        assert_eq!((failed.status.as_str(), failed.progress), ("failed", 0.5));
+        assert_eq!(failed.current_step.as_deref(), Some("Execute VITRA"));
+        assert_eq!((failed.created_at.as_str(), failed.updated_at.as_str()), ("t1", "t3"));
+        assert_eq!(failed.finished_at.as_deref(), Some("t3"));
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_progress_and_wrong_secret() {
+        assert!(event(CallbackKind::Progress, None, Some(1.5)).validate().is_err());
+        assert!(event(CallbackKind::Completed, None, None).validate().is_ok());
+
+        assert!(constant_time_eq(b"s3cret", b"s3cret"));
+        assert!(!constant_time_eq(b"s3cre", b"s3cret"));
+        assert!(!constant_time_eq(b"s3creT", b"s3cret"));
+    }
+}
@@ -0,0 +1,64 @@
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::config::N8nConfig;
+use crate::error::VhqError;
+
+pub mod api;
+pub mod callback;
+pub mod sync;
+pub mod webhook;
+
+pub use api::{DeployedWorkflow, N8nExecution, N8nWorkflow};
+pub use sync::SyncReport;
+pub use webhook::WebhookResponse;
+
+/// HTTP client for the configured n8n instance
+#[derive(Debug, Clone)]
+pub struct N8nClient {
+    config: N8nConfig,
+    http: reqwest::Client,
+}
+
+impl N8nClient {
+    pub fn new(config: &N8nConfig) -> Result<Self, VhqError> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| VhqError::Internal(format!("Failed to create n8n client: {}", e)))?;
+
+        Ok(Self { config: config.clone(), http })
+    }
+
+    /// Base URL without a trailing slash
+    fn base_url(&self) -> &str {
+        self.config.url.trim_end_matches('/')
+    }
+
+    /// Error for a request that got no usable response
+    fn request_error(&self, operation: &str, error: &reqwest::Error) -> VhqError {
+        if error.is_timeout() {
+            VhqError::Timeout { operation: operation.to_string(), secs: self.config.timeout_secs }
+        } else {
+            VhqError::request("n8n", error)
+        }
+    }
+}
+
+/// JSON body, the raw text if it is not JSON, or null when empty
+fn parse_body(text: &str) -> Value {
+    if text.trim().is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Error for a non-success response. n8n explains e.g. unregistered webhooks
+/// or invalid workflows in a `message` field.
+fn response_error(status: reqwest::StatusCode, body: &Value, context: &str) -> VhqError {
+    let message = match body.get("message").and_then(Value::as_str) {
+        Some(message) => message.to_string(),
+        None => body.as_str().map(str::to_string).unwrap_or_else(|| body.to_string()),
+    };
+    VhqError::backend("n8n", Some(status.as_u16()), format!("{}: {}", context, message))
+}
@@ -0,0 +1,433 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::{N8nClient, N8nWorkflow};
+use crate::database;
+use crate::error::VhqError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    Create,
+    Update,
+    Unchanged,
+    /// The definition could not be loaded, substituted or validated
+    Invalid,
+    /// n8n rejected the deployment
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowSync {
+    pub file: String,
+    pub name: Option<String>,
+    pub action: SyncAction,
+    /// One line per difference, prefixed with `+`, `-` or `~`
+    pub changes: Vec<String>,
+    pub errors: Vec<String>,
+    pub workflow_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub dry_run: bool,
+    pub workflows: Vec<WorkflowSync>,
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "n8n workflow sync{}", if self.dry_run { " (dry run)" } else { "" })?;
+        for workflow in &self.workflows {
+            let name = workflow.name.as_deref().unwrap_or("?");
+            let marker = match workflow.action {
+                SyncAction::Create => "+",
+                SyncAction::Update => "~",
+                SyncAction::Unchanged => "=",
+                SyncAction::Invalid | SyncAction::Failed => "!",
+            };
+            writeln!(f, "{} {} ({}): {:?}", marker, name, workflow.file, workflow.action)?;
+            for line in workflow.changes.iter().chain(&workflow.errors) {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl N8nClient {
+    /// Deploy the workflow definitions in `dir` whose content differs from n8n.
+    /// With `dry_run` nothing is deployed and the report only shows the differences.
+    pub async fn sync_workflows(&self, dir: &Path, dry_run: bool) -> Result<SyncReport, VhqError> {
+        let files = definition_files(dir)?;
+        let variables = resolve_variables(&self.config.variables, |name| std::env::var(name).ok());
+        let existing = self.list_workflows().await?;
+
+        let mut workflows = Vec::new();
+        for path in files {
+            let file = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let mut sync = WorkflowSync {
+                file,
+                name: None,
+                action: SyncAction::Invalid,
+                changes: Vec::new(),
+                errors: Vec::new(),
+                workflow_id: None,
+            };
+
+            let mut definition = match std::fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<Value>(&text).map_err(|e| format!("invalid JSON: {}", e)))
+            {
+                Ok(definition) => definition,
+                Err(e) => {
+                    sync.errors.push(e);
+                    workflows.push(sync);
+                    continue;
+                }
+            };
+            sync.name = definition.get("name").and_then(Value::as_str).map(str::to_string);
+            sync.errors.extend(substitute(&mut definition, &variables));
+            sync.errors.extend(validate(&definition));
+            if !sync.errors.is_empty() {
+                workflows.push(sync);
+                continue;
+            }
+
+            let current = existing.iter().find(|workflow| Some(&workflow.name) == sync.name.as_ref());
+            sync.workflow_id = current.map(|workflow| workflow.id.clone());
+            (sync.action, sync.changes) = match current {
+                Some(current) => {
+                    let changes = diff(&definition, current);
+                    (if changes.is_empty() { SyncAction::Unchanged } else { SyncAction::Update }, changes)
+                }
+                None => (SyncAction::Create, node_names(&definition).map(|name| format!("+ node {}", name)).collect()),
+            };
+
+            if !dry_run && matches!(sync.action, SyncAction::Create | SyncAction::Update) {
+                match self.save_workflow(&definition, current).await {
+                    Ok(deployed) => sync.workflow_id = Some(deployed.workflow.id),
+                    Err(e) => {
+                        sync.action = SyncAction::Failed;
+                        sync.errors.push(e.to_string());
+                    }
+                }
+            }
+            workflows.push(sync);
+        }
+
+        let report = SyncReport { dry_run, workflows };
+        info!("{}", report);
+        Ok(report)
+    }
+}
+
+/// `*.json` files in `dir`, sorted by name
+fn definition_files(dir: &Path) -> Result<Vec<PathBuf>, VhqError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => VhqError::FileNotFound(dir.display().to_string()),
+        _ => VhqError::Internal(format!("Failed to read workflows directory {}: {}", dir.display(), e)),
+    })?;
+
+    let mut files: Vec<PathBuf> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Placeholder values: configured ones, then the environment, then defaults for this machine
+pub fn resolve_variables(configured: &BTreeMap<String, String>, env: impl Fn(&str) -> Option<String>) -> BTreeMap<String, String> {
+    let agents_path = std::env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let defaults = [
+        ("VHQ_AGENTS_PATH", agents_path),
+        ("VHQ_WATCH_PATH", database::data_dir().join("watch")),
+        ("VHQ_OUTPUT_PATH", database::data_dir().join("output")),
+    ];
+
+    let mut variables: BTreeMap<String, String> = defaults.into_iter()
+        .map(|(name, default)| {
+            let value = env(name).unwrap_or_else(|| default.to_string_lossy().into_owned());
+            (name.to_string(), value)
+        })
+        .collect();
+    variables.extend(configured.iter().map(|(name, value)| (name.clone(), value.clone())));
+    variables
+}
+
+/// Replace `${VHQ_...}` placeholders in every string. Other `${...}`, e.g. in
+/// Code node JavaScript, are left alone. Returns a message per unknown name.
+pub fn substitute(value: &mut Value, variables: &BTreeMap<String, String>) -> Vec<String> {
+    let mut unknown = Vec::new();
+    substitute_into(value, variables, &mut unknown);
+    unknown.sort();
+    unknown.dedup();
+    unknown.into_iter().map(|name| format!("unknown placeholder ${{{}}}", name)).collect()
+}
+
+fn substitute_into(value: &mut Value, variables: &BTreeMap<String, String>, unknown: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            let mut result = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${VHQ_") {
+                let end = match rest[start..].find('}') {
+                    Some(end) => start + end,
+                    None => break,
+                };
+                let name = &rest[start + 2..end];
+                result.push_str(&rest[..start]);
+                match variables.get(name) {
+                    Some(replacement) => result.push_str(replacement),
+                    None => {
+                        unknown.push(name.to_string());
+                        result.push_str(&rest[start..=end]);
+                    }
+                }
+                rest = &rest[end + 1..];
+            }
+            result.push_str(rest);
+            *text = result;
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| substitute_into(item, variables, unknown)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| substitute_into(field, variables, unknown)),
+        _ => {}
+    }
+}
+
+/// Check the node list and that every connection joins existing nodes
+pub fn validate(definition: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if definition.get("name").and_then(Value::as_str).is_none_or(|name| name.trim().is_empty()) {
+        problems.push("name must be a non-empty string".to_string());
+    }
+
+    let nodes = match definition.get("nodes").and_then(Value::as_array) {
+        Some(nodes) if !nodes.is_empty() => nodes,
+        _ => {
+            problems.push("nodes must be a non-empty array".to_string());
+            return problems;
+        }
+    };
+
+    let mut names = HashSet::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let name = match node.get("name").and_then(Value::as_str).filter(|name| !name.trim().is_empty()) {
+            Some(name) => name,
+            None => {
+                problems.push(format!("node {} has no name", index));
+                continue;
+            }
+        };
+        if !names.insert(name) {
+            problems.push(format!("node {} is defined more than once", name));
+        }
+        if !node.get("type").and_then(Value::as_str).is_some_and(|node_type| node_type.contains('.')) {
+            problems.push(format!("node {} needs a type such as n8n-nodes-base.if", name));
+        }
+        if !node.get("typeVersion").is_some_and(Value::is_number) {
+            problems.push(format!("node {} needs a numeric typeVersion", name));
+        }
+        if !node.get("parameters").is_some_and(Value::is_object) {
+            problems.push(format!("node {} needs a parameters object", name));
+        }
+        let position = node.get("position").and_then(Value::as_array);
+        if !position.is_some_and(|position| position.len() == 2 && position.iter().all(Value::is_number)) {
+            problems.push(format!("node {} needs a position [x, y]", name));
+        }
+    }
+
+    let connections = match definition.get("connections") {
+        None => return problems,
+        Some(Value::Object(connections)) => connections,
+        Some(_) => {
+            problems.push("connections must be an object".to_string());
+            return problems;
+        }
+    };
+    for (source, outputs) in connections {
+        if !names.contains(source.as_str()) {
+            problems.push(format!("connections refer to unknown node {}", source));
+        }
+        let outputs = match outputs.as_object() {
+            Some(outputs) => outputs,
+            None => {
+                problems.push(format!("connections of {} must be an object", source));
+                continue;
+            }
+        };
+        for (kind, branches) in outputs {
+            let targets = branches.as_array().into_iter().flatten().map(|branch| branch.as_array());
+            for (branch, targets) in targets.enumerate() {
+                let targets = match targets {
+                    Some(targets) => targets,
+                    None => {
+                        problems.push(format!("{} output {} of {} must be an array", kind, branch, source));
+                        continue;
+                    }
+                };
+                for target in targets {
+                    match target.get("node").and_then(Value::as_str) {
+                        Some(node) if names.contains(node) => {}
+                        Some(node) => problems.push(format!("{} connects to unknown node {}", source, node)),
+                        None => problems.push(format!("{} has a connection without a target node", source)),
+                    }
+                    if target.get("index").and_then(Value::as_u64).is_none() {
+                        problems.push(format!("{} has a connection without an input index", source));
+                    }
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Differences between a definition and the deployed workflow that matter to n8n.
+/// Node ids and positions are ignored since the editor changes them freely.
+pub fn diff(definition: &Value, current: &N8nWorkflow) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    let deployed: BTreeMap<&str, &Value> = current.nodes.iter()
+        .filter_map(|node| Some((node.get("name")?.as_str()?, node)))
+        .collect();
+    let desired: BTreeMap<&str, &Value> = definition["nodes"].as_array().into_iter().flatten()
+        .filter_map(|node| Some((node.get("name")?.as_str()?, node)))
+        .collect();
+
+    for (name, node) in &desired {
+        match deployed.get(name) {
+            None => changes.push(format!("+ node {}", name)),
+            Some(existing) => {
+                for field in ["type", "typeVersion", "parameters", "credentials", "disabled"] {
+                    if node.get(field) != existing.get(field) {
+                        changes.push(format!("~ node {}: {}", name, field));
+                    }
+                }
+            }
+        }
+    }
+    for name in deployed.keys().filter(|name| !desired.contains_key(*name)) {
+        changes.push(format!("- node {}", name));
+    }
+
+    let connections = definition.get("connections").cloned().unwrap_or_else(|| serde_json::json!({}));
+    let deployed_connections = if current.connections.is_null() { serde_json::json!({}) } else { current.connections.clone() };
+    if connections != deployed_connections {
+        changes.push("~ connections".to_string());
+    }
+
+    // n8n fills in defaults for settings that were not given
+    if let Some(settings) = definition.get("settings").and_then(Value::as_object) {
+        for (key, value) in settings {
+            if current.settings.get(key) != Some(value) {
+                changes.push(format!("~ settings.{}", key));
+            }
+        }
+    }
+
+    if let Some(active) = definition.get("active").and_then(Value::as_bool) {
+        if active != current.active {
+            changes.push(format!("~ active: {} -> {}", current.active, active));
+        }
+    }
+
+    changes
+}
+
+fn node_names(definition: &Value) -> impl Iterator<Item = &str> {
+    definition["nodes"].as_array().into_iter().flatten().filter_map(|node| node.get("name")?.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition() -> Value {
+        serde_json::json!({
+            "name": "VITRA Transcription Pipeline",
+            "active": true,
+            "settings": { "executionOrder": "v1" },
+            "nodes": [
+                {
+                    "name": "File Trigger", "type": "n8n-nodes-base.fileTrigger", "typeVersion": 1, "position": [240, 300],
+                    "parameters": { "path": "${VHQ_WATCH_PATH}/input_audio" }
+                },
+                {
+                    "name": "Execute VITRA", "type": "n8n-nodes-base.executeCommand", "typeVersion": 1, "position": [460, 300],
+                    "parameters": { "command": "${VHQ_AGENTS_PATH}/vitra_lag", "arguments": "{{ $json.filePath }}" }
+                }
+            ],
+            "connections": {
+                "File Trigger": { "main": [[{ "node": "Execute VITRA", "type": "main", "index": 0 }]] }
+            }
+        })
+    }
+
+    #[test]
+    fn test_substitutes_configured_then_environment_variables() {
+        let configured = BTreeMap::from([("VHQ_AGENTS_PATH".to_string(), "/opt/vhq/bin".to_string())]);
+        let env = |name: &str| (name == "VHQ_WATCH_PATH").then(|| "/srv/inbox".to_string());
+        let variables = resolve_variables(&configured, env);
+
+        let mut workflow = definition();
+        assert!(substitute(&mut workflow, &variables).is_empty());
+        assert_eq!(workflow["nodes"][0]["parameters"]["path"], "/srv/inbox/input_audio");
+        assert_eq!(workflow["nodes"][1]["parameters"]["command"], "/opt/vhq/bin/vitra_lag");
+        assert_eq!(workflow["nodes"][1]["parameters"]["arguments"], "{{ $json.filePath }}");
+
+        let mut unknown = serde_json::json!({ "cwd": "${VHQ_HOME}/x", "code": "`${total}`" });
+        assert_eq!(substitute(&mut unknown, &variables), ["unknown placeholder ${VHQ_HOME}"]);
+        assert_eq!(unknown["code"], "`${total}`");
+    }
+
+    #[test]
+    fn test_validates_nodes_and_connections() {
+        assert!(validate(&definition()).is_empty());
+
+        let mut broken = definition();
+        broken["nodes"][1]["type"] = Value::Null;
+        broken["connections"]["Execute VITRA"] = serde_json::json!({ "main": [[{ "node": "Check Success", "index": 0 }]] });
+        let problems = validate(&broken);
+
+        assert_eq!(problems, [
+            "node Execute VITRA needs a type such as n8n-nodes-base.if",
+            "Execute VITRA connects to unknown node Check Success",
+        ]);
+    }
+
+    #[test]
+    fn test_diff_ignores_positions_and_reports_changes() {
+        let desired = definition();
+        let mut nodes = desired["nodes"].as_array().unwrap().clone();
+        nodes[0]["position"] = serde_json::json!([0, 0]);
+        nodes[0]["id"] = serde_json::json!("generated-id");
+        let mut current = N8nWorkflow {
+            id: "1".to_string(),
+            name: "VITRA Transcription Pipeline".to_string(),
+            active: true,
+            nodes,
+            connections: desired["connections"].clone(),
+            settings: serde_json::json!({ "executionOrder": "v1", "saveManualExecutions": true }),
+            created_at: None,
+            updated_at: None,
+        };
+        assert!(diff(&desired, &current).is_empty());
+
+        current.nodes[1]["parameters"]["command"] = serde_json::json!("cargo run --bin vitra_lag");
+        current.nodes.push(serde_json::json!({ "name": "Old Notification" }));
+        current.active = false;
+
+        assert_eq!(diff(&desired, &current), [
+            "~ node Execute VITRA: parameters",
+            "- node Old Notification",
+            "~ active: false -> true",
+        ]);
+    }
+}
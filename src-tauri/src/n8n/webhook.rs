@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::{parse_body, response_error, N8nClient};
+use crate::config::WebhookAuth;
+use crate::error::VhqError;
+
+/// What n8n answered to a webhook call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookResponse {
+    pub workflow: String,
+    pub url: String,
+    pub status: u16,
+    /// JSON body, the raw text if it was not JSON, or null when empty. Depending on the
+    /// webhook's response mode this is the workflow's output or just an acknowledgement.
+    pub body: Value,
+    pub attempts: u32,
+}
+
+impl N8nClient {
+    /// Webhook URL of a workflow: its configured path, or its name
+    pub fn webhook_url(&self, workflow: &str) -> String {
+        let path = self.config.webhooks.get(workflow).map(String::as_str).unwrap_or(workflow);
+        let prefix = if self.config.use_test_webhooks { "webhook-test" } else { "webhook" };
+        format!("{}/{}/{}", self.base_url(), prefix, path.trim_start_matches('/'))
+    }
+
+    /// POST `payload` to the workflow's webhook, retrying failures that may be temporary
+    pub async fn trigger(&self, workflow: &str, payload: &Value) -> Result<WebhookResponse, VhqError> {
+        let url = self.webhook_url(workflow);
+        let max_attempts = self.config.max_attempts.max(1);
+
+        let mut attempt = 1;
+        loop {
+            match self.post_webhook(workflow, &url, payload).await {
+                Ok((status, body)) => {
+                    info!("n8n workflow {} triggered (HTTP {}, attempt {})", workflow, status, attempt);
+                    return Ok(WebhookResponse { workflow: workflow.to_string(), url, status, body, attempts: attempt });
+                }
+                Err(e) if e.retryable() && attempt < max_attempts => {
+                    let delay = self.config.retry_delay_ms.saturating_mul(1 << (attempt - 1).min(16));
+                    warn!("Triggering n8n workflow {} failed (attempt {}/{}), retrying in {}ms: {}", workflow, attempt, max_attempts, delay, e);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn post_webhook(&self, workflow: &str, url: &str, payload: &Value) -> Result<(u16, Value), VhqError> {
+        let mut request = self.http.post(url).json(payload);
+        request = match &self.config.webhook_auth {
+            Some(WebhookAuth::Header { name, value }) => request.header(name.as_str(), value.as_str()),
+            Some(WebhookAuth::Basic { username, password }) => request.basic_auth(username, Some(password)),
+            None => request,
+        };
+
+        let response = request.send().await
+            .map_err(|e| self.request_error(&format!("n8n workflow {}", workflow), &e))?;
+        let status = response.status();
+        let text = response.text().await
+            .map_err(|e| self.request_error(&format!("n8n workflow {}", workflow), &e))?;
+        let body = parse_body(&text);
+
+        if !status.is_success() {
+            return Err(response_error(status, &body, &format!("workflow {}", workflow)));
+        }
+
+        Ok((status.as_u16(), body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::N8nConfig;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(server: &MockServer) -> N8nConfig {
+        N8nConfig {
+            url: format!("{}/", server.uri()),
+            retry_delay_ms: 10,
+            ..N8nConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_posts_payload_with_auth_to_configured_path() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/webhook/vhq/transcribe"))
+            .and(header("X-VHQ-Key", "secret"))
+            .and(body_json(serde_json::json!({ "file": "talk.mp3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "executionId": "42" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = config(&server);
+        config.webhooks.insert("vitra".to_string(), "/vhq/transcribe".to_string());
+        config.webhook_auth = Some(WebhookAuth::Header { name: "X-VHQ-Key".to_string(), value: "secret".to_string() });
+
+        let response = N8nClient::new(&config).unwrap()
+            .trigger("vitra", &serde_json::json!({ "file": "talk.mp3" }))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, serde_json::json!({ "executionId": "42" }));
+        assert_eq!(response.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors_on_test_webhooks() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/webhook-test/ghost"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/webhook-test/ghost"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Workflow was started"))
+            .mount(&server)
+            .await;
+
+        let mut config = config(&server);
+        config.use_test_webhooks = true;
+
+        let response = N8nClient::new(&config).unwrap().trigger("ghost", &Value::Null).await.unwrap();
+
+        assert_eq!(response.attempts, 3);
+        assert_eq!(response.body, Value::String("Workflow was started".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_webhook_fails_without_retry() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "code": 404,
+                "message": "The requested webhook \"POST missing\" is not registered."
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = N8nClient::new(&config(&server)).unwrap().trigger("missing", &Value::Null).await.unwrap_err();
+
+        assert_eq!(error.code(), "backend_error");
+        assert!(!error.retryable());
+        assert!(error.to_string().contains("is not registered"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_slow_webhook_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+            .mount(&server)
+            .await;
+
+        let mut config = config(&server);
+        config.timeout_secs = 1;
+        config.max_attempts = 1;
+
+        let error = N8nClient::new(&config).unwrap().trigger("slow", &Value::Null).await.unwrap_err();
+
+        assert_eq!(error.code(), "timeout");
+    }
+}
@@ -0,0 +1,556 @@
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn, error};
+
+use super::{Combinator, Condition, NotifyLevel, Operator, Pipeline, PipelineStep, StepAction};
+use crate::agents::ceo::{CeoAgent, Task, TaskStatus};
+use crate::agents::graph::{TaskGraphRequest, TaskNodeRequest};
+use crate::database::workflows::{WorkflowRecord, WorkflowRun};
+use crate::database::Database;
+use crate::error::VhqError;
+
+/// How often the state of a queued agent task is checked
+const TASK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long an agent step waits for its task unless configured otherwise
+const DEFAULT_STEP_TIMEOUT: Duration = Duration::from_secs(3600);
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Executes the agent steps of a pipeline
+pub trait AgentSteps: Send + Sync {
+    /// Process `file_path` with `agent` (`vitra_lag`, `ghost_lag`, or `ceo` to let the CEO
+    /// pick one) and return the agent's output. Fails once `timeout` has passed.
+    fn run<'a>(&'a self, agent: &'a str, file_path: &'a str, options: Value, timeout: Duration) -> BoxFuture<'a, Result<String>>;
+}
+
+/// Agent steps queued as CEO tasks, so they share the agents' limits, retries and timeouts
+#[derive(Clone)]
+pub struct CeoSteps {
+    ceo: Arc<RwLock<CeoAgent>>,
+    db: Database,
+}
+
+impl CeoSteps {
+    pub fn new(ceo: Arc<RwLock<CeoAgent>>, db: Database) -> Self {
+        Self { ceo, db }
+    }
+
+    async fn queue(&self, agent: &str, file_path: &str, options: Value) -> Result<String> {
+        let ceo = self.ceo.read().await;
+        if ceo.get_status().status != "running" {
+            return Err(VhqError::invalid_input("CEO_LAG must be running to execute agent steps").into());
+        }
+
+        if agent == "ceo" {
+            let queued: Value = serde_json::from_str(&ceo.process_file(file_path, options).await?)?;
+            return queued.get("task_id").and_then(Value::as_str).map(str::to_string)
+                .ok_or_else(|| VhqError::Internal("CEO_LAG did not return a task id".to_string()).into());
+        }
+
+        let graph = ceo.submit_graph(TaskGraphRequest {
+            name: None,
+            nodes: vec![TaskNodeRequest {
+                key: "pipeline_step".to_string(),
+                agent_name: agent.to_string(),
+                file_path: Some(file_path.to_string()),
+                options,
+                depends_on: Vec::new(),
+                priority: None,
+            }],
+        }).await?;
+        Ok(graph.nodes[0].task_id.clone())
+    }
+
+    async fn wait(&self, task_id: &str) -> Result<Task> {
+        loop {
+            tokio::time::sleep(TASK_POLL_INTERVAL).await;
+            let task = self.db.tasks().get(task_id).await?
+                .ok_or_else(|| VhqError::NotFound { entity: "task", id: task_id.to_string() })?;
+            if task.status.is_terminal() {
+                return Ok(task);
+            }
+        }
+    }
+}
+
+impl AgentSteps for CeoSteps {
+    fn run<'a>(&'a self, agent: &'a str, file_path: &'a str, options: Value, timeout: Duration) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let task_id = self.queue(agent, file_path, options).await?;
+            self.outcome(task_id, timeout).await
+        })
+    }
+}
+
+impl CeoSteps {
+    /// Wait up to `timeout` for a queued task and return its result
+    async fn outcome(&self, task_id: String, timeout: Duration) -> Result<String> {
+        let mut queued = QueuedTask { ceo: self.ceo.clone(), task_id: Some(task_id.clone()) };
+
+        let task = match tokio::time::timeout(timeout, self.wait(&task_id)).await {
+            Ok(task) => task?,
+            Err(_) => return Err(VhqError::Timeout {
+                operation: format!("waiting for task {}", task_id),
+                secs: timeout.as_secs(),
+            }.into()),
+        };
+        queued.task_id = None;
+
+        match task.status {
+            TaskStatus::Completed => Ok(task.result.unwrap_or_default()),
+            TaskStatus::Cancelled => Err(VhqError::Cancelled.into()),
+            status => Err(anyhow::anyhow!(
+                "Task {} ended as {}: {}", task_id, status.as_str(), task.error.unwrap_or_default()
+            )),
+        }
+    }
+}
+
+/// Cancels a queued task when the step stops waiting for it before it finished,
+/// because it timed out or the run was dropped
+struct QueuedTask {
+    ceo: Arc<RwLock<CeoAgent>>,
+    task_id: Option<String>,
+}
+
+impl Drop for QueuedTask {
+    fn drop(&mut self) {
+        let (Some(task_id), Ok(runtime)) = (self.task_id.take(), tokio::runtime::Handle::try_current()) else {
+            return;
+        };
+        let ceo = self.ceo.clone();
+        runtime.spawn(async move {
+            if let Err(e) = ceo.read().await.cancel_task(&task_id).await {
+                warn!("Failed to cancel abandoned pipeline task {}: {}", task_id, e);
+            }
+        });
+    }
+}
+
+type Listener = Arc<dyn Fn(&WorkflowRecord) + Send + Sync>;
+
+/// Runs pipelines step by step, storing each run in the `workflows` table
+/// and each step in `workflow_steps` like the runs n8n reports
+#[derive(Clone)]
+pub struct PipelineEngine {
+    db: Database,
+    agents: Arc<dyn AgentSteps>,
+    step_timeout: Duration,
+    listener: Option<Listener>,
+}
+
+/// What a step produced, as seen by the expressions of later steps
+#[derive(Debug, Default)]
+struct StepOutput {
+    output: String,
+    exit_code: Option<i32>,
+    data: Value,
+}
+
+impl PipelineEngine {
+    pub fn new(db: Database, agents: Arc<dyn AgentSteps>) -> Self {
+        Self { db, agents, step_timeout: DEFAULT_STEP_TIMEOUT, listener: None }
+    }
+
+    /// How long agent steps without their own `timeout_secs` wait for their task
+    pub fn with_step_timeout(mut self, timeout: Duration) -> Self {
+        self.step_timeout = timeout;
+        self
+    }
+
+    /// Called with the run whenever it is stored
+    pub fn with_listener(mut self, listener: Option<Listener>) -> Self {
+        self.listener = listener;
+        self
+    }
+
+    /// Run `pipeline` to its end. `trigger` is available to expressions as `trigger`.
+    /// A failed step without an `on_error` branch fails the run, which is still returned.
+    pub async fn run(&self, pipeline: &Pipeline, trigger: Value) -> Result<WorkflowRun> {
+        let repository = self.db.workflows();
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut record = WorkflowRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: pipeline.name.clone(),
+            status: "running".to_string(),
+            progress: 0.0,
+            current_step: None,
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+            finished_at: None,
+        };
+        info!("Running pipeline {} ({})", pipeline.name, record.id);
+
+        let mut context = json!({ "trigger": trigger, "steps": {} });
+        let mut steps = Vec::new();
+        let mut next = pipeline.first_step().map(str::to_string);
+
+        while let Some(id) = next.take() {
+            let step = pipeline.step(&id)
+                .ok_or_else(|| VhqError::invalid_input(format!("Pipeline {} has no step {}", pipeline.name, id)))?;
+            record.current_step = Some(step.id.clone());
+            record.updated_at = chrono::Utc::now().to_rfc3339();
+            self.store(&record).await?;
+
+            let started = repository.start_step(&record.id, &step.id, &record.updated_at).await?;
+            let result = self.execute(step, &context).await;
+            let finished_at = chrono::Utc::now().to_rfc3339();
+
+            let (status, error) = match &result {
+                Ok(_) => ("completed", None),
+                Err(e) => ("failed", Some(e.to_string())),
+            };
+            steps.push(repository.finish_step(&started, status, error.as_deref(), &finished_at).await?);
+
+            let output = result.as_ref().map(|output| step_value(status, output, None))
+                .unwrap_or_else(|e| step_value(status, &StepOutput::default(), Some(e.to_string())));
+            context["steps"][step.id.as_str()] = output;
+
+            next = match (&result, &step.action) {
+                (Ok(output), StepAction::Condition { then, otherwise, .. }) => {
+                    if output.data == Value::Bool(true) { then.clone() } else { otherwise.clone() }
+                }
+                (Ok(_), _) => step.on_success.clone(),
+                (Err(_), _) if step.on_error.is_some() => step.on_error.clone(),
+                (Err(e), _) => {
+                    warn!("Pipeline {} failed at step {}: {}", pipeline.name, step.id, e);
+                    record.status = "failed".to_string();
+                    record.error = Some(format!("Step {} failed: {}", step.id, e));
+                    None
+                }
+            };
+            record.progress = (steps.len() as f64 / pipeline.steps.len() as f64).min(1.0);
+        }
+
+        if record.status != "failed" {
+            record.status = "completed".to_string();
+            record.progress = 1.0;
+        }
+        record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        record.updated_at = record.finished_at.clone().unwrap_or_default();
+        self.store(&record).await?;
+        info!("Pipeline {} {} ({})", pipeline.name, record.status, record.id);
+
+        Ok(WorkflowRun::new(record, steps))
+    }
+
+    async fn store(&self, record: &WorkflowRecord) -> Result<()> {
+        self.db.workflows().upsert(record).await?;
+        if let Some(listener) = &self.listener {
+            listener(record);
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, step: &PipelineStep, context: &Value) -> Result<StepOutput> {
+        match &step.action {
+            StepAction::Vitra { file, options, timeout_secs } => self.run_agent("vitra_lag", file, options, *timeout_secs, context).await,
+            StepAction::Ghost { file, options, timeout_secs } => self.run_agent("ghost_lag", file, options, *timeout_secs, context).await,
+            StepAction::Ceo { file, options, timeout_secs } => self.run_agent("ceo", file, options, *timeout_secs, context).await,
+            StepAction::Shell { command, args, cwd, timeout_secs } => {
+                let args: Vec<String> = args.iter().map(|arg| render_text(arg, context)).collect();
+                let cwd = cwd.as_deref().map(|cwd| render_text(cwd, context));
+                run_command(&render_text(command, context), &args, cwd.as_deref(), *timeout_secs).await
+            }
+            StepAction::Condition { conditions, combinator, .. } => {
+                let mut results = conditions.iter().map(|condition| holds(condition, context));
+                let passed = match combinator {
+                    Combinator::All => results.all(|holds| holds),
+                    Combinator::Any => results.any(|holds| holds),
+                };
+                Ok(StepOutput { output: passed.to_string(), exit_code: None, data: Value::Bool(passed) })
+            }
+            StepAction::Move { source, destination } => {
+                let target = move_file(Path::new(&render_text(source, context)), Path::new(&render_text(destination, context))).await?;
+                Ok(StepOutput { output: target.clone(), exit_code: None, data: json!({ "path": target }) })
+            }
+            StepAction::Notify { message, level } => {
+                let message = render_text(message, context);
+                match level {
+                    NotifyLevel::Info => info!("Pipeline notification: {}", message),
+                    NotifyLevel::Warning => warn!("Pipeline notification: {}", message),
+                    NotifyLevel::Error => error!("Pipeline notification: {}", message),
+                }
+                Ok(StepOutput { output: message, exit_code: None, data: Value::Null })
+            }
+        }
+    }
+
+    async fn run_agent(&self, agent: &str, file: &str, options: &Value, timeout_secs: Option<u64>, context: &Value) -> Result<StepOutput> {
+        let timeout = timeout_secs.map(Duration::from_secs).unwrap_or(self.step_timeout);
+        let output = self.agents.run(agent, &render_text(file, context), render(options, context), timeout).await?;
+        let data = serde_json::from_str(&output).unwrap_or(Value::Null);
+        Ok(StepOutput { output, exit_code: None, data })
+    }
+}
+
+/// A step's entry under `steps` in the expression context
+fn step_value(status: &str, output: &StepOutput, error: Option<String>) -> Value {
+    json!({
+        "status": status,
+        "output": output.output,
+        "exit_code": output.exit_code,
+        "data": output.data,
+        "error": error,
+    })
+}
+
+async fn run_command(command: &str, args: &[String], cwd: Option<&str>, timeout_secs: Option<u64>) -> Result<StepOutput> {
+    let mut process = tokio::process::Command::new(command);
+    process.args(args).kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+
+    let output = match timeout_secs {
+        Some(secs) => tokio::time::timeout(Duration::from_secs(secs), process.output()).await
+            .map_err(|_| VhqError::Timeout { operation: command.to_string(), secs })?,
+        None => process.output().await,
+    };
+    let output = output.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => VhqError::tool_missing(command, e),
+        _ => VhqError::tool_crashed(command, e),
+    })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match output.status.code() {
+            Some(code) => VhqError::tool_failed(command, format!("exited with code {}: {}", code, stderr.trim())),
+            None => VhqError::tool_crashed(command, format!("was terminated: {}", stderr.trim())),
+        }.into());
+    }
+
+    Ok(StepOutput {
+        data: serde_json::from_str(&stdout).unwrap_or(Value::Null),
+        output: stdout,
+        exit_code: output.status.code(),
+    })
+}
+
+/// Move `source` into the directory `destination`, copying across filesystems
+async fn move_file(source: &Path, destination: &Path) -> Result<String> {
+    let name = source.file_name()
+        .ok_or_else(|| VhqError::invalid_input(format!("Cannot move {}: not a file", source.display())))?;
+    if !tokio::fs::try_exists(source).await.unwrap_or(false) {
+        return Err(VhqError::FileNotFound(source.display().to_string()).into());
+    }
+
+    tokio::fs::create_dir_all(destination).await?;
+    let target = destination.join(name);
+    if tokio::fs::rename(source, &target).await.is_err() {
+        tokio::fs::copy(source, &target).await?;
+        tokio::fs::remove_file(source).await?;
+    }
+    Ok(target.display().to_string())
+}
+
+/// Replace `{{ path }}` expressions in every string of `value`. A string that is a single
+/// expression takes the referenced value as is, so numbers stay numbers.
+pub fn render(value: &Value, context: &Value) -> Value {
+    match value {
+        Value::String(text) => {
+            let trimmed = text.trim();
+            let single = trimmed.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}"))
+                .filter(|inner| !inner.contains("{{") && !inner.contains("}}"));
+            match single {
+                Some(path) => lookup(context, path.trim()).cloned().unwrap_or(Value::Null),
+                None => Value::String(render_text(text, context)),
+            }
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, context)).collect()),
+        Value::Object(fields) => Value::Object(fields.iter().map(|(key, field)| (key.clone(), render(field, context))).collect::<Map<_, _>>()),
+        other => other.clone(),
+    }
+}
+
+/// Replace `{{ path }}` expressions in `text`; missing values become empty
+pub fn render_text(text: &str, context: &Value) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        result.push_str(&to_text(lookup(context, rest[start + 2..end].trim()).unwrap_or(&Value::Null)));
+        rest = &rest[end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Value at a dotted path such as `steps.transcribe.data.language` or `trigger.files.0`
+fn lookup<'a>(context: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(context, |value, key| match value {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+        _ => value.get(key),
+    })
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub fn holds(condition: &Condition, context: &Value) -> bool {
+    let left = render(&condition.left, context);
+    let right = render(&condition.right, context);
+    let equal = || match (as_number(&left), as_number(&right)) {
+        (Some(left), Some(right)) => left == right,
+        _ => to_text(&left) == to_text(&right),
+    };
+    let empty = match &left {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => false,
+    };
+
+    match condition.operator {
+        Operator::Equals => equal(),
+        Operator::NotEquals => !equal(),
+        Operator::Contains => match &left {
+            Value::Array(items) => items.contains(&right),
+            _ => to_text(&left).contains(&to_text(&right)),
+        },
+        Operator::GreaterThan => matches!((as_number(&left), as_number(&right)), (Some(left), Some(right)) if left > right),
+        Operator::LessThan => matches!((as_number(&left), as_number(&right)), (Some(left), Some(right)) if left < right),
+        Operator::Empty => empty,
+        Operator::NotEmpty => !empty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::collections::BTreeMap;
+
+    /// Agents answering with a fixed transcription
+    struct FakeAgents;
+
+    impl AgentSteps for FakeAgents {
+        fn run<'a>(&'a self, agent: &'a str, file_path: &'a str, _options: Value, _timeout: Duration) -> BoxFuture<'a, Result<String>> {
+            Box::pin(async move {
+                match file_path {
+                    "broken.mp3" => Err(VhqError::tool_failed(agent, "whisper crashed").into()),
+                    _ => Ok(json!({ "file_path": file_path, "confidence": 0.9 }).to_string()),
+                }
+            })
+        }
+    }
+
+    async fn engine() -> PipelineEngine {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        PipelineEngine::new(Database::new(pool), Arc::new(FakeAgents))
+    }
+
+    const PIPELINE: &str = r#"
+name: Transcribe
+steps:
+  - { id: transcribe, type: vitra, file: "{{ trigger.file_path }}", on_success: check, on_error: failed }
+  - id: check
+    type: condition
+    conditions: [{ left: "{{ steps.transcribe.data.confidence }}", operator: greater_than, right: 0.5 }]
+    then: done
+  - { id: done, type: notify, message: "Transcribed {{ trigger.file_path }}" }
+  - { id: failed, type: notify, level: error, message: "{{ steps.transcribe.error }}" }
+"#;
+
+    #[tokio::test]
+    async fn test_follows_condition_and_error_branches() {
+        let pipeline = Pipeline::parse(PIPELINE, "yaml", &BTreeMap::new()).unwrap();
+        let engine = engine().await;
+
+        let run = engine.run(&pipeline, json!({ "file_path": "talk.mp3" })).await.unwrap();
+        assert_eq!(run.record.status, "completed");
+        assert_eq!(run.steps.iter().map(|step| step.name.as_str()).collect::<Vec<_>>(), ["transcribe", "check", "done"]);
+
+        let run = engine.run(&pipeline, json!({ "file_path": "broken.mp3" })).await.unwrap();
+        assert_eq!(run.record.status, "completed");
+        assert_eq!(run.steps[0].status, "failed");
+        assert_eq!(run.steps[1].name, "failed");
+        assert_eq!(run.steps[0].error.as_deref(), Some("vitra_lag failed: whisper crashed"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_step_times_out_and_cancels_its_task() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let db = Database::new(pool);
+        // Never started, like a CEO stopped after the step was queued
+        let ceo = Arc::new(RwLock::new(CeoAgent::new(db.clone())));
+        let graph = ceo.read().await.submit_graph(TaskGraphRequest {
+            name: None,
+            nodes: vec![TaskNodeRequest {
+                key: "pipeline_step".to_string(),
+                agent_name: "vitra_lag".to_string(),
+                file_path: None,
+                options: Value::Null,
+                depends_on: Vec::new(),
+                priority: None,
+            }],
+        }).await.unwrap();
+        let task_id = graph.nodes[0].task_id.clone();
+
+        let steps = CeoSteps::new(ceo, db.clone());
+        let error = steps.outcome(task_id.clone(), Duration::from_millis(50)).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<VhqError>(), Some(VhqError::Timeout { .. })));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(db.tasks().get(&task_id).await.unwrap().unwrap().status, TaskStatus::Cancelled);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_exit_code_fails_run_without_error_branch() {
+        let definition = r#"{"name": "Shell", "steps": [
+            {"id": "list", "type": "shell", "command": "sh", "args": ["-c", "echo '{\"count\": 2}'"], "on_success": "fail"},
+            {"id": "fail", "type": "shell", "command": "sh", "args": ["-c", "exit {{ steps.list.data.count }}"]}
+        ]}"#;
+        let pipeline = Pipeline::parse(definition, "json", &BTreeMap::new()).unwrap();
+
+        let run = engine().await.run(&pipeline, Value::Null).await.unwrap();
+
+        assert_eq!(run.record.status, "failed");
+        assert_eq!(run.record.error.as_deref(), Some("Step fail failed: sh failed: exited with code 2: "));
+        assert!(run.duration_ms.is_some());
+    }
+
+    #[test]
+    fn test_renders_expressions_and_compares_numbers() {
+        let context = json!({ "steps": { "run": { "exit_code": 0, "output": "done" } } });
+
+        assert_eq!(render(&json!("{{ steps.run.exit_code }}"), &context), json!(0));
+        assert_eq!(render_text("{{steps.run.output}} ({{ steps.missing.output }})", &context), "done ()");
+
+        let condition = |left: &str, operator, right| Condition { left: json!(left), operator, right };
+        assert!(holds(&condition("{{ steps.run.exit_code }}", Operator::Equals, json!("0")), &context));
+        assert!(holds(&condition("{{ steps.run.output }}", Operator::Contains, json!("on")), &context));
+        assert!(holds(&condition("{{ steps.missing }}", Operator::Empty, Value::Null), &context));
+        assert!(!holds(&condition("{{ steps.run.output }}", Operator::GreaterThan, json!(1)), &context));
+    }
+}
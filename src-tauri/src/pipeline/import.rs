@@ -0,0 +1,619 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use tracing::info;
+
+use super::{Combinator, Condition, NotifyLevel, Operator, Pipeline, PipelineStep, StepAction, Trigger};
+use crate::error::VhqError;
+
+/// n8n node that has no native counterpart, or uses options the native step lacks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsupportedNode {
+    pub name: String,
+    pub node_type: String,
+    pub reason: String,
+}
+
+/// Outcome of converting one n8n workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct N8nImport {
+    pub workflow: String,
+    /// Without the unsupported nodes and the branches leading to them
+    pub pipeline: Pipeline,
+    /// `pipeline` as YAML, ready for `pipelines.dir`
+    pub yaml: String,
+    pub unsupported: Vec<UnsupportedNode>,
+    /// Conversions that may behave differently than in n8n
+    pub warnings: Vec<String>,
+    /// Where the pipeline was written; only complete conversions are saved
+    pub saved_to: Option<String>,
+}
+
+impl N8nImport {
+    /// Whether every node was converted
+    pub fn complete(&self) -> bool {
+        self.unsupported.is_empty()
+    }
+}
+
+/// An n8n node as exported
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Node {
+    name: String,
+    #[serde(rename = "type")]
+    node_type: String,
+    #[serde(default)]
+    parameters: Value,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(default)]
+    continue_on_fail: bool,
+    /// `stopWorkflow`, `continueRegularOutput` or `continueErrorOutput`
+    #[serde(default)]
+    on_error: Option<String>,
+}
+
+impl Node {
+    /// Type without the package, e.g. `if` for `n8n-nodes-base.if`
+    fn kind(&self) -> &str {
+        self.node_type.rsplit('.').next().unwrap_or(&self.node_type)
+    }
+
+    fn is_trigger(&self) -> bool {
+        matches!(self.kind(), "fileTrigger" | "localFileTrigger" | "manualTrigger" | "scheduleTrigger")
+    }
+
+    fn text(&self, key: &str) -> String {
+        self.parameters.get(key).and_then(Value::as_str).unwrap_or_default().to_string()
+    }
+}
+
+/// Convert an exported n8n workflow into a native pipeline. Supported nodes are
+/// `fileTrigger`, `localFileTrigger`, `manualTrigger`, `scheduleTrigger` with a cron
+/// expression, `executeCommand`, `if`, `moveFiles` and `notification`. Any other node is
+/// reported in `unsupported` and left out together with the branches leading to it.
+pub fn import_n8n(definition: &Value) -> Result<N8nImport, VhqError> {
+    let workflow = definition.get("name").and_then(Value::as_str)
+        .filter(|name| !name.trim().is_empty())
+        .ok_or_else(|| VhqError::invalid_input("n8n workflow has no name"))?
+        .to_string();
+    let nodes: Vec<Node> = definition.get("nodes").cloned()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| VhqError::invalid_input(format!("Invalid nodes in n8n workflow {}: {}", workflow, e)))?
+        .unwrap_or_default();
+    let outputs = connections(definition.get("connections").unwrap_or(&Value::Null));
+
+    let mut converter = Converter::new(&nodes, &outputs);
+    let pipeline = converter.convert(&workflow);
+    let mut warnings = converter.warnings;
+    warnings.extend(pipeline.validate());
+
+    let yaml = serde_yaml::to_string(&pipeline)
+        .map_err(|e| VhqError::Internal(format!("Failed to write pipeline {} as YAML: {}", workflow, e)))?;
+    Ok(N8nImport { workflow, pipeline, yaml, unsupported: converter.unsupported, warnings, saved_to: None })
+}
+
+/// Target node names per source node and output index, from n8n's `connections`
+fn connections(connections: &Value) -> HashMap<String, Vec<Vec<String>>> {
+    let mut outputs = HashMap::new();
+    for (source, types) in connections.as_object().into_iter().flatten() {
+        let main = types.get("main").and_then(Value::as_array).into_iter().flatten();
+        let targets = main.map(|output| {
+            output.as_array().into_iter().flatten()
+                .filter_map(|target| target.get("node").and_then(Value::as_str).map(str::to_string))
+                .collect()
+        });
+        outputs.insert(source.clone(), targets.collect());
+    }
+    outputs
+}
+
+struct Converter<'a> {
+    nodes: HashMap<&'a str, &'a Node>,
+    order: Vec<&'a Node>,
+    outputs: &'a HashMap<String, Vec<Vec<String>>>,
+    /// Step id per node name
+    ids: HashMap<&'a str, String>,
+    /// Nodes feeding each node
+    inputs: HashMap<&'a str, Vec<&'a str>>,
+    unsupported: Vec<UnsupportedNode>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(nodes: &'a [Node], outputs: &'a HashMap<String, Vec<Vec<String>>>) -> Self {
+        let mut taken = HashSet::new();
+        let mut ids = HashMap::new();
+        for node in nodes {
+            let base = step_id(&node.name);
+            let mut id = base.clone();
+            let mut suffix = 2;
+            while !taken.insert(id.clone()) {
+                id = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            ids.insert(node.name.as_str(), id);
+        }
+
+        let mut inputs: HashMap<&str, Vec<&str>> = HashMap::new();
+        for node in nodes {
+            for target in outputs.get(&node.name).into_iter().flatten().flatten() {
+                if let Some(target) = nodes.iter().find(|candidate| &candidate.name == target) {
+                    inputs.entry(target.name.as_str()).or_default().push(node.name.as_str());
+                }
+            }
+        }
+
+        Self {
+            nodes: nodes.iter().map(|node| (node.name.as_str(), node)).collect(),
+            order: nodes.iter().collect(),
+            outputs,
+            ids,
+            inputs,
+            unsupported: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn convert(&mut self, workflow: &str) -> Pipeline {
+        let mut trigger = None;
+        let mut start = None;
+        let mut steps = Vec::new();
+
+        for node in self.order.clone() {
+            if node.disabled {
+                self.warnings.push(format!("{} is disabled in n8n and was left out", node.name));
+            } else if node.is_trigger() {
+                match self.trigger(node) {
+                    Some(_) if trigger.is_some() => {
+                        self.warnings.push(format!("{} is an additional trigger; only the first one is used", node.name));
+                    }
+                    Some(converted) => {
+                        trigger = Some(converted);
+                        start = self.branch(node, 0);
+                    }
+                    None => {}
+                }
+            } else if let Some(step) = self.step(node) {
+                steps.push(step);
+            }
+        }
+
+        // Branches into nodes that were not converted end the run instead
+        let converted: HashSet<String> = steps.iter().map(|step: &PipelineStep| step.id.clone()).collect();
+        let keep = |branch: &mut Option<String>| {
+            if branch.as_ref().is_some_and(|id| !converted.contains(id)) {
+                *branch = None;
+            }
+        };
+        for step in &mut steps {
+            keep(&mut step.on_success);
+            keep(&mut step.on_error);
+            if let StepAction::Condition { then, otherwise, .. } = &mut step.action {
+                keep(then);
+                keep(otherwise);
+            }
+        }
+        let mut start = start.filter(|id| converted.contains(id));
+        if start.is_none() && !steps.is_empty() {
+            self.warnings.push(format!("No trigger leads to a converted node; runs begin with {}", steps[0].id));
+        }
+        // The first step is the default start
+        if start.as_ref() == steps.first().map(|step| &step.id) {
+            start = None;
+        }
+
+        Pipeline {
+            name: workflow.to_string(),
+            description: Some(format!("Imported from the n8n workflow {}", workflow)),
+            trigger: trigger.unwrap_or_default(),
+            start,
+            steps,
+        }
+    }
+
+    fn trigger(&mut self, node: &Node) -> Option<Trigger> {
+        match node.kind() {
+            "manualTrigger" => Some(Trigger::Manual),
+            "fileTrigger" | "localFileTrigger" => {
+                let path = node.text("path");
+                let extensions = node.parameters.get("fileTypes").and_then(Value::as_array).into_iter().flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect();
+                Some(Trigger::File { path, extensions })
+            }
+            "scheduleTrigger" => {
+                let cron = node.parameters.pointer("/rule/interval/0/expression").and_then(Value::as_str);
+                match cron {
+                    Some(cron) => Some(Trigger::Schedule { cron: cron.to_string() }),
+                    None => {
+                        self.reject(node, "only cron expression schedules are supported");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn step(&mut self, node: &'a Node) -> Option<PipelineStep> {
+        let id = self.ids[node.name.as_str()].clone();
+        let action = match node.kind() {
+            "executeCommand" => {
+                let mut words = split_arguments(&node.text("command")).into_iter();
+                let command = self.expression(node, &words.next().unwrap_or_default());
+                let args = words.chain(split_arguments(&node.text("arguments")))
+                    .map(|arg| self.expression(node, &arg))
+                    .collect();
+                let cwd = node.parameters.pointer("/options/cwd").and_then(Value::as_str).map(|cwd| self.expression(node, cwd));
+                StepAction::Shell { command, args, cwd, timeout_secs: None }
+            }
+            "if" => return self.condition(node, id),
+            "moveFiles" => StepAction::Move {
+                source: self.expression(node, &node.text("source")),
+                destination: self.expression(node, &node.text("destination")),
+            },
+            "notification" => StepAction::Notify {
+                message: self.expression(node, &node.text("message")),
+                level: NotifyLevel::Info,
+            },
+            _ => {
+                self.reject(node, &format!("no native step for {}", node.node_type));
+                return None;
+            }
+        };
+
+        let on_success = self.branch(node, 0);
+        let on_error = match node.on_error.as_deref() {
+            Some("continueErrorOutput") => self.branch(node, 1),
+            Some("continueRegularOutput") => on_success.clone(),
+            _ if node.continue_on_fail => on_success.clone(),
+            _ => None,
+        };
+        Some(PipelineStep { id, action, on_success, on_error })
+    }
+
+    fn condition(&mut self, node: &'a Node, id: String) -> Option<PipelineStep> {
+        let combinator = match node.parameters.pointer("/conditions/combinator").and_then(Value::as_str) {
+            Some("or") => Combinator::Any,
+            _ => Combinator::All,
+        };
+        let rules = node.parameters.pointer("/conditions/conditions").and_then(Value::as_array).cloned().unwrap_or_default();
+        if rules.is_empty() {
+            self.reject(node, "only conditions in the format of If node version 2 are supported");
+            return None;
+        }
+
+        let mut conditions = Vec::new();
+        for rule in &rules {
+            let operation = rule.pointer("/operator/operation").and_then(Value::as_str).unwrap_or_default();
+            let operator = match operation {
+                "equals" => Operator::Equals,
+                "notEquals" => Operator::NotEquals,
+                "contains" => Operator::Contains,
+                "gt" => Operator::GreaterThan,
+                "lt" => Operator::LessThan,
+                "empty" | "notExists" => Operator::Empty,
+                "notEmpty" | "exists" => Operator::NotEmpty,
+                _ => {
+                    self.reject(node, &format!("operator {} is not supported", operation));
+                    return None;
+                }
+            };
+            let left = rule.get("leftValue").cloned().unwrap_or_default();
+            let right = rule.get("rightValue").cloned().unwrap_or_default();
+            self.check_exit_code(node, &left);
+            conditions.push(Condition { left: self.value(node, left), operator, right: self.value(node, right) });
+        }
+
+        Some(PipelineStep {
+            id,
+            action: StepAction::Condition { conditions, combinator, then: self.branch(node, 0), otherwise: self.branch(node, 1) },
+            on_success: None,
+            on_error: None,
+        })
+    }
+
+    /// Commands that stop the run on failure never hand a non-zero exit code to a check
+    fn check_exit_code(&mut self, node: &Node, left: &Value) {
+        if !left.as_str().is_some_and(|left| left.contains("exitCode")) {
+            return;
+        }
+        if let Some(source) = self.source(&node.name).filter(|source| source.kind() == "executeCommand") {
+            if !source.continue_on_fail && source.on_error.is_none() {
+                self.warnings.push(format!(
+                    "{} checks the exit code of {}, which fails the run on a non-zero exit code; \
+                     set an on_error branch to handle failures", node.name, source.name
+                ));
+            }
+        }
+    }
+
+    /// Step id of the first node connected to `output`; further targets are not run
+    fn branch(&mut self, node: &Node, output: usize) -> Option<String> {
+        let targets = self.outputs.get(&node.name)?.get(output)?;
+        if targets.len() > 1 {
+            self.warnings.push(format!("{} output {} leads to several nodes; only {} is kept", node.name, output, targets[0]));
+        }
+        let target = targets.first()?;
+        self.ids.get(target.as_str()).cloned()
+    }
+
+    /// Node whose output `$json` refers to in `name`: its input, looking through `if` nodes
+    fn source(&self, name: &str) -> Option<&'a Node> {
+        let mut current = name;
+        let mut seen = HashSet::new();
+        loop {
+            let input = *self.inputs.get(current)?.first()?;
+            let node = *self.nodes.get(input)?;
+            if node.kind() != "if" || !seen.insert(input) {
+                return Some(node);
+            }
+            current = input;
+        }
+    }
+
+    fn value(&mut self, node: &Node, value: Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.expression(node, &text)),
+            other => other,
+        }
+    }
+
+    /// Translate n8n expressions such as `={{ $json.exitCode }}` into native ones
+    fn expression(&mut self, node: &Node, text: &str) -> String {
+        let text = text.strip_prefix('=').unwrap_or(text);
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let end = match rest[start..].find("}}") {
+                Some(end) => start + end,
+                None => break,
+            };
+            result.push_str(&rest[..start]);
+            let inner = rest[start + 2..end].trim();
+            match self.reference(node, inner) {
+                Some(path) => result.push_str(&format!("{{{{ {} }}}}", path)),
+                None => {
+                    self.warnings.push(format!("{}: expression {{{{ {} }}}} could not be translated", node.name, inner));
+                    result.push_str(&rest[start..end + 2]);
+                }
+            }
+            rest = &rest[end + 2..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Native path for `$json.field` or `$('Node').item.json.field`
+    fn reference(&mut self, node: &Node, expression: &str) -> Option<String> {
+        let (source, field) = if let Some(field) = expression.strip_prefix("$json.") {
+            (self.source(&node.name)?, field)
+        } else {
+            let rest = expression.strip_prefix("$('")?;
+            let (name, field) = rest.split_once("').item.json.")?;
+            (*self.nodes.get(name)?, field)
+        };
+        if !field.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+            return None;
+        }
+
+        if source.is_trigger() {
+            let field = match field {
+                "filePath" | "path" => "file_path",
+                "fileName" | "name" => "file_name",
+                "timestamp" => "scheduled_at",
+                other => {
+                    self.warnings.push(format!("{}: the trigger provides no {}", node.name, other));
+                    other
+                }
+            };
+            return Some(format!("trigger.{}", field));
+        }
+
+        let id = self.ids.get(source.name.as_str())?;
+        Some(match (source.kind(), field) {
+            ("executeCommand", "exitCode") => format!("steps.{}.exit_code", id),
+            ("executeCommand", "stdout") => format!("steps.{}.output", id),
+            ("executeCommand", "stderr") => format!("steps.{}.error", id),
+            ("moveFiles", "path" | "destination") => format!("steps.{}.data.path", id),
+            _ => format!("steps.{}.data.{}", id, field),
+        })
+    }
+
+    fn reject(&mut self, node: &Node, reason: &str) {
+        self.unsupported.push(UnsupportedNode {
+            name: node.name.clone(),
+            node_type: node.node_type.clone(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Split a command line at spaces outside quotes and `{{ }}` expressions, dropping the quotes
+fn split_arguments(text: &str) -> Vec<String> {
+    let text = text.strip_prefix('=').unwrap_or(text);
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('{', None) if chars.peek() == Some(&'{') => {
+                // Copy the expression as a whole
+                word.push_str("{{");
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    word.push(c);
+                    if previous == '}' && c == '}' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (c, _) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Step id from a node name, e.g. `execute_vitra` for `Execute VITRA`
+fn step_id(name: &str) -> String {
+    let id: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let id = id.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_");
+    if id.is_empty() { "step".to_string() } else { id }
+}
+
+/// File name for an imported pipeline, e.g. `vitra-transcription-pipeline.yaml`
+pub fn file_name(pipeline: &Pipeline) -> String {
+    format!("{}.yaml", step_id(&pipeline.name).replace('_', "-"))
+}
+
+/// Export `file` inside `dir`; absolute paths and `..` components are rejected
+pub fn workflow_path(dir: &Path, file: &str) -> Result<PathBuf, VhqError> {
+    let relative = Path::new(file);
+    if file.trim().is_empty() || relative.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(VhqError::invalid_input(format!("{:?} must be a file name inside the n8n workflows directory", file)));
+    }
+    Ok(dir.join(relative))
+}
+
+/// Convert the n8n export at `path`. With `save_to`, a complete conversion is also written
+/// to that directory, unless a file of the same name exists there.
+pub fn import_file(path: &Path, save_to: Option<&Path>) -> Result<N8nImport, VhqError> {
+    let text = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => VhqError::FileNotFound(path.display().to_string()),
+        _ => VhqError::Internal(format!("Failed to read {}: {}", path.display(), e)),
+    })?;
+    let definition: Value = serde_json::from_str(&text)
+        .map_err(|e| VhqError::invalid_input(format!("{} is not valid JSON: {}", path.display(), e)))?;
+    let mut import = import_n8n(&definition)?;
+
+    let dir = match save_to {
+        Some(dir) => dir,
+        None => return Ok(import),
+    };
+    let target = dir.join(file_name(&import.pipeline));
+    if !import.complete() {
+        import.warnings.push(format!("Not saved: {} nodes could not be converted", import.unsupported.len()));
+    } else if target.exists() {
+        import.warnings.push(format!("Not saved: {} already exists", target.display()));
+    } else {
+        std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(&target, &import.yaml))
+            .map_err(|e| VhqError::Internal(format!("Failed to write {}: {}", target.display(), e)))?;
+        info!("Imported n8n workflow {} as {}", import.workflow, target.display());
+        import.saved_to = Some(target.display().to_string());
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VITRA: &str = include_str!("../../../n8n-workflows/vitra-pipeline.json");
+
+    #[test]
+    fn test_converts_bundled_vitra_workflow() {
+        let import = import_n8n(&serde_json::from_str(VITRA).unwrap()).unwrap();
+
+        assert!(import.complete());
+        let pipeline = &import.pipeline;
+        assert!(matches!(&pipeline.trigger, Trigger::File { path, .. } if path == "${VHQ_WATCH_PATH}/input_audio"));
+        assert_eq!(pipeline.steps.iter().map(|step| step.id.as_str()).collect::<Vec<_>>(),
+            ["execute_vitra", "check_success", "move_file", "success_notification", "error_notification"]);
+
+        match &pipeline.steps[0].action {
+            StepAction::Shell { command, args, cwd, .. } => {
+                assert_eq!(command, "${VHQ_AGENTS_PATH}/vitra_lag");
+                assert_eq!(args, &["{{ trigger.file_path }}"]);
+                assert_eq!(cwd.as_deref(), Some("${VHQ_AGENTS_PATH}"));
+            }
+            other => panic!("expected a shell step, got {:?}", other),
+        }
+        match &pipeline.steps[1].action {
+            StepAction::Condition { conditions, then, otherwise, .. } => {
+                assert_eq!(conditions[0].left, Value::String("{{ steps.execute_vitra.exit_code }}".to_string()));
+                assert_eq!((then.as_deref(), otherwise.as_deref()), (Some("move_file"), Some("error_notification")));
+            }
+            other => panic!("expected a condition, got {:?}", other),
+        }
+        assert!(matches!(&pipeline.steps[2].action, StepAction::Move { source, .. } if source == "{{ steps.execute_vitra.data.outputPath }}"));
+        assert!(import.warnings.iter().any(|warning| warning.contains("exit code of Execute VITRA")), "{:?}", import.warnings);
+
+        // The YAML loads back as the same pipeline
+        let variables = ["VHQ_AGENTS_PATH", "VHQ_WATCH_PATH", "VHQ_OUTPUT_PATH"].into_iter()
+            .map(|name| (name.to_string(), "/vhq".to_string()))
+            .collect();
+        let reloaded = Pipeline::parse(&import.yaml, "yaml", &variables).unwrap();
+        assert_eq!(reloaded.steps.len(), 5);
+        assert_eq!(file_name(pipeline), "vitra-transcription-pipeline.yaml");
+    }
+
+    #[test]
+    fn test_workflow_path_stays_inside_directory() {
+        let dir = Path::new("n8n-workflows");
+
+        assert_eq!(workflow_path(dir, "vitra-pipeline.json").unwrap(), dir.join("vitra-pipeline.json"));
+        assert!(workflow_path(dir, "../config.json").is_err());
+        assert!(workflow_path(dir, "exports/../../secrets.json").is_err());
+        assert!(workflow_path(dir, "/etc/passwd").is_err());
+        assert!(workflow_path(dir, "").is_err());
+    }
+
+    #[test]
+    fn test_splits_arguments_around_expressions_and_quotes() {
+        assert_eq!(
+            split_arguments("{{ $json.filePath }} --title \"My talk\" --content-type {{ $json.contentType }}"),
+            ["{{ $json.filePath }}", "--title", "My talk", "--content-type", "{{ $json.contentType }}"]
+        );
+    }
+
+    #[test]
+    fn test_reports_unsupported_nodes_and_drops_their_branches() {
+        let definition = serde_json::json!({
+            "name": "Upload",
+            "nodes": [
+                { "name": "Start", "type": "n8n-nodes-base.manualTrigger", "parameters": {} },
+                { "name": "Upload", "type": "n8n-nodes-base.httpRequest", "parameters": {} },
+                { "name": "Check", "type": "n8n-nodes-base.if", "parameters": { "conditions": { "conditions": [
+                    { "leftValue": "={{ $json.name }}", "rightValue": "^a", "operator": { "type": "string", "operation": "regex" } }
+                ] } } },
+                { "name": "Done", "type": "n8n-nodes-base.notification", "parameters": { "message": "Uploaded" } }
+            ],
+            "connections": {
+                "Start": { "main": [[{ "node": "Done", "type": "main", "index": 0 }]] },
+                "Done": { "main": [[{ "node": "Upload", "type": "main", "index": 0 }]] }
+            }
+        });
+
+        let import = import_n8n(&definition).unwrap();
+
+        assert!(!import.complete());
+        assert_eq!(import.unsupported, [
+            UnsupportedNode { name: "Upload".to_string(), node_type: "n8n-nodes-base.httpRequest".to_string(), reason: "no native step for n8n-nodes-base.httpRequest".to_string() },
+            UnsupportedNode { name: "Check".to_string(), node_type: "n8n-nodes-base.if".to_string(), reason: "operator regex is not supported".to_string() },
+        ]);
+        assert_eq!(import.pipeline.steps.len(), 1);
+        assert_eq!(import.pipeline.steps[0].on_success, None);
+        assert_eq!(import.pipeline.trigger, Trigger::Manual);
+    }
+}
@@ -0,0 +1,423 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::agents::scheduler;
+use crate::error::VhqError;
+use crate::n8n::sync::substitute;
+
+pub mod engine;
+pub mod import;
+pub mod registry;
+pub mod triggers;
+
+pub use engine::{CeoSteps, PipelineEngine};
+pub use registry::PipelineRegistry;
+
+/// A workflow the app runs itself, without n8n. Written in YAML or JSON, e.g.
+///
+/// ```yaml
+/// name: VITRA Transcription Pipeline
+/// trigger: { type: file, path: "${VHQ_WATCH_PATH}/input_audio", extensions: [mp3, wav] }
+/// steps:
+///   - id: transcribe
+///     type: vitra
+///     file: "{{ trigger.file_path }}"
+///     on_success: done
+///     on_error: failed
+///   - { id: done, type: notify, message: "Transcribed {{ trigger.file_name }}" }
+///   - { id: failed, type: notify, level: error, message: "{{ steps.transcribe.error }}" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pipeline {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub trigger: Trigger,
+    /// Step a run begins with; the first step when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// What starts a run besides a manual start, which every pipeline allows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Trigger {
+    #[default]
+    Manual,
+    /// A file appearing in `path`. `extensions` limits which ones; all when empty.
+    File {
+        path: String,
+        #[serde(default)]
+        extensions: Vec<String>,
+    },
+    /// A cron expression, with or without the seconds field
+    Schedule { cron: String },
+}
+
+/// One node of a pipeline. Steps and their branches form a graph without cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub id: String,
+    #[serde(flatten)]
+    pub action: StepAction,
+    /// Step to continue with once this one succeeded; the run completes when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_success: Option<String>,
+    /// Step to continue with when this one failed; the run fails when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<String>,
+}
+
+/// What a step does. String fields may contain `{{ trigger.file_path }}` or
+/// `{{ steps.<id>.output }}` expressions, see `engine::render`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Transcribe `file` with VITRA through the CEO's task queue
+    Vitra {
+        file: String,
+        #[serde(default)]
+        options: Value,
+        /// Seconds to wait for the task; `pipelines.step_timeout_secs` when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_secs: Option<u64>,
+    },
+    /// Generate content from `file` with GHOST through the CEO's task queue
+    Ghost {
+        file: String,
+        #[serde(default)]
+        options: Value,
+        /// Seconds to wait for the task; `pipelines.step_timeout_secs` when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_secs: Option<u64>,
+    },
+    /// Let the CEO pick the agent for `file` by its extension
+    Ceo {
+        file: String,
+        #[serde(default)]
+        options: Value,
+        /// Seconds to wait for the task; `pipelines.step_timeout_secs` when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_secs: Option<u64>,
+    },
+    /// Run a program directly, without a shell. A non-zero exit code fails the step.
+    Shell {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_secs: Option<u64>,
+    },
+    /// Continue with `then` when the conditions hold and with `else` otherwise; the run
+    /// completes when the chosen branch is unset
+    Condition {
+        conditions: Vec<Condition>,
+        #[serde(default)]
+        combinator: Combinator,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        then: Option<String>,
+        #[serde(default, rename = "else", skip_serializing_if = "Option::is_none")]
+        otherwise: Option<String>,
+    },
+    /// Move the file `source` into the directory `destination`
+    Move { source: String, destination: String },
+    /// Report `message` in the log and the step's output
+    Notify {
+        message: String,
+        #[serde(default)]
+        level: NotifyLevel,
+    },
+}
+
+impl StepAction {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StepAction::Vitra { .. } => "vitra",
+            StepAction::Ghost { .. } => "ghost",
+            StepAction::Ceo { .. } => "ceo",
+            StepAction::Shell { .. } => "shell",
+            StepAction::Condition { .. } => "condition",
+            StepAction::Move { .. } => "move",
+            StepAction::Notify { .. } => "notify",
+        }
+    }
+}
+
+/// Comparison of an expression, usually an earlier step's output, with a value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    pub left: Value,
+    pub operator: Operator,
+    /// Unused by `empty` and `not_empty`
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub right: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+    GreaterThan,
+    LessThan,
+    Empty,
+    NotEmpty,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Combinator {
+    /// Every condition holds
+    #[default]
+    All,
+    /// At least one condition holds
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyLevel {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/// A definition file and the pipeline it holds, or why it could not be used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineFile {
+    pub file: String,
+    pub pipeline: Option<Pipeline>,
+    pub errors: Vec<String>,
+}
+
+impl Pipeline {
+    /// Parse a definition, replacing `${VHQ_...}` placeholders, and validate it.
+    /// `extension` picks the format: `json`, or YAML for anything else.
+    pub fn parse(text: &str, extension: &str, variables: &BTreeMap<String, String>) -> Result<Self, Vec<String>> {
+        let mut definition: Value = if extension.eq_ignore_ascii_case("json") {
+            serde_json::from_str(text).map_err(|e| vec![format!("invalid JSON: {}", e)])?
+        } else {
+            serde_yaml::from_str(text).map_err(|e| vec![format!("invalid YAML: {}", e)])?
+        };
+
+        let unknown = substitute(&mut definition, variables);
+        if !unknown.is_empty() {
+            return Err(unknown);
+        }
+        let pipeline: Pipeline = serde_json::from_value(definition).map_err(|e| vec![e.to_string()])?;
+
+        let problems = pipeline.validate();
+        if problems.is_empty() {
+            Ok(pipeline)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Report every problem at once: missing or duplicate steps, dangling branches and cycles
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.name.trim().is_empty() {
+            problems.push("name must not be empty".to_string());
+        }
+        if self.steps.is_empty() {
+            problems.push("steps must not be empty".to_string());
+        }
+        match &self.trigger {
+            Trigger::File { path, .. } if path.trim().is_empty() => problems.push("trigger.path must not be empty".to_string()),
+            Trigger::Schedule { cron } => {
+                if let Err(e) = scheduler::parse_cron(cron) {
+                    problems.push(e.to_string());
+                }
+            }
+            _ => {}
+        }
+
+        let mut ids = HashSet::new();
+        for step in &self.steps {
+            if step.id.trim().is_empty() {
+                problems.push(format!("a {} step has no id", step.action.kind()));
+            } else if !ids.insert(step.id.as_str()) {
+                problems.push(format!("step {} is defined more than once", step.id));
+            }
+        }
+
+        if let Some(start) = self.start.as_deref().filter(|start| !ids.contains(start)) {
+            problems.push(format!("start refers to unknown step {}", start));
+        }
+        for step in &self.steps {
+            if let StepAction::Condition { conditions, .. } = &step.action {
+                if conditions.is_empty() {
+                    problems.push(format!("step {} has no conditions", step.id));
+                }
+                if step.on_success.is_some() || step.on_error.is_some() {
+                    problems.push(format!("step {} branches with then and else, not on_success or on_error", step.id));
+                }
+            }
+            for next in next_steps(step) {
+                if !ids.contains(next) {
+                    problems.push(format!("step {} continues with unknown step {}", step.id, next));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            if let Some(step) = self.find_cycle() {
+                problems.push(format!("step {} is part of a cycle", step));
+            }
+        }
+        problems
+    }
+
+    pub fn step(&self, id: &str) -> Option<&PipelineStep> {
+        self.steps.iter().find(|step| step.id == id)
+    }
+
+    /// Id of the step a run begins with
+    pub fn first_step(&self) -> Option<&str> {
+        self.start.as_deref().or_else(|| self.steps.first().map(|step| step.id.as_str()))
+    }
+
+    /// A step that can be reached from itself
+    fn find_cycle(&self) -> Option<&str> {
+        let edges: HashMap<&str, Vec<&str>> = self.steps.iter()
+            .map(|step| (step.id.as_str(), next_steps(step).collect()))
+            .collect();
+
+        let mut done = HashSet::new();
+        let mut path = HashSet::new();
+        self.steps.iter().find_map(|step| visit(step.id.as_str(), &edges, &mut path, &mut done))
+    }
+}
+
+/// Depth-first search returning a step reached again while on `path`
+fn visit<'a>(id: &'a str, edges: &HashMap<&'a str, Vec<&'a str>>, path: &mut HashSet<&'a str>, done: &mut HashSet<&'a str>) -> Option<&'a str> {
+    if path.contains(id) {
+        return Some(id);
+    }
+    if !done.insert(id) {
+        return None;
+    }
+    path.insert(id);
+    let cycle = edges.get(id).into_iter().flatten().find_map(|next| visit(next, edges, path, done));
+    path.remove(id);
+    cycle
+}
+
+/// Steps a step may continue with
+fn next_steps(step: &PipelineStep) -> impl Iterator<Item = &str> {
+    let branches = match &step.action {
+        StepAction::Condition { then, otherwise, .. } => [then.as_deref(), otherwise.as_deref()],
+        _ => [step.on_success.as_deref(), step.on_error.as_deref()],
+    };
+    branches.into_iter().flatten()
+}
+
+/// Every definition in `dir`, sorted by file name. Later files may not reuse a pipeline's name.
+pub fn load_dir(dir: &Path, variables: &BTreeMap<String, String>) -> Result<Vec<PipelineFile>, VhqError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => VhqError::FileNotFound(dir.display().to_string()),
+        _ => VhqError::Internal(format!("Failed to read pipelines directory {}: {}", dir.display(), e)),
+    })?;
+
+    let mut paths: Vec<PathBuf> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml" || ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut names = HashSet::new();
+    let mut files = Vec::new();
+    for path in paths {
+        let file = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| vec![e.to_string()])
+            .and_then(|text| Pipeline::parse(&text, &extension, variables));
+        let (pipeline, errors) = match parsed {
+            Ok(pipeline) if !names.insert(pipeline.name.clone()) => {
+                (None, vec![format!("pipeline {} is already defined by another file", pipeline.name)])
+            }
+            Ok(pipeline) => (Some(pipeline), Vec::new()),
+            Err(errors) => (None, errors),
+        };
+        files.push(PipelineFile { file, pipeline, errors });
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIBE: &str = r#"
+name: Transcribe
+trigger:
+  type: file
+  path: ${VHQ_WATCH_PATH}/input_audio
+  extensions: [mp3]
+steps:
+  - id: transcribe
+    type: vitra
+    file: "{{ trigger.file_path }}"
+    options: { generate_srt: true }
+    on_success: check
+    on_error: failed
+  - id: check
+    type: condition
+    conditions:
+      - { left: "{{ steps.transcribe.data.confidence }}", operator: greater_than, right: 0.5 }
+    then: archive
+    else: failed
+  - { id: archive, type: move, source: "{{ trigger.file_path }}", destination: "${VHQ_OUTPUT_PATH}/done" }
+  - { id: failed, type: notify, level: error, message: "Transcription failed" }
+"#;
+
+    fn variables() -> BTreeMap<String, String> {
+        [("VHQ_WATCH_PATH", "/watch"), ("VHQ_OUTPUT_PATH", "/out")].into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parses_yaml_with_placeholders() {
+        let pipeline = Pipeline::parse(TRANSCRIBE, "yaml", &variables()).unwrap();
+
+        assert_eq!(pipeline.trigger, Trigger::File { path: "/watch/input_audio".to_string(), extensions: vec!["mp3".to_string()] });
+        assert_eq!(pipeline.first_step(), Some("transcribe"));
+        match &pipeline.step("check").unwrap().action {
+            StepAction::Condition { conditions, then, otherwise, .. } => {
+                assert_eq!(conditions[0].operator, Operator::GreaterThan);
+                assert_eq!((then.as_deref(), otherwise.as_deref()), (Some("archive"), Some("failed")));
+            }
+            other => panic!("expected a condition, got {:?}", other),
+        }
+        assert!(matches!(&pipeline.step("archive").unwrap().action, StepAction::Move { destination, .. } if destination == "/out/done"));
+    }
+
+    #[test]
+    fn test_rejects_dangling_branches_and_cycles() {
+        let dangling = TRANSCRIBE.replace("else: failed", "else: retry");
+        let errors = Pipeline::parse(&dangling, "yml", &variables()).unwrap_err();
+        assert_eq!(errors, ["step check continues with unknown step retry"]);
+
+        let cycle = TRANSCRIBE.replace("then: archive", "then: transcribe");
+        let errors = Pipeline::parse(&cycle, "yaml", &variables()).unwrap_err();
+        assert!(errors[0].contains("cycle"), "{:?}", errors);
+
+        let errors = Pipeline::parse(TRANSCRIBE, "yaml", &BTreeMap::new()).unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+}
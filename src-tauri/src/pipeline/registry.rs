@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{info, warn};
+
+use super::{load_dir, Pipeline, PipelineFile};
+use crate::database::workflows::WorkflowRecord;
+use crate::error::VhqError;
+
+pub type Listener = Arc<dyn Fn(&WorkflowRecord) + Send + Sync>;
+
+/// Pipelines loaded from `pipelines.dir`, including the files that could not be used
+#[derive(Clone, Default)]
+pub struct PipelineRegistry {
+    files: Arc<RwLock<Vec<PipelineFile>>>,
+    listener: Arc<Mutex<Option<Listener>>>,
+}
+
+impl PipelineRegistry {
+    /// Replace the loaded pipelines with the definitions in `dir` and return the usable ones
+    pub fn load(&self, dir: &Path, variables: &BTreeMap<String, String>) -> Result<Vec<Pipeline>, VhqError> {
+        let files = load_dir(dir, variables)?;
+        for file in files.iter().filter(|file| !file.errors.is_empty()) {
+            warn!("Ignoring pipeline {}: {}", file.file, file.errors.join("; "));
+        }
+
+        let pipelines: Vec<Pipeline> = files.iter().filter_map(|file| file.pipeline.clone()).collect();
+        info!("Loaded {} pipelines from {}", pipelines.len(), dir.display());
+        *self.files.write().unwrap() = files;
+        Ok(pipelines)
+    }
+
+    pub fn list(&self) -> Vec<PipelineFile> {
+        self.files.read().unwrap().clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Pipeline> {
+        self.files.read().unwrap().iter()
+            .filter_map(|file| file.pipeline.as_ref())
+            .find(|pipeline| pipeline.name == name)
+            .cloned()
+    }
+
+    /// Called with a pipeline run whenever it is stored, e.g. to emit it as a Tauri event
+    pub fn set_listener(&self, listener: impl Fn(&WorkflowRecord) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    pub fn listener(&self) -> Option<Listener> {
+        self.listener.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,122 @@
+use anyhow::Result;
+use notify::event::{CreateKind, ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn, error};
+
+use super::{Pipeline, Trigger};
+use crate::agents::scheduler;
+use crate::state::AppState;
+
+/// Files are usually still being written when they appear; wait until their size settles
+const SETTLE: Duration = Duration::from_secs(1);
+
+/// Start the file and schedule triggers of `pipelines`. They run until the app exits.
+/// A trigger that cannot start is logged and the other pipelines keep theirs.
+pub fn start(state: &AppState, pipelines: &[Pipeline]) {
+    for pipeline in pipelines {
+        let started = match &pipeline.trigger {
+            Trigger::Manual => Ok(()),
+            Trigger::File { path, extensions } => watch_files(state.clone(), pipeline.name.clone(), Path::new(path), extensions),
+            Trigger::Schedule { cron } => scheduler::parse_cron(cron).map(|schedule| {
+                tokio::spawn(run_on_schedule(state.clone(), pipeline.name.clone(), schedule));
+            }),
+        };
+        if let Err(e) = started {
+            error!("Failed to start the trigger of pipeline {}: {}", pipeline.name, e);
+        }
+    }
+}
+
+fn watch_files(state: AppState, name: String, dir: &Path, extensions: &[String]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        match event {
+            // Files created in the directory or moved into it
+            Ok(event) if matches!(event.kind,
+                EventKind::Create(CreateKind::File | CreateKind::Any)
+                | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Any))) => {
+                let _ = tx.send(event.paths);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Pipeline file watcher error: {}", e),
+        }
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    info!("Pipeline {} watches {}", name, dir.display());
+
+    let extensions: Vec<String> = extensions.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect();
+    tokio::spawn(async move {
+        // The watcher stops when dropped
+        let _watcher = watcher;
+        while let Some(paths) = rx.recv().await {
+            let paths: HashSet<PathBuf> = paths.into_iter().filter(|path| matches_extension(path, &extensions)).collect();
+            for path in paths {
+                let state = state.clone();
+                let name = name.clone();
+                tokio::spawn(async move {
+                    if !settled(&path).await {
+                        return;
+                    }
+                    let trigger = file_trigger(&path);
+                    if let Err(e) = state.workflows.run_pipeline(&state, &name, trigger).await {
+                        error!("Pipeline {} failed for {}: {}", name, path.display(), e);
+                    }
+                });
+            }
+        }
+        warn!("Pipeline {} stopped watching files", name);
+    });
+
+    Ok(())
+}
+
+async fn run_on_schedule(state: AppState, name: String, schedule: cron::Schedule) {
+    while let Some(next) = scheduler::next_occurrence(&schedule, chrono::Utc::now()) {
+        let wait = (next - chrono::Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        let trigger = json!({ "scheduled_at": next.to_rfc3339() });
+        if let Err(e) = state.workflows.run_pipeline(&state, &name, trigger).await {
+            error!("Scheduled run of pipeline {} failed: {}", name, e);
+        }
+    }
+}
+
+fn matches_extension(path: &Path, extensions: &[String]) -> bool {
+    extensions.is_empty() || path.extension()
+        .is_some_and(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()))
+}
+
+/// Wait until the file stops growing; false when it disappeared meanwhile
+async fn settled(path: &Path) -> bool {
+    let mut size = None;
+    loop {
+        tokio::time::sleep(SETTLE).await;
+        match tokio::fs::metadata(path).await {
+            Ok(metadata) if metadata.is_file() => {
+                if size == Some(metadata.len()) {
+                    return true;
+                }
+                size = Some(metadata.len());
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// What a file trigger passes to expressions as `trigger`
+fn file_trigger(path: &Path) -> Value {
+    json!({
+        "file_path": path.display().to_string(),
+        "file_name": path.file_name().map(|name| name.to_string_lossy().into_owned()),
+        "extension": path.extension().map(|ext| ext.to_string_lossy().into_owned()),
+        "directory": path.parent().map(|dir| dir.display().to_string()),
+    })
+}
@@ -0,0 +1,277 @@
+use anyhow::Result;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn, error};
+
+use crate::agents::templates::TemplateSet;
+use crate::config;
+use crate::state::AppState;
+
+/// Tauri event carrying a `ReloadEvent` after every reload attempt
+pub const RELOAD_EVENT: &str = "reload";
+
+/// Editors write a file in several steps; wait this long for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadTarget {
+    Config,
+    Templates,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadEvent {
+    pub target: ReloadTarget,
+    /// False when the new version was rejected and the previous one stays in use
+    pub success: bool,
+    pub errors: Vec<String>,
+    pub reloaded_at: String,
+}
+
+type Listener = Arc<dyn Fn(&ReloadEvent) + Send + Sync>;
+
+/// The templates directory and what is watched for it: the directory itself, or its
+/// parent until the directory is created
+struct TemplatesWatch {
+    dir: PathBuf,
+    watched: Option<PathBuf>,
+}
+
+impl TemplatesWatch {
+    fn waiting_for_dir(&self) -> bool {
+        self.watched.as_deref() != Some(self.dir.as_path())
+    }
+}
+
+/// Watches `config.toml` and the GHOST templates directory and swaps in valid changes
+/// without a restart. Running tasks keep the version they started with.
+#[derive(Clone)]
+pub struct HotReloader {
+    state: AppState,
+    listener: Arc<Mutex<Option<Listener>>>,
+}
+
+impl HotReloader {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state,
+            listener: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Called with the outcome of every reload, e.g. to emit it as a Tauri event
+    pub fn set_listener(&self, listener: impl Fn(&ReloadEvent) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    pub fn start(&self) -> Result<()> {
+        info!("Starting configuration and template watcher");
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) => {
+                    let _ = tx.send(event.paths);
+                }
+                Err(e) => warn!("File watcher error: {}", e),
+            }
+        })?;
+
+        // Watch the directory: editors often replace the file instead of writing it in place
+        let config_path = config::config_path();
+        if let Some(config_dir) = config_path.parent() {
+            std::fs::create_dir_all(config_dir)?;
+            watcher.watch(config_dir, RecursiveMode::NonRecursive)?;
+        }
+
+        let reloader = self.clone();
+        let mut config_changes = self.state.settings.subscribe();
+
+        tokio::spawn(async move {
+            let mut templates = reloader.watch_templates(&mut watcher, None);
+
+            loop {
+                tokio::select! {
+                    paths = rx.recv() => {
+                        let mut paths = match paths {
+                            Some(paths) => paths,
+                            None => break,
+                        };
+                        tokio::time::sleep(DEBOUNCE).await;
+                        while let Ok(more) = rx.try_recv() {
+                            paths.extend(more);
+                        }
+
+                        if paths.iter().any(|path| path.file_name() == config_path.file_name() && path.parent() == config_path.parent()) {
+                            reloader.reload_config().await;
+                        }
+                        if templates.waiting_for_dir() && templates.dir.is_dir() {
+                            templates = reloader.watch_templates(&mut watcher, Some(&templates));
+                            reloader.reload_templates().await;
+                        } else if paths.iter().any(|path| is_template(path, &templates.dir)) {
+                            reloader.reload_templates().await;
+                        }
+                    }
+                    changed = config_changes.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        let configured = config_changes.borrow_and_update().ghost.templates_dir.clone();
+                        let moved = templates.dir != canonical(Path::new(&configured));
+                        if moved || templates.waiting_for_dir() {
+                            templates = reloader.watch_templates(&mut watcher, Some(&templates));
+                            if moved || !templates.waiting_for_dir() {
+                                reloader.reload_templates().await;
+                            }
+                        }
+                    }
+                }
+            }
+
+            warn!("Configuration watcher stopped");
+        });
+
+        Ok(())
+    }
+
+    /// Watch the configured templates directory instead of `previous`. A directory that
+    /// does not exist yet is picked up once it is created in its parent.
+    fn watch_templates(&self, watcher: &mut RecommendedWatcher, previous: Option<&TemplatesWatch>) -> TemplatesWatch {
+        if let Some(watched) = previous.and_then(|previous| previous.watched.as_ref()) {
+            let _ = watcher.unwatch(watched);
+        }
+
+        let dir = canonical(Path::new(&self.state.settings.config().ghost.templates_dir));
+        let target = if dir.is_dir() {
+            Some(dir.clone())
+        } else {
+            warn!("GHOST templates directory {} does not exist; waiting for it to be created", dir.display());
+            dir.parent().filter(|parent| parent.is_dir()).map(Path::to_path_buf)
+        };
+        let watched = target.and_then(|target| match watcher.watch(&target, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                info!("Watching GHOST templates in {}", target.display());
+                Some(target)
+            }
+            Err(e) => {
+                error!("Failed to watch GHOST templates in {}: {}", target.display(), e);
+                None
+            }
+        });
+        TemplatesWatch { dir, watched }
+    }
+
+    async fn reload_config(&self) {
+        let result = async {
+            let (settings, config) = self.state.settings.reload().await?;
+            self.state.apply_config(&config).await?;
+            self.state.settings.notify(&settings);
+            Ok::<_, anyhow::Error>(())
+        }.await;
+
+        match result {
+            Ok(()) => self.report(ReloadTarget::Config, Vec::new()),
+            Err(e) => {
+                error!("Rejected configuration change: {}", e);
+                self.report(ReloadTarget::Config, vec![e.to_string()]);
+            }
+        }
+    }
+
+    /// Load the whole templates directory and swap it in only if every template is valid
+    async fn reload_templates(&self) {
+        let dir = self.state.settings.config().ghost.templates_dir;
+        let (templates, errors) = tokio::task::spawn_blocking(move || TemplateSet::load(Path::new(&dir)))
+            .await
+            .unwrap_or_else(|e| (TemplateSet::default(), vec![e.to_string()]));
+
+        if errors.is_empty() {
+            info!("Reloaded {} GHOST templates", templates.len());
+            self.state.ghost.write().await.templates = Arc::new(templates);
+        } else {
+            error!("Rejected GHOST template change: {}", errors.join("; "));
+        }
+        self.report(ReloadTarget::Templates, errors);
+    }
+
+    fn report(&self, target: ReloadTarget, errors: Vec<String>) {
+        let event = ReloadEvent {
+            target,
+            success: errors.is_empty(),
+            errors,
+            reloaded_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let listener = self.listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener(&event);
+        }
+    }
+}
+
+fn is_template(path: &Path, dir: &Path) -> bool {
+    path.parent() == Some(dir) && path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// Absolute form of a possibly relative directory, as reported by the watcher
+fn canonical(dir: &Path) -> PathBuf {
+    std::fs::canonicalize(dir).unwrap_or_else(|_| {
+        std::env::current_dir().map(|cwd| cwd.join(dir)).unwrap_or_else(|_| dir.to_path_buf())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::database::Database;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[test]
+    fn test_only_json_files_in_the_templates_dir_are_templates() {
+        let dir = Path::new("/app/templates");
+
+        assert!(is_template(Path::new("/app/templates/blog.json"), dir));
+        assert!(!is_template(Path::new("/app/templates/notes.txt"), dir));
+        assert!(!is_template(Path::new("/app/templates/drafts/blog.json"), dir));
+        assert!(!is_template(Path::new("/app/config.json"), dir));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_templates_keep_the_loaded_set() {
+        let dir = std::env::temp_dir().join(format!("vhq-reload-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("blog.json"), r#"{
+            "name": "Blog post", "prompt_template": "Write a blog post about {content}",
+            "variables": ["content"], "content_type": "article", "description": "Blog"
+        }"#).unwrap();
+
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::database::migrations::migrate(&pool).await.unwrap();
+        let mut config = AppConfig::default();
+        config.ghost.templates_dir = dir.to_string_lossy().to_string();
+        let state = AppState::new(Database::new(pool), config);
+        let reloader = HotReloader::new(state.clone());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = events.clone();
+        reloader.set_listener(move |event| received.lock().unwrap().push(event.clone()));
+
+        let loaded = state.ghost.read().await.templates.clone();
+        assert_eq!(loaded.len(), 1);
+        std::fs::write(dir.join("broken.json"), "{ not json").unwrap();
+        reloader.reload_templates().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(Arc::ptr_eq(&state.ghost.read().await.templates, &loaded));
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].target, ReloadTarget::Templates);
+        assert!(!events[0].success);
+        assert!(events[0].errors[0].contains("broken.json"));
+    }
+}
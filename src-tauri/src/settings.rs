@@ -0,0 +1,257 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::config::{self, AppConfig, GhostConfig, N8nConfig, WebhookAuth, PipelinesConfig, TasksConfig, VitraConfig};
+use crate::database::Database;
+use crate::error::VhqError;
+use crate::health::HealthThresholds;
+
+/// Tauri event carrying the new `Settings` after they changed
+pub const SETTINGS_EVENT: &str = "settings-changed";
+
+/// Stands in for secrets sent to the webview. Saving it back keeps the stored secret.
+pub const REDACTED: &str = "***";
+
+/// App preferences plus the effective agent and service configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub theme: String,
+    pub auto_save: bool,
+    pub notifications: bool,
+    /// Memory budget in MB, 0 for no limit
+    pub max_memory: i64,
+    /// Percent of total CPU time, up to 100
+    pub cpu_limit: f64,
+    pub ghost: GhostConfig,
+    pub vitra: VitraConfig,
+    pub n8n: N8nConfig,
+    pub pipelines: PipelinesConfig,
+    pub tasks: TasksConfig,
+    pub health: HealthThresholds,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+            auto_save: true,
+            notifications: true,
+            max_memory: 0,
+            cpu_limit: 100.0,
+            ghost: GhostConfig::default(),
+            vitra: VitraConfig::default(),
+            n8n: N8nConfig::default(),
+            pipelines: PipelinesConfig::default(),
+            tasks: TasksConfig::default(),
+            health: HealthThresholds::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Preferences from the saved overrides, agent sections from the resolved config
+    fn resolve(overrides: &Value, config: &AppConfig) -> Result<Self> {
+        let mut value = serde_json::to_value(Settings::default())?;
+        config::merge(&mut value, overrides.clone());
+
+        let mut settings: Settings = serde_json::from_value(value)
+            .map_err(|e| VhqError::invalid_input(format!("Invalid settings: {}", e)))?;
+        settings.ghost = config.ghost.clone();
+        settings.vitra = config.vitra.clone();
+        settings.n8n = redact(config.n8n.clone());
+        settings.pipelines = config.pipelines.clone();
+        settings.tasks = config.tasks.clone();
+        settings.health = config.health.clone();
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.theme.trim().is_empty() {
+            return Err(VhqError::invalid_input("theme must not be empty").into());
+        }
+        if self.max_memory < 0 {
+            return Err(VhqError::invalid_input("max_memory cannot be negative").into());
+        }
+        if !(self.cpu_limit > 0.0 && self.cpu_limit <= 100.0) {
+            return Err(VhqError::invalid_input(format!("cpu_limit must be between 0 and 100, got {}", self.cpu_limit)).into());
+        }
+        Ok(())
+    }
+}
+
+/// Hide the API key, callback secret and webhook credentials
+fn redact(mut n8n: N8nConfig) -> N8nConfig {
+    for secret in [&mut n8n.api_key, &mut n8n.callback_secret] {
+        if secret.is_some() {
+            *secret = Some(REDACTED.to_string());
+        }
+    }
+    match &mut n8n.webhook_auth {
+        Some(WebhookAuth::Header { value, .. }) => *value = REDACTED.to_string(),
+        Some(WebhookAuth::Basic { password, .. }) => *password = REDACTED.to_string(),
+        None => {}
+    }
+    n8n
+}
+
+/// Remove redacted values from changes so the saved ones stay in place
+fn drop_redacted(value: &mut Value) {
+    if let Value::Object(fields) = value {
+        fields.retain(|_, field| field.as_str() != Some(REDACTED));
+        fields.values_mut().for_each(drop_redacted);
+    }
+}
+
+type Listener = Arc<dyn Fn(&Settings) + Send + Sync>;
+
+/// Saved settings and the configuration currently in effect. Clones share the same state.
+#[derive(Clone)]
+pub struct SettingsStore {
+    db: Database,
+    config: Arc<watch::Sender<AppConfig>>,
+    listener: Arc<Mutex<Option<Listener>>>,
+}
+
+impl std::fmt::Debug for SettingsStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SettingsStore")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SettingsStore {
+    pub fn new(db: Database, config: AppConfig) -> Self {
+        Self {
+            db,
+            config: Arc::new(watch::Sender::new(config)),
+            listener: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Configuration currently in effect
+    pub fn config(&self) -> AppConfig {
+        self.config.borrow().clone()
+    }
+
+    /// Receiver that sees every configuration change
+    pub fn subscribe(&self) -> watch::Receiver<AppConfig> {
+        self.config.subscribe()
+    }
+
+    pub async fn get(&self) -> Result<Settings> {
+        let overrides = self.overrides().await?;
+        Settings::resolve(&overrides, &self.config())
+    }
+
+    /// Merge `changes` into the saved settings and resolve the configuration again.
+    /// Nothing is saved when the result is invalid.
+    pub async fn update(&self, changes: Value) -> Result<(Settings, AppConfig)> {
+        if !changes.is_object() {
+            return Err(VhqError::invalid_input("Settings must be a JSON object").into());
+        }
+
+        let mut changes = changes;
+        drop_redacted(&mut changes);
+        let mut overrides = self.overrides().await?;
+        config::merge(&mut overrides, changes);
+
+        let config = config::resolve(Some(&overrides)).map_err(VhqError::from)?;
+        let settings = Settings::resolve(&overrides, &config)?;
+
+        self.db.settings().save_overrides(&overrides).await?;
+        self.config.send_replace(config.clone());
+        info!("Settings updated");
+
+        Ok((settings, config))
+    }
+
+    /// Resolve the configuration again after `config.toml` changed.
+    /// The configuration in effect is kept when the file is invalid.
+    pub async fn reload(&self) -> Result<(Settings, AppConfig)> {
+        let overrides = self.overrides().await?;
+
+        let config = config::resolve(Some(&overrides)).map_err(VhqError::from)?;
+        let settings = Settings::resolve(&overrides, &config)?;
+
+        self.config.send_replace(config.clone());
+        info!("Configuration reloaded");
+
+        Ok((settings, config))
+    }
+
+    /// Called after settings changed and were applied, e.g. to emit them as a Tauri event
+    pub fn set_listener(&self, listener: impl Fn(&Settings) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    pub fn notify(&self, settings: &Settings) {
+        let listener = self.listener.lock().unwrap().clone();
+        if let Some(listener) = listener {
+            listener(settings);
+        }
+    }
+
+    async fn overrides(&self) -> Result<Value> {
+        Ok(self.db.settings().load_overrides().await?.unwrap_or_else(|| Value::Object(Default::default())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferences_come_from_overrides_and_sections_from_config() {
+        let mut config = AppConfig::default();
+        config.vitra.whisper_model = "small".to_string();
+        config.pipelines.triggers = true;
+        let overrides = serde_json::json!({
+            "theme": "light",
+            "vitra": { "whisper_model": "tiny" },
+            "pipelines": { "triggers": false },
+        });
+
+        let settings = Settings::resolve(&overrides, &config).unwrap();
+
+        assert_eq!(settings.theme, "light");
+        assert!(settings.auto_save);
+        assert_eq!(settings.vitra.whisper_model, "small");
+        assert!(settings.pipelines.triggers);
+    }
+
+    #[test]
+    fn test_secrets_are_redacted_and_kept_when_sent_back() {
+        let mut config = AppConfig::default();
+        config.n8n.api_key = Some("n8n_api_123".to_string());
+        config.n8n.webhook_auth = Some(WebhookAuth::Basic { username: "vhq".to_string(), password: "hunter2".to_string() });
+
+        let settings = Settings::resolve(&serde_json::json!({}), &config).unwrap();
+        assert_eq!(settings.n8n.api_key.as_deref(), Some(REDACTED));
+        assert_eq!(settings.n8n.callback_secret, None);
+        assert!(!serde_json::to_string(&settings).unwrap().contains("hunter2"));
+
+        let mut changes = serde_json::to_value(&settings).unwrap();
+        changes["n8n"]["url"] = serde_json::json!("http://n8n.local:5678");
+        drop_redacted(&mut changes);
+        assert!(changes["n8n"].get("api_key").is_none());
+        assert!(changes["n8n"]["webhook_auth"].get("password").is_none());
+        assert_eq!(changes["n8n"]["webhook_auth"]["username"], "vhq");
+        assert_eq!(changes["n8n"]["url"], "http://n8n.local:5678");
+    }
+
+    #[test]
+    fn test_rejects_invalid_preferences() {
+        let config = AppConfig::default();
+
+        assert!(Settings::resolve(&serde_json::json!({ "cpu_limit": 150.0 }), &config).is_err());
+        assert!(Settings::resolve(&serde_json::json!({ "colour": "red" }), &config).is_err());
+    }
+}
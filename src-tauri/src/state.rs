@@ -0,0 +1,64 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::alerts::AlertEngine;
+use crate::config::AppConfig;
+use crate::metrics;
+use crate::settings::SettingsStore;
+use crate::system::WorkflowManager;
+use crate::database::Database;
+use crate::agents::{vitra::VitraAgent, ghost::GhostAgent, ceo::CeoAgent, dispatcher::TaskDispatcher, scheduler::TaskScheduler};
+
+/// Application-wide state registered with `tauri::Builder::manage`.
+///
+/// Holds a single long-lived instance of every agent so that status,
+/// queues and settings survive across command invocations. Clones share the same instances.
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Database,
+    pub vitra: Arc<RwLock<VitraAgent>>,
+    pub ghost: Arc<RwLock<GhostAgent>>,
+    pub ceo: Arc<RwLock<CeoAgent>>,
+    pub scheduler: TaskScheduler,
+    pub alerts: AlertEngine,
+    pub settings: SettingsStore,
+    pub workflows: Arc<WorkflowManager>,
+}
+
+impl AppState {
+    pub fn new(db: Database, config: AppConfig) -> Self {
+        info!("Creating shared agent state");
+
+        let vitra = Arc::new(RwLock::new(VitraAgent::with_config(&config.vitra)));
+        let ghost = Arc::new(RwLock::new(GhostAgent::with_config(&config.ghost)));
+        
+        // CEO executes queued tasks on the same instances the commands use
+        let dispatcher = TaskDispatcher::new(vitra.clone(), ghost.clone());
+        let ceo = Arc::new(RwLock::new(CeoAgent::with_dispatcher(db.clone(), dispatcher)));
+
+        let scheduler = TaskScheduler::new(db.clone());
+        let alerts = AlertEngine::new(db.clone());
+        let workflows = Arc::new(WorkflowManager::with_config(&config.n8n));
+        let settings = SettingsStore::new(db.clone(), config);
+
+        Self { db, vitra, ghost, ceo, scheduler, alerts, settings, workflows }
+    }
+
+    /// Push configuration into the running agents without restarting them
+    pub async fn apply_config(&self, config: &AppConfig) -> Result<()> {
+        self.ghost.write().await.apply_config(&config.ghost);
+        self.vitra.write().await.apply_config(&config.vitra);
+        self.workflows.apply_config(&config.n8n);
+        self.alerts.set_thresholds(config.health.clone());
+        self.alerts.set_monitored_dirs(metrics::monitored_dirs(&config.output_dirs()));
+
+        let mut ceo = self.ceo.write().await;
+        ceo.apply_config(config);
+        ceo.configure_agent("vitra_lag", config.vitra.max_concurrent_tasks, config.vitra.task_timeout_secs)?;
+        ceo.configure_agent("ghost_lag", config.ghost.max_concurrent_tasks, config.ghost.task_timeout_secs)?;
+
+        Ok(())
+    }
+}
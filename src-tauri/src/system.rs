@@ -6,7 +6,17 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
 use crate::agents::{Agent, AgentManager};
-use crate::database;
+use crate::config::{self, N8nConfig};
+use crate::database::{self, Database};
+use crate::database::workflows::{WorkflowFilter, WorkflowRecord, WorkflowRun};
+use crate::health::{HealthChecker, HealthHistory, HealthReport};
+use crate::metrics::{self, SystemSampler};
+use crate::error::VhqError;
+use crate::n8n::{self, N8nClient, SyncReport, WebhookResponse};
+use crate::pipeline::{self, CeoSteps, PipelineEngine, PipelineFile, PipelineRegistry};
+use crate::state::AppState;
+
+pub use crate::health::HealthStatus;
 
 /// System coordinator for managing all VHQ_LAG components
 pub struct SystemCoordinator {
@@ -38,13 +48,19 @@ impl SystemCoordinator {
         info!("Starting VHQ_LAG system...");
         
         // Initialize database
-        database::init().await?;
+        let db = database::init().await?;
+        let config = config::load(&db).await?;
         
         // Start resource monitoring
-        self.resource_manager.start_monitoring().await?;
+        self.resource_manager.start_monitoring(metrics::monitored_dirs(&config.output_dirs())).await?;
         
         // Start health monitoring
-        self.health_monitor.start().await?;
+        self.health_monitor.start(HealthChecker {
+            db,
+            ollama_url: config.ghost.ollama_url.clone(),
+            output_dirs: config.output_dirs(),
+            thresholds: config.health.clone(),
+        }).await?;
         
         // Start workflow manager
         self.workflow_manager.start().await?;
@@ -100,9 +116,9 @@ impl SystemCoordinator {
         info!("Stopping all agents...");
         
         let mut agent_manager = self.agent_manager.write().await;
-        let agents = agent_manager.get_all_agents();
+        let agents: Vec<String> = agent_manager.get_all_agents().into_iter().map(String::from).collect();
         
-        for agent_name in agents {
+        for agent_name in &agents {
             if let Err(e) = agent_manager.stop_agent(agent_name) {
                 warn!("Failed to stop agent {}: {}", agent_name, e);
             }
@@ -123,6 +139,7 @@ impl SystemCoordinator {
             agents: agent_manager.get_all_agents().len(),
             resources: resource_status,
             health: health_status,
+            health_history: self.health_monitor.get_history(),
             workflows: workflow_status,
             uptime: chrono::Utc::now(),
         }
@@ -133,28 +150,35 @@ impl SystemCoordinator {
 pub struct HealthMonitor {
     status: Arc<RwLock<HealthStatus>>,
     last_check: Arc<RwLock<DateTime<Utc>>>,
+    history: HealthHistory,
 }
 
 impl HealthMonitor {
     pub fn new() -> Self {
         Self {
-            status: Arc::new(RwLock::new(HealthStatus::Healthy)),
+            status: Arc::new(RwLock::new(HealthStatus::Unknown)),
             last_check: Arc::new(RwLock::new(Utc::now())),
+            history: HealthHistory::default(),
         }
     }
 
-    pub async fn start(&self) -> Result<()> {
+    pub async fn start(&self, checker: HealthChecker) -> Result<()> {
         info!("Starting health monitor");
         
         let status = self.status.clone();
         let last_check = self.last_check.clone();
+        let history = self.history.clone();
         
         tokio::spawn(async move {
             loop {
                 // Update health status
-                let new_status = Self::check_system_health().await;
-                *status.write().await = new_status;
+                let report = checker.run().await;
+                if report.status != HealthStatus::Healthy {
+                    warn!("System health is {:?}", report.status);
+                }
+                *status.write().await = report.status;
                 *last_check.write().await = Utc::now();
+                history.push(report);
                 
                 tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
             }
@@ -169,13 +193,12 @@ impl HealthMonitor {
     }
 
     pub async fn get_status(&self) -> HealthStatus {
-        self.status.read().await.clone()
+        *self.status.read().await
     }
 
-    async fn check_system_health() -> HealthStatus {
-        // TODO: Implement actual health checks
-        // For now, return healthy status
-        HealthStatus::Healthy
+    /// Recent probe results, oldest first
+    pub fn get_history(&self) -> Vec<HealthReport> {
+        self.history.reports()
     }
 }
 
@@ -195,7 +218,7 @@ impl ResourceManager {
         }
     }
 
-    pub async fn start_monitoring(&self) -> Result<()> {
+    pub async fn start_monitoring(&self, dirs: Vec<std::path::PathBuf>) -> Result<()> {
         info!("Starting resource monitoring");
         
         let cpu_usage = self.cpu_usage.clone();
@@ -203,11 +226,13 @@ impl ResourceManager {
         let disk_usage = self.disk_usage.clone();
         
         tokio::spawn(async move {
+            let mut sampler = SystemSampler::new(dirs);
             loop {
                 // Update resource usage
-                *cpu_usage.write().await = Self::get_cpu_usage().await;
-                *memory_usage.write().await = Self::get_memory_usage().await;
-                *disk_usage.write().await = Self::get_disk_usage().await;
+                let snapshot = sampler.sample();
+                *cpu_usage.write().await = snapshot.cpu_percent;
+                *memory_usage.write().await = snapshot.memory.used_bytes;
+                *disk_usage.write().await = snapshot.disk_used_bytes();
                 
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
             }
@@ -228,46 +253,134 @@ impl ResourceManager {
             disk_usage: *self.disk_usage.read().await,
         }
     }
-
-    async fn get_cpu_usage() -> f64 {
-        // TODO: Implement actual CPU usage monitoring
-        0.0
-    }
-
-    async fn get_memory_usage() -> u64 {
-        // TODO: Implement actual memory usage monitoring
-        0
-    }
-
-    async fn get_disk_usage() -> u64 {
-        // TODO: Implement actual disk usage monitoring
-        0
-    }
 }
 
-/// Workflow management system for n8n integration
+/// Workflow management for n8n and the native pipelines
 pub struct WorkflowManager {
     workflows: Arc<RwLock<Vec<WorkflowStatus>>>,
-    n8n_url: String,
+    config: std::sync::RwLock<N8nConfig>,
+    pipelines: PipelineRegistry,
 }
 
 impl WorkflowManager {
     pub fn new() -> Self {
+        Self::with_config(&N8nConfig::default())
+    }
+
+    pub fn with_config(config: &N8nConfig) -> Self {
         Self {
             workflows: Arc::new(RwLock::new(Vec::new())),
-            n8n_url: "http://localhost:5678".to_string(),
+            config: std::sync::RwLock::new(config.clone()),
+            pipelines: PipelineRegistry::default(),
         }
     }
 
+    /// Use changed n8n settings from the next request on
+    pub fn apply_config(&self, config: &N8nConfig) {
+        *self.config.write().unwrap() = config.clone();
+    }
+
+    fn client(&self) -> Result<N8nClient, VhqError> {
+        N8nClient::new(&self.config.read().unwrap())
+    }
+
     pub async fn start(&self) -> Result<()> {
         info!("Starting workflow manager");
         
-        // TODO: Connect to n8n instance
-        // TODO: Load existing workflows
+        let config = self.config.read().unwrap().clone();
+        if !config.sync_on_startup {
+            return Ok(());
+        }
+        if config.api_key.is_none() {
+            info!("n8n.api_key is not set; bundled workflows are not synced");
+            return Ok(());
+        }
         
+        let report = self.sync(false).await?;
+        let failed = report.workflows.iter().filter(|w| !w.errors.is_empty()).count();
+        if failed > 0 {
+            warn!("{} bundled n8n workflows could not be synced", failed);
+        }
+        
+        Ok(())
+    }
+
+    /// Deploy changed workflows from `n8n.workflows_dir`, or only report the differences
+    pub async fn sync(&self, dry_run: bool) -> Result<SyncReport> {
+        let client = self.client()?;
+        let dir = self.config.read().unwrap().workflows_dir.clone();
+        let report = client.sync_workflows(std::path::Path::new(&dir), dry_run).await?;
+        
+        if !dry_run {
+            self.load_workflows(&client).await?;
+        }
+        Ok(report)
+    }
+
+    /// Track every workflow n8n knows, keeping the counters of known ones
+    async fn load_workflows(&self, client: &N8nClient) -> Result<()> {
+        let deployed = client.list_workflows().await?;
+        
+        let mut workflows = self.workflows.write().await;
+        for workflow in deployed {
+            let status = if workflow.active { "active" } else { "inactive" }.to_string();
+            match workflows.iter_mut().find(|w| w.name == workflow.name) {
+                Some(existing) => existing.status = status,
+                None => workflows.push(WorkflowStatus {
+                    name: workflow.name,
+                    status,
+                    last_execution: None,
+                    execution_count: 0,
+                }),
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Load `pipelines.dir` and start the triggers of pipelines that were not loaded before.
+    /// Runs at startup and again after an imported pipeline was saved.
+    pub async fn start_pipelines(&self, state: &AppState) -> Result<()> {
+        let config = state.settings.config();
+        let variables = n8n::sync::resolve_variables(&config.n8n.variables, |name| std::env::var(name).ok());
+        let loaded: std::collections::HashSet<String> = self.pipelines.list().into_iter()
+            .filter_map(|file| file.pipeline.map(|pipeline| pipeline.name))
+            .collect();
+        let pipelines = self.pipelines.load(std::path::Path::new(&config.pipelines.dir), &variables)?;
+        
+        if config.pipelines.triggers {
+            let added: Vec<_> = pipelines.into_iter().filter(|pipeline| !loaded.contains(&pipeline.name)).collect();
+            pipeline::triggers::start(state, &added);
+        }
         Ok(())
     }
 
+    pub fn list_pipelines(&self) -> Vec<PipelineFile> {
+        self.pipelines.list()
+    }
+
+    /// Called with a native run whenever it is stored
+    pub fn set_pipeline_listener(&self, listener: impl Fn(&WorkflowRecord) + Send + Sync + 'static) {
+        self.pipelines.set_listener(listener);
+    }
+
+    /// Run a loaded pipeline to its end; `trigger` is what its expressions see as `trigger`
+    pub async fn run_pipeline(&self, state: &AppState, name: &str, trigger: serde_json::Value) -> Result<WorkflowRun, VhqError> {
+        let pipeline = self.pipelines.get(name)
+            .ok_or_else(|| VhqError::NotFound { entity: "pipeline", id: name.to_string() })?;
+        
+        let agents = Arc::new(CeoSteps::new(state.ceo.clone(), state.db.clone()));
+        let engine = PipelineEngine::new(state.db.clone(), agents)
+            .with_step_timeout(std::time::Duration::from_secs(state.settings.config().pipelines.step_timeout_secs))
+            .with_listener(self.pipelines.listener());
+        self.record_execution(name, "running", true).await;
+        let run = engine.run(&pipeline, trigger).await;
+        
+        let status = run.as_ref().map(|run| run.record.status.as_str()).unwrap_or("failed");
+        self.record_execution(name, status, false).await;
+        Ok(run?)
+    }
+
     pub async fn stop(&self) -> Result<()> {
         info!("Stopping workflow manager");
         Ok(())
@@ -277,14 +390,74 @@ impl WorkflowManager {
         self.workflows.read().await.clone()
     }
 
-    pub async fn trigger_workflow(&self, workflow_name: &str, payload: serde_json::Value) -> Result<()> {
+    pub async fn trigger_workflow(&self, workflow_name: &str, payload: serde_json::Value) -> Result<WebhookResponse> {
         info!("Triggering workflow: {}", workflow_name);
         
-        // TODO: Implement n8n workflow triggering
-        // This would make an HTTP request to n8n's webhook endpoint
+        let result = self.client()?.trigger(workflow_name, &payload).await;
         
-        Ok(())
+        // The execution itself is counted when the workflow reports its start
+        let mut workflows = self.workflows.write().await;
+        let workflow = status_entry(&mut workflows, workflow_name);
+        workflow.status = if result.is_ok() { "triggered" } else { "failed" }.to_string();
+        workflow.last_execution = Some(Utc::now());
+        
+        Ok(result?)
     }
+
+    /// Track an execution reported by n8n; `started` counts it as a new execution
+    pub async fn record_execution(&self, workflow_name: &str, status: &str, started: bool) {
+        let mut workflows = self.workflows.write().await;
+        let workflow = status_entry(&mut workflows, workflow_name);
+        workflow.status = status.to_string();
+        if started {
+            workflow.last_execution = Some(Utc::now());
+            workflow.execution_count += 1;
+        }
+    }
+
+    /// Tracked workflows and their stored runs matching `filter`
+    pub async fn history(&self, db: &Database, filter: &WorkflowFilter) -> Result<WorkflowHistory, VhqError> {
+        let filter = WorkflowFilter {
+            since: filter.since.as_deref().map(|since| timestamp(since, false)).transpose()?,
+            until: filter.until.as_deref().map(|until| timestamp(until, true)).transpose()?,
+            ..filter.clone()
+        };
+        let runs = db.workflows().history(&filter).await?;
+
+        let mut workflows = self.get_status().await;
+        if let Some(name) = &filter.name {
+            workflows.retain(|workflow| &workflow.name == name);
+        }
+        Ok(WorkflowHistory { workflows, runs })
+    }
+}
+
+/// A filter bound as stored in the `workflows` table. A plain date is a whole day,
+/// so as an upper bound it ends at the following midnight.
+fn timestamp(value: &str, end_of_day: bool) -> Result<String, VhqError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc).to_rfc3339());
+    }
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| VhqError::invalid_input(format!("Expected a date or RFC 3339 timestamp, got {}", value)))?;
+    let date = if end_of_day { date.succ_opt().unwrap_or(date) } else { date };
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().to_rfc3339())
+}
+
+fn status_entry<'a>(workflows: &'a mut Vec<WorkflowStatus>, name: &str) -> &'a mut WorkflowStatus {
+    let index = match workflows.iter().position(|w| w.name == name) {
+        Some(index) => index,
+        None => {
+            workflows.push(WorkflowStatus {
+                name: name.to_string(),
+                status: "idle".to_string(),
+                last_execution: None,
+                execution_count: 0,
+            });
+            workflows.len() - 1
+        }
+    };
+    &mut workflows[index]
 }
 
 // Data structures for system status
@@ -294,29 +467,33 @@ pub struct SystemStatus {
     pub agents: usize,
     pub resources: ResourceStatus,
     pub health: HealthStatus,
+    /// Recent probe results, oldest first
+    pub health_history: Vec<HealthReport>,
     pub workflows: Vec<WorkflowStatus>,
     pub uptime: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceStatus {
+    /// Percent of total CPU time
     pub cpu_usage: f64,
+    /// Used memory in bytes
     pub memory_usage: u64,
+    /// Used bytes on the filesystems holding app data and agent outputs
     pub disk_usage: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum HealthStatus {
-    Healthy,
-    Warning,
-    Critical,
-    Unknown,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowStatus {
     pub name: String,
     pub status: String,
     pub last_execution: Option<DateTime<Utc>>,
     pub execution_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowHistory {
+    pub workflows: Vec<WorkflowStatus>,
+    /// Newest first
+    pub runs: Vec<WorkflowRun>,
 } 
\ No newline at end of file
@@ -137,7 +137,7 @@ impl TestSuite {
 
     /// Test CEO_LAG agent
     async fn test_ceo_agent(&self) -> Result<()> {
-        let agent = CeoAgent::new();
+        let agent = CeoAgent::new(database::init().await?);
         
         // Test agent initialization
         let status = agent.get_status();
@@ -153,7 +153,7 @@ impl TestSuite {
         });
         
         // This would normally manage tasks, but we'll just test the interface
-        let _result = agent.process_file(test_task, options)?;
+        let _result = agent.process_file(test_task, options).await?;
         
         Ok(())
     }